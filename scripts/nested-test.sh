@@ -0,0 +1,107 @@
+#!/bin/sh
+# End-to-end check of rowlink against a headless, nested sway. Starts sway with the
+# headless wlroots backend and a rowlink daemon with a fixed config, then drives rowlink
+# over IPC and with virtual key presses and checks where a fullscreen `wev`, started
+# afresh for each step, saw the pointer buttons land.
+#
+# Needs sway, wev and wtype on PATH. Usage: scripts/nested-test.sh [path/to/rowlink]
+# `cargo test -- --ignored` runs it against the test build.
+set -eu
+
+ROWLINK=$(realpath "${1:-target/debug/rowlink}")
+WORK=$(mktemp -d)
+export XDG_RUNTIME_DIR="$WORK/runtime" XDG_CONFIG_HOME="$WORK/config"
+export WLR_BACKENDS=headless WLR_LIBINPUT_NO_DEVICES=1 WLR_RENDERER=pixman
+mkdir -m 700 "$XDG_RUNTIME_DIR"
+mkdir -p "$XDG_CONFIG_HOME/rowlink"
+
+cleanup() {
+    kill $(jobs -p) 2>/dev/null || true
+    rm -rf "$WORK"
+}
+trap cleanup EXIT
+
+cat > "$WORK/sway.conf" <<SWAY
+output HEADLESS-1 resolution 1280x720 position 0 0
+for_window [app_id="wev"] fullscreen enable
+default_border none
+SWAY
+
+# A 2x2 main grid with the default 8x3 sub-grid, so targets are easy to compute: cell AA
+# spans 640x360 and sub-cell J (row 1, column 4) is centered on (359.5, 180).
+cat > "$XDG_CONFIG_HOME/rowlink/config.yaml" <<CONFIG
+screen_width: 1280
+screen_height: 720
+main_grid_size: 2
+respect_exclusive_zones: false
+idle_inhibit: false
+restore_focus: false
+hotspots:
+  - { name: probe, x: 100, y: 200 }
+CONFIG
+
+wait_for() {
+    tries=50
+    until eval "$1"; do
+        tries=$((tries - 1))
+        [ "$tries" -gt 0 ] || { echo "timed out waiting for: $1" >&2; exit 1; }
+        sleep 0.1
+    done
+}
+
+sway -c "$WORK/sway.conf" >"$WORK/sway.log" 2>&1 &
+wait_for '[ -S "$XDG_RUNTIME_DIR/wayland-1" ]'
+export WAYLAND_DISPLAY=wayland-1 SWAYSOCK=$(ls "$XDG_RUNTIME_DIR"/sway-ipc.*.sock)
+
+"$ROWLINK" >"$WORK/rowlink.log" 2>&1 &
+ROWLINK_PID=$!
+wait_for '[ -S "$XDG_RUNTIME_DIR/rowlink.sock" ]'
+
+FAILED=0
+WEV_PID=
+
+# Replaces the click target with a new wev logging to its own file, and waits for sway to
+# map its window, by pid as the old one may not be gone yet. There are no input devices,
+# so the log stays empty until rowlink's virtual pointer enters the window.
+start_wev() {
+    [ -z "$WEV_PID" ] || { kill "$WEV_PID"; wait "$WEV_PID" 2>/dev/null || true; }
+    WEV_LOG="$WORK/wev-$1.log"
+    wev -f wl_pointer >"$WEV_LOG" 2>&1 &
+    WEV_PID=$!
+    wait_for 'swaymsg -t get_tree -r | grep -q "\"pid\": $WEV_PID,"'
+}
+
+# Checks that the newest left-button press in the step's wev log happened at ($1, $2),
+# give or take a pixel for rounding.
+expect_click() {
+    sleep 0.5
+    awk -v want_x="$1" -v want_y="$2" -v name="$3" '
+        /(enter|motion):/ {
+            split($0, parts, "x, y: "); split(parts[2], xy, ", "); x = xy[1]; y = xy[2]
+        }
+        /button:.*\(left\), state: 1/ { pressed = 1; px = x; py = y }
+        END {
+            if (!pressed) { print "FAIL " name ": no click"; exit 1 }
+            if ((px - want_x) ^ 2 > 1 || (py - want_y) ^ 2 > 1) {
+                printf "FAIL %s: clicked at %s,%s, want %s,%s\n", name, px, py, want_x, want_y
+                exit 1
+            }
+            print "ok   " name
+        }' "$WEV_LOG" || FAILED=1
+}
+
+start_wev pick
+"$ROWLINK" pick "hotspot: probe" >/dev/null
+expect_click 100 200 "pick over IPC"
+
+start_wev grid
+kill -USR1 "$ROWLINK_PID"
+sleep 0.3
+wtype a a j
+expect_click 359.5 180 "grid and sub-grid selection"
+
+if [ "$FAILED" -ne 0 ]; then
+    echo "--- rowlink log ---" >&2
+    cat "$WORK/rowlink.log" >&2
+fi
+exit "$FAILED"
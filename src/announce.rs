@@ -0,0 +1,140 @@
+use crate::cfg;
+use accesskit::{
+    ActionHandler, ActionRequest, ActivationHandler, DeactivationHandler, Live, Node, NodeId, Role,
+    Tree, TreeId, TreeUpdate,
+};
+use accesskit_unix::Adapter;
+use serde_json::Value;
+use std::sync::Mutex;
+
+// --- Speech Announcements ---
+// Speaks state changes aloud: "grid shown", "cell F G", "clicked at 840, 600". They go out
+// as AT-SPI announcements, so the screen reader speaks them in the user's voice, shows them
+// on a braille display and keeps them in its history. The overlay has no accessible tree
+// of its own, so rowlink registers a minimal one: a window holding a single live-region
+// label. Each announcement swaps in a new label, which the screen reader reads out; a
+// fresh node rather than a renamed one, so that saying the same text twice is heard twice.
+// Without a running screen reader the tree is never requested and nothing is sent.
+
+const WINDOW: NodeId = NodeId(0);
+
+/// The tree's handle, and the id of the label last announced.
+static ADAPTER: Mutex<Option<(Adapter, u64)>> = Mutex::new(None);
+
+struct Overlay;
+
+impl ActivationHandler for Overlay {
+    fn request_initial_tree(&mut self) -> Option<TreeUpdate> {
+        Some(update(window(None), Vec::new()))
+    }
+}
+
+impl ActionHandler for Overlay {
+    fn do_action(&mut self, _: ActionRequest) {}
+}
+
+impl DeactivationHandler for Overlay {
+    fn deactivate_accessibility(&mut self) {}
+}
+
+fn window(label: Option<NodeId>) -> Node {
+    let mut node = Node::new(Role::Window);
+    node.set_label("rowlink");
+    node.set_children(label.into_iter().collect::<Vec<_>>());
+    node
+}
+
+fn update(window: Node, mut nodes: Vec<(NodeId, Node)>) -> TreeUpdate {
+    nodes.insert(0, (WINDOW, window));
+    let mut tree = Tree::new(WINDOW);
+    tree.toolkit_name = Some("rowlink".to_string());
+    TreeUpdate {
+        nodes,
+        tree: Some(tree),
+        tree_id: TreeId::ROOT,
+        focus: WINDOW,
+    }
+}
+
+/// Announces an event broadcast by `ipc::emit`.
+pub fn event(event: &Value) {
+    if !cfg().announce {
+        return;
+    }
+    let number = |key: &str| event[key].as_f64().unwrap_or_default().round();
+    let text = match event["event"].as_str() {
+        Some("shown") => {
+            // The overlay holds the keyboard, so the screen reader treats it as focused.
+            with_adapter(|adapter, _| adapter.update_window_focus_state(true));
+            format!("{} shown", mode_name(&event["mode"]))
+        }
+        Some("zoomed") if event["depth"].as_u64().is_some_and(|depth| depth > 1) => {
+            format!("zoomed in, level {}", event["depth"])
+        }
+        Some("zoomed") => {
+            let row = event["row"].as_i64().unwrap_or_default() as i32;
+            let col = event["col"].as_i64().unwrap_or_default() as i32;
+            match cfg().label_table.get(row, col) {
+                Some(label) => format!("cell {}", spell(label)),
+                None => format!("row {}, column {}", row + 1, col + 1),
+            }
+        }
+        Some("clicked") => {
+            let verb = match event["count"].as_u64() {
+                Some(2) => "double clicked",
+                _ => "clicked",
+            };
+            format!("{} at {}, {}", verb, number("x"), number("y"))
+        }
+        Some("hidden") => "grid hidden".to_string(),
+        _ => return,
+    };
+    say(text);
+    if event["event"] == "hidden" {
+        with_adapter(|adapter, _| adapter.update_window_focus_state(false));
+    }
+}
+
+/// Announces a mode switch within the open overlay.
+pub fn mode(name: &str) {
+    if cfg().announce {
+        say(format!("{} mode", name.replace('_', " ")));
+    }
+}
+
+fn mode_name(mode: &Value) -> String {
+    match mode.as_str() {
+        Some("grid") | None => "grid".to_string(),
+        Some(mode) => format!("{} mode", mode.replace('_', " ")),
+    }
+}
+
+/// `FG` as "F G", so that labels are read letter by letter rather than as words.
+fn spell(label: &str) -> String {
+    label
+        .chars()
+        .map(String::from)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Runs `f` on the tree's handle and the last label's id, registering the tree first.
+fn with_adapter(f: impl FnOnce(&mut Adapter, &mut u64)) {
+    let mut adapter = ADAPTER.lock().unwrap_or_else(|e| e.into_inner());
+    let (adapter, last) =
+        adapter.get_or_insert_with(|| (Adapter::new(Overlay, Overlay, Overlay), 0));
+    f(adapter, last);
+}
+
+fn say(text: String) {
+    with_adapter(|adapter, last| {
+        *last += 1;
+        let id = NodeId(*last);
+        adapter.update_if_active(|| {
+            let mut label = Node::new(Role::Label);
+            label.set_value(text);
+            label.set_live(Live::Polite);
+            update(window(Some(id)), vec![(id, label)])
+        });
+    });
+}
@@ -0,0 +1,390 @@
+use iced::keyboard;
+
+// --- Key Bindings ---
+// Config lines of the form `bind "<keys>" <action>`, compiled once at load time.
+// <keys> is a space-separated sequence of combos such as `ctrl+enter` or `g g`.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    LeftClick,
+    RightClick,
+    MiddleClick,
+    /// Side buttons (8 and 9), for back/forward navigation in browsers and editors.
+    BackClick,
+    ForwardClick,
+    DoubleClick,
+    TripleClick,
+    /// Click and paste, see `middle_click_paste_apps`.
+    Paste,
+    GotoTopLeft,
+    GotoTopRight,
+    GotoBottomLeft,
+    GotoBottomRight,
+    GotoCenter,
+    ScrollUp,
+    ScrollDown,
+    ScrollLeft,
+    ScrollRight,
+    RepeatLast,
+    Close,
+}
+
+impl Action {
+    fn parse(name: &str) -> Option<Self> {
+        Some(match name {
+            "left_click" => Self::LeftClick,
+            "right_click" => Self::RightClick,
+            "middle_click" => Self::MiddleClick,
+            "back_click" => Self::BackClick,
+            "forward_click" => Self::ForwardClick,
+            "double_click" => Self::DoubleClick,
+            "triple_click" => Self::TripleClick,
+            "paste" => Self::Paste,
+            "goto_top_left" => Self::GotoTopLeft,
+            "goto_top_right" => Self::GotoTopRight,
+            "goto_bottom_left" => Self::GotoBottomLeft,
+            "goto_bottom_right" => Self::GotoBottomRight,
+            "goto_center" => Self::GotoCenter,
+            "scroll_up" => Self::ScrollUp,
+            "scroll_down" => Self::ScrollDown,
+            "scroll_left" => Self::ScrollLeft,
+            "scroll_right" => Self::ScrollRight,
+            "repeat_last" => Self::RepeatLast,
+            "close" => Self::Close,
+            _ => return None,
+        })
+    }
+}
+
+/// One key with the exact modifiers that must be held.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Combo {
+    ctrl: bool,
+    alt: bool,
+    shift: bool,
+    logo: bool,
+    /// Lowercased character, or the lowercased name of a named key (`enter`, `tab`, ...).
+    key: String,
+}
+
+impl Combo {
+    fn parse(input: &str) -> Option<Self> {
+        let mut combo = Combo {
+            ctrl: false,
+            alt: false,
+            shift: false,
+            logo: false,
+            key: String::new(),
+        };
+        let mut parts: Vec<&str> = input.split('+').collect();
+        // `+` and `ctrl++` bind the plus key itself.
+        if input == "+" || input.ends_with("++") {
+            parts.truncate(parts.len().saturating_sub(2));
+            parts.push("+");
+        }
+        let (key, modifiers) = parts.split_last()?;
+        for modifier in modifiers {
+            match modifier.to_lowercase().as_str() {
+                "ctrl" | "control" => combo.ctrl = true,
+                "alt" => combo.alt = true,
+                "shift" => combo.shift = true,
+                "super" | "logo" => combo.logo = true,
+                _ => return None,
+            }
+        }
+        if !is_key_name(key) {
+            return None;
+        }
+        combo.key = key.to_lowercase();
+        Some(combo)
+    }
+
+    fn matches(&self, key: &keyboard::Key, modifiers: keyboard::Modifiers) -> bool {
+        let name = match key {
+            keyboard::Key::Character(c) => c.to_lowercase(),
+            keyboard::Key::Named(named) => format!("{:?}", named).to_lowercase(),
+            keyboard::Key::Unidentified => return false,
+        };
+        self.key == name
+            && self.ctrl == modifiers.control()
+            && self.alt == modifiers.alt()
+            && self.shift == modifiers.shift()
+            && self.logo == modifiers.logo()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Binding {
+    sequence: Vec<Combo>,
+    action: Action,
+}
+
+/// Parses one `bind "<keys>" <action>` line.
+pub fn parse(line: &str) -> Result<Binding, String> {
+    let rest = line
+        .trim()
+        .strip_prefix("bind")
+        .ok_or("expected `bind`")?
+        .trim_start();
+    let rest = rest
+        .strip_prefix('"')
+        .ok_or("expected a quoted key sequence")?;
+    let (keys, action) = rest.split_once('"').ok_or("unterminated key sequence")?;
+    let action = action.trim();
+    let sequence = keys
+        .split_whitespace()
+        .map(|combo| Combo::parse(combo).ok_or(format!("invalid key combo {:?}", combo)))
+        .collect::<Result<Vec<_>, _>>()?;
+    if sequence.is_empty() {
+        return Err("empty key sequence".to_string());
+    }
+    let action = Action::parse(action).ok_or(format!("unknown action {:?}", action))?;
+    Ok(Binding { sequence, action })
+}
+
+impl Binding {
+    /// Whether this binding makes `other` unreachable: it completes on the same keys, or
+    /// on keys `other` only starts with.
+    pub fn shadows(&self, other: &Binding) -> bool {
+        other.sequence.starts_with(&self.sequence)
+    }
+}
+
+/// Compiles every binding line, skipping the invalid ones; config validation reports
+/// them.
+pub fn compile(lines: &[String]) -> Vec<Binding> {
+    lines.iter().filter_map(|line| parse(line).ok()).collect()
+}
+
+// Named keys as iced reports them, plus F1-F35.
+const NAMED_KEYS: &[&str] = &[
+    "Enter",
+    "Tab",
+    "Space",
+    "Backspace",
+    "Escape",
+    "Delete",
+    "Insert",
+    "Home",
+    "End",
+    "PageUp",
+    "PageDown",
+    "ArrowUp",
+    "ArrowDown",
+    "ArrowLeft",
+    "ArrowRight",
+    "CapsLock",
+    "Shift",
+    "Control",
+    "Alt",
+    "Super",
+    "Meta",
+    "ContextMenu",
+    "PrintScreen",
+    "Pause",
+    "ScrollLock",
+    "NumLock",
+];
+
+/// Whether `name` is a single character or a named key, ignoring case.
+pub fn is_key_name(name: &str) -> bool {
+    let function_key = name
+        .strip_prefix(['F', 'f'])
+        .and_then(|n| n.parse::<u8>().ok())
+        .is_some_and(|n| (1..=35).contains(&n));
+    name.chars().count() == 1
+        || function_key
+        || NAMED_KEYS
+            .iter()
+            .any(|named| named.eq_ignore_ascii_case(name))
+}
+
+pub enum Lookup {
+    /// The keys complete this binding.
+    Complete(Action),
+    /// The keys start at least one binding; wait for more.
+    Prefix,
+    NoMatch,
+}
+
+/// Matches the keys pressed so far against the bindings.
+pub fn lookup(bindings: &[Binding], keys: &[(keyboard::Key, keyboard::Modifiers)]) -> Lookup {
+    let mut prefix = false;
+    for binding in bindings {
+        if binding.sequence.len() < keys.len() {
+            continue;
+        }
+        let matched = binding
+            .sequence
+            .iter()
+            .zip(keys)
+            .all(|(combo, (key, modifiers))| combo.matches(key, *modifiers));
+        if !matched {
+            continue;
+        }
+        if binding.sequence.len() == keys.len() {
+            return Lookup::Complete(binding.action);
+        }
+        prefix = true;
+    }
+    if prefix {
+        Lookup::Prefix
+    } else {
+        Lookup::NoMatch
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use keyboard::key::Named;
+
+    fn combo(key: &str, ctrl: bool, alt: bool, shift: bool, logo: bool) -> Combo {
+        Combo {
+            ctrl,
+            alt,
+            shift,
+            logo,
+            key: key.to_string(),
+        }
+    }
+
+    fn char_key(c: &str) -> keyboard::Key {
+        keyboard::Key::Character(c.into())
+    }
+
+    #[test]
+    fn valid_lines_parse() {
+        let binding = parse(r#"bind "ctrl+enter" right_click"#).unwrap();
+        assert_eq!(
+            binding.sequence,
+            vec![combo("enter", true, false, false, false)]
+        );
+        assert_eq!(binding.action, Action::RightClick);
+
+        let binding = parse(r#"  bind   "g g"   goto_top_left  "#).unwrap();
+        let g = combo("g", false, false, false, false);
+        assert_eq!(binding.sequence, vec![g.clone(), g]);
+        assert_eq!(binding.action, Action::GotoTopLeft);
+
+        let binding = parse(r#"bind "F12 PageDown" scroll_down"#).unwrap();
+        assert_eq!(
+            binding.sequence,
+            vec![
+                combo("f12", false, false, false, false),
+                combo("pagedown", false, false, false, false)
+            ]
+        );
+    }
+
+    #[test]
+    fn modifier_combinations() {
+        let parsed = |keys: &str| Combo::parse(keys);
+        assert_eq!(
+            parsed("ctrl+shift+a"),
+            Some(combo("a", true, false, true, false))
+        );
+        assert_eq!(
+            parsed("Control+Alt+Super+Tab"),
+            Some(combo("tab", true, true, false, true))
+        );
+        assert_eq!(
+            parsed("logo+x"),
+            Some(combo("x", false, false, false, true))
+        );
+        // `+` on its own and after modifiers binds the plus key.
+        assert_eq!(parsed("+"), Some(combo("+", false, false, false, false)));
+        assert_eq!(
+            parsed("ctrl++"),
+            Some(combo("+", true, false, false, false))
+        );
+        assert_eq!(
+            parsed("shift+A"),
+            Some(combo("a", false, false, true, false))
+        );
+    }
+
+    #[test]
+    fn invalid_lines_are_rejected_with_a_reason() {
+        let error = |line: &str| parse(line).unwrap_err();
+        assert_eq!(error(r#"map "a" left_click"#), "expected `bind`");
+        assert_eq!(error("bind a left_click"), "expected a quoted key sequence");
+        assert_eq!(error(r#"bind "a left_click"#), "unterminated key sequence");
+        assert_eq!(error(r#"bind "  " left_click"#), "empty key sequence");
+        assert_eq!(
+            error(r#"bind "hyper+a" left_click"#),
+            r#"invalid key combo "hyper+a""#
+        );
+        assert_eq!(
+            error(r#"bind "ctrl+foo" left_click"#),
+            r#"invalid key combo "ctrl+foo""#
+        );
+        assert_eq!(
+            error(r#"bind "F36" left_click"#),
+            r#"invalid key combo "F36""#
+        );
+        assert_eq!(
+            error(r#"bind "ctrl+" left_click"#),
+            r#"invalid key combo "ctrl+""#
+        );
+        assert_eq!(error(r#"bind "a" fly"#), r#"unknown action "fly""#);
+        assert_eq!(error(r#"bind "a""#), r#"unknown action """#);
+    }
+
+    #[test]
+    fn invalid_lines_are_skipped_when_compiling() {
+        let lines = [r#"bind "a" left_click"#, r#"bind "hyper+b" close"#, "bind"];
+        let lines: Vec<String> = lines.iter().map(|line| line.to_string()).collect();
+        assert_eq!(compile(&lines).len(), 1);
+    }
+
+    #[test]
+    fn lookup_requires_the_exact_modifiers() {
+        let bindings = compile(&[
+            r#"bind "ctrl+enter" right_click"#.to_string(),
+            r#"bind "g g" goto_top_left"#.to_string(),
+        ]);
+        let enter = keyboard::Key::Named(Named::Enter);
+        let ctrl = keyboard::Modifiers::CTRL;
+        assert!(matches!(
+            lookup(&bindings, &[(enter.clone(), ctrl)]),
+            Lookup::Complete(Action::RightClick)
+        ));
+        assert!(matches!(
+            lookup(
+                &bindings,
+                &[(enter.clone(), ctrl | keyboard::Modifiers::SHIFT)]
+            ),
+            Lookup::NoMatch
+        ));
+        assert!(matches!(
+            lookup(&bindings, &[(enter, keyboard::Modifiers::empty())]),
+            Lookup::NoMatch
+        ));
+
+        let g = (char_key("G"), keyboard::Modifiers::empty());
+        assert!(matches!(
+            lookup(&bindings, std::slice::from_ref(&g)),
+            Lookup::Prefix
+        ));
+        assert!(matches!(
+            lookup(&bindings, &[g.clone(), g.clone()]),
+            Lookup::Complete(Action::GotoTopLeft)
+        ));
+        assert!(matches!(
+            lookup(&bindings, &[g.clone(), g.clone(), g]),
+            Lookup::NoMatch
+        ));
+    }
+
+    #[test]
+    fn shorter_sequences_shadow_longer_ones() {
+        let g = parse(r#"bind "g" close"#).unwrap();
+        let gg = parse(r#"bind "g g" goto_top_left"#).unwrap();
+        let ctrl_g = parse(r#"bind "ctrl+g" close"#).unwrap();
+        assert!(g.shadows(&gg));
+        assert!(g.shadows(&g));
+        assert!(!gg.shadows(&g));
+        assert!(!ctrl_g.shadows(&gg));
+    }
+}
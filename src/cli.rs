@@ -0,0 +1,217 @@
+use std::path::PathBuf;
+
+// --- Command Line ---
+
+#[derive(Debug, Default)]
+pub enum Command {
+    /// Run the overlay daemon.
+    #[default]
+    Daemon,
+    /// Print named click targets, one per line, for dmenu/fuzzel.
+    List,
+    /// Send a chosen target back to the daemon; read from stdin when omitted.
+    Pick(Option<String>),
+    /// Repeat the last click, scroll or drag without opening the grid.
+    Repeat,
+    /// rofi script mode (`rofi -show rowlink -modi rowlink:"rowlink picker"`): lists the
+    /// targets when called without an entry, picks the entry rofi passes otherwise.
+    Picker(Option<String>),
+    /// Open the grid and type the text after the chosen click.
+    Type(String),
+    /// Open the quick targets (corners, edge midpoints, center), like SIGUSR2.
+    Quick,
+    /// Switch the daemon's profile, or list profiles when no name is given.
+    Profile(Option<String>),
+    /// Switch the daemon's theme, or list themes when no name is given.
+    Theme(Option<String>),
+    /// Stop or resume recording clicks, or print whether recording is off.
+    Private(Option<bool>),
+    /// Delete the recorded click history (and so the heatmap), learned offsets and marks,
+    /// through the daemon or, when none runs, from the state directory.
+    Purge,
+    /// Print the daemon's events (shown, zoomed, clicked, ...) as JSON lines as they happen.
+    Subscribe,
+    /// List, add or remove marks: the words after `mark`, passed on to the daemon.
+    Mark(Vec<String>),
+    /// Draw the idle grid offscreen and save it as a PNG.
+    Render { out: PathBuf, theme: Option<String> },
+    /// Draw the recorded clicks as a heatmap over the grid and save it as a PNG.
+    Heatmap { out: PathBuf, app: Option<String> },
+    /// Write marks, hotspots and profiles to one JSON file.
+    Export(PathBuf),
+    /// Merge marks, hotspots and profiles from a file written by `export`; the hotspots and
+    /// profiles go into a copy of the config file to review.
+    Import(PathBuf),
+    /// Print the config file's path, the resolved config, or the defaults.
+    Config(ConfigCommand),
+    /// Ask a few questions and write a config file.
+    Setup,
+    /// Validate the config file, print every problem and exit non-zero if there are any.
+    CheckConfig,
+    /// Feed keys to an in-process grid and print the pointer calls they cause.
+    #[cfg(feature = "headless")]
+    Headless(Vec<String>),
+}
+
+#[derive(Debug)]
+pub enum ConfigCommand {
+    Path,
+    /// Where the config, state and control socket live, after overrides.
+    Dirs,
+    /// The config as loaded, with every default filled in; the active profile unless one
+    /// is named.
+    Dump(Option<String>),
+    /// The built-in defaults, as a starting config file.
+    Default,
+}
+
+#[derive(Debug, Default)]
+pub struct Args {
+    /// Seat to bind pointer emulation to, overriding the config file.
+    pub seat: Option<String>,
+    /// `gpu`, `software` or `auto`, overriding the config file.
+    pub renderer: Option<String>,
+    /// Overrides for where files live; see `paths`.
+    pub config: Option<PathBuf>,
+    pub state_dir: Option<PathBuf>,
+    pub socket: Option<PathBuf>,
+    pub command: Command,
+}
+
+const USAGE: &str = "Usage: rowlink [--seat <name>] [--renderer <gpu|software|auto>] \
+     [--config <file>] [--state-dir <dir>] [--socket <path>] [--check-config] \
+     [list | pick [<entry>] | repeat | picker [<entry>] | type <text> | quick | profile [<name>] \
+     | theme [set <name>] \
+     | private [on|off] | purge | subscribe | mark [list | add <name> [<x> <y>] | remove <name>] \
+     | config <path|dirs|dump [<profile>]|default> | setup \
+     | export <file.json> | import <file.json> \
+     | render --out <file.png> [--theme <name>] | heatmap --out <file.png> [--app <id>] \
+     | headless <key>...]";
+
+pub fn parse() -> Args {
+    let mut args = Args::default();
+    let mut iter = std::env::args().skip(1);
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--seat" => args.seat = Some(iter.next().unwrap_or_else(|| usage_error(&arg))),
+            "--renderer" => {
+                args.renderer = Some(iter.next().unwrap_or_else(|| usage_error(&arg)));
+            }
+            "--config" => args.config = Some(path_arg(&mut iter, &arg)),
+            "--state-dir" => args.state_dir = Some(path_arg(&mut iter, &arg)),
+            "--socket" => args.socket = Some(path_arg(&mut iter, &arg)),
+            "--check-config" => args.command = Command::CheckConfig,
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                std::process::exit(0);
+            }
+            "list" => args.command = Command::List,
+            "pick" => {
+                let entry: Vec<String> = iter.by_ref().collect();
+                args.command = Command::Pick((!entry.is_empty()).then(|| entry.join(" ")));
+            }
+            "repeat" => args.command = Command::Repeat,
+            "picker" => {
+                let entry: Vec<String> = iter.by_ref().collect();
+                args.command = Command::Picker((!entry.is_empty()).then(|| entry.join(" ")));
+            }
+            "type" => {
+                let text: Vec<String> = iter.by_ref().collect();
+                if text.is_empty() {
+                    usage_error(&arg);
+                }
+                args.command = Command::Type(text.join(" "));
+            }
+            "profile" => args.command = Command::Profile(iter.next()),
+            "theme" => {
+                args.command = Command::Theme(match iter.next().as_deref() {
+                    None | Some("list") => None,
+                    Some("set") => Some(iter.next().unwrap_or_else(|| usage_error(&arg))),
+                    Some(_) => usage_error(&arg),
+                });
+            }
+            "private" => {
+                args.command = Command::Private(match iter.next().as_deref() {
+                    None => None,
+                    Some("on") => Some(true),
+                    Some("off") => Some(false),
+                    Some(_) => usage_error(&arg),
+                });
+            }
+            "quick" => args.command = Command::Quick,
+            "purge" => args.command = Command::Purge,
+            "subscribe" => args.command = Command::Subscribe,
+            "mark" => args.command = Command::Mark(iter.by_ref().collect()),
+            "setup" => args.command = Command::Setup,
+            "render" => {
+                let (mut out, mut theme) = (None, None);
+                while let Some(option) = iter.next() {
+                    match option.as_str() {
+                        "--out" => out = Some(path_arg(&mut iter, &option)),
+                        "--theme" => {
+                            theme = Some(iter.next().unwrap_or_else(|| usage_error(&option)));
+                        }
+                        _ => usage_error(&option),
+                    }
+                }
+                let out = out.unwrap_or_else(|| usage_error(&arg));
+                args.command = Command::Render { out, theme };
+            }
+            "export" => args.command = Command::Export(path_arg(&mut iter, &arg)),
+            "import" => args.command = Command::Import(path_arg(&mut iter, &arg)),
+            "heatmap" => {
+                let (mut out, mut app) = (None, None);
+                while let Some(option) = iter.next() {
+                    match option.as_str() {
+                        "--out" => out = Some(path_arg(&mut iter, &option)),
+                        "--app" => app = Some(iter.next().unwrap_or_else(|| usage_error(&option))),
+                        _ => usage_error(&option),
+                    }
+                }
+                let out = out.unwrap_or_else(|| usage_error(&arg));
+                args.command = Command::Heatmap { out, app };
+            }
+            "config" => {
+                args.command = Command::Config(match iter.next().as_deref() {
+                    Some("path") => ConfigCommand::Path,
+                    Some("dirs") => ConfigCommand::Dirs,
+                    Some("dump") => ConfigCommand::Dump(iter.next()),
+                    Some("default") => ConfigCommand::Default,
+                    _ => usage_error(&arg),
+                });
+            }
+            #[cfg(feature = "headless")]
+            "headless" => args.command = Command::Headless(iter.by_ref().collect()),
+            #[cfg(not(feature = "headless"))]
+            "headless" => {
+                eprintln!("rowlink was built without the headless feature.");
+                std::process::exit(2);
+            }
+            _ => {
+                if let Some(seat) = arg.strip_prefix("--seat=") {
+                    args.seat = Some(seat.to_string());
+                } else if let Some(renderer) = arg.strip_prefix("--renderer=") {
+                    args.renderer = Some(renderer.to_string());
+                } else if let Some(path) = arg.strip_prefix("--config=") {
+                    args.config = Some(path.into());
+                } else if let Some(path) = arg.strip_prefix("--state-dir=") {
+                    args.state_dir = Some(path.into());
+                } else if let Some(path) = arg.strip_prefix("--socket=") {
+                    args.socket = Some(path.into());
+                } else {
+                    usage_error(&arg);
+                }
+            }
+        }
+    }
+    args
+}
+
+fn path_arg(iter: &mut impl Iterator<Item = String>, flag: &str) -> PathBuf {
+    iter.next().unwrap_or_else(|| usage_error(flag)).into()
+}
+
+fn usage_error(arg: &str) -> ! {
+    eprintln!("Invalid argument: {}\n{}", arg, USAGE);
+    std::process::exit(2);
+}
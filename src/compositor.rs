@@ -0,0 +1,419 @@
+use serde_json::Value;
+use std::process::Command;
+
+// --- Compositor IPC ---
+// Thin wrappers over the compositor's CLI clients. Everything here is best-effort:
+// when no supported compositor is detected, queries return None/false. Window positions
+// cross this boundary in the overlay's output-local coordinates; the conversion from and
+// to the compositor's global layout happens here and nowhere else.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compositor {
+    Hyprland,
+    Sway,
+}
+
+pub fn detect() -> Option<Compositor> {
+    if std::env::var_os("HYPRLAND_INSTANCE_SIGNATURE").is_some() {
+        Some(Compositor::Hyprland)
+    } else if std::env::var_os("SWAYSOCK").is_some() {
+        Some(Compositor::Sway)
+    } else {
+        None
+    }
+}
+
+fn run_json(program: &str, args: &[&str]) -> Option<Value> {
+    let output = Command::new(program).args(args).output().ok()?;
+    if !output.status.success() {
+        eprintln!("{} {:?} failed with {}", program, args, output.status);
+        return None;
+    }
+    serde_json::from_slice(&output.stdout).ok()
+}
+
+fn run(program: &str, args: &[&str]) {
+    match Command::new(program).args(args).status() {
+        Ok(status) if status.success() => {}
+        Ok(status) => eprintln!("{} {:?} failed with {}", program, args, status),
+        Err(e) => eprintln!("{} {:?} failed: {:?}", program, args, e),
+    }
+}
+
+/// Compositor-specific handle to a toplevel window.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WindowRef {
+    /// Hyprland window address (`0x...`).
+    Hyprland(String),
+    /// Sway container id.
+    Sway(i64),
+}
+
+fn sway_focused_node(node: &Value) -> Option<&Value> {
+    if node["focused"].as_bool() == Some(true) {
+        return Some(node);
+    }
+    ["nodes", "floating_nodes"]
+        .iter()
+        .filter_map(|key| node[*key].as_array())
+        .flatten()
+        .find_map(sway_focused_node)
+}
+
+/// The toplevel holding keyboard focus, read in a single query.
+#[derive(Debug, Clone)]
+pub struct Focused {
+    pub window: WindowRef,
+    /// App id (Wayland) or class (Xwayland).
+    pub app: Option<String>,
+    /// Fullscreen clients are games, video players, ...
+    pub fullscreen: bool,
+}
+
+pub fn focused() -> Option<Focused> {
+    match detect()? {
+        Compositor::Hyprland => {
+            let win = run_json("hyprctl", &["-j", "activewindow"])?;
+            Some(Focused {
+                window: WindowRef::Hyprland(win["address"].as_str()?.to_string()),
+                app: win["class"].as_str().map(str::to_string),
+                fullscreen: match &win["fullscreen"] {
+                    // Older Hyprland reports a bool, newer versions a fullscreen mode number.
+                    Value::Bool(b) => *b,
+                    Value::Number(n) => n.as_i64().unwrap_or(0) != 0,
+                    _ => false,
+                },
+            })
+        }
+        Compositor::Sway => {
+            let tree = run_json("swaymsg", &["-t", "get_tree", "-r"])?;
+            let node = sway_focused_node(&tree)?;
+            Some(Focused {
+                window: WindowRef::Sway(node["id"].as_i64()?),
+                app: node["app_id"]
+                    .as_str()
+                    .or_else(|| node["window_properties"]["class"].as_str())
+                    .map(str::to_string),
+                fullscreen: node["fullscreen_mode"].as_i64().unwrap_or(0) != 0,
+            })
+        }
+    }
+}
+
+pub fn focus_window(window: &WindowRef) {
+    match window {
+        WindowRef::Hyprland(address) => run(
+            "hyprctl",
+            &["dispatch", "focuswindow", &format!("address:{}", address)],
+        ),
+        WindowRef::Sway(id) => run("swaymsg", &[&format!("[con_id={}]", id), "focus"]),
+    }
+}
+
+/// Floats a window and moves its top-left corner to the output-local `(x, y)`.
+pub fn move_window(window: &WindowRef, x: f32, y: f32) {
+    let (x, y) = crate::placement().to_global((x, y));
+    let (x, y) = (x.round() as i32, y.round() as i32);
+    match window {
+        WindowRef::Hyprland(address) => {
+            let target = format!("address:{}", address);
+            run("hyprctl", &["dispatch", "setfloating", &target]);
+            let position = format!("exact {} {},{}", x, y, target);
+            run("hyprctl", &["dispatch", "movewindowpixel", &position]);
+        }
+        WindowRef::Sway(id) => run(
+            "swaymsg",
+            &[&format!(
+                "[con_id={}] floating enable, move absolute position {} {}",
+                id, x, y
+            )],
+        ),
+    }
+}
+
+/// Floats a window and gives it the output-local top-left corner `(x, y)` and size
+/// `width` x `height`. Floating a tiled window moves it, so it is put back in place before
+/// the resize.
+pub fn resize_window(window: &WindowRef, x: f32, y: f32, width: f32, height: f32) {
+    let (x, y) = crate::placement().to_global((x, y));
+    let (x, y) = (x.round() as i32, y.round() as i32);
+    let (width, height) = (width.round() as i32, height.round() as i32);
+    match window {
+        WindowRef::Hyprland(address) => {
+            let target = format!("address:{}", address);
+            run("hyprctl", &["dispatch", "setfloating", &target]);
+            let position = format!("exact {} {},{}", x, y, target);
+            run("hyprctl", &["dispatch", "movewindowpixel", &position]);
+            let size = format!("exact {} {},{}", width, height, target);
+            run("hyprctl", &["dispatch", "resizewindowpixel", &size]);
+        }
+        WindowRef::Sway(id) => run(
+            "swaymsg",
+            &[&format!(
+                "[con_id={}] floating enable, move absolute position {} {}, resize set {} {}",
+                id, x, y, width, height
+            )],
+        ),
+    }
+}
+
+/// A mapped toplevel window with its title and output-local geometry.
+#[derive(Debug, Clone)]
+pub struct Toplevel {
+    pub window: WindowRef,
+    pub app_id: String,
+    pub title: String,
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+    /// Place in the stacking order; of overlapping windows, the higher one is on top.
+    pub stacking: i64,
+}
+
+impl Toplevel {
+    /// Whether any of the window shows on the overlay's `width` x `height` output.
+    pub fn is_on_output(&self, width: f32, height: f32) -> bool {
+        self.x < width && self.y < height && self.x + self.width > 0.0 && self.y + self.height > 0.0
+    }
+}
+
+fn sway_toplevels(node: &Value, out: &mut Vec<Toplevel>) {
+    let children: Vec<&Value> = ["nodes", "floating_nodes"]
+        .iter()
+        .filter_map(|key| node[*key].as_array())
+        .flatten()
+        .collect();
+    let is_window = matches!(node["type"].as_str(), Some("con" | "floating_con"))
+        && children.is_empty()
+        && node["visible"].as_bool() == Some(true);
+    if is_window && let Some(id) = node["id"].as_i64() {
+        let rect = &node["rect"];
+        let app_id = node["app_id"]
+            .as_str()
+            .or_else(|| node["window_properties"]["class"].as_str())
+            .unwrap_or_default();
+        // Sway lists floating windows after the tiled ones they cover, bottom to top.
+        let stacking = out.len() as i64;
+        out.push(Toplevel {
+            window: WindowRef::Sway(id),
+            app_id: app_id.to_string(),
+            title: node["name"].as_str().unwrap_or_default().to_string(),
+            x: rect["x"].as_f64().unwrap_or(0.0) as f32,
+            y: rect["y"].as_f64().unwrap_or(0.0) as f32,
+            width: rect["width"].as_f64().unwrap_or(0.0) as f32,
+            height: rect["height"].as_f64().unwrap_or(0.0) as f32,
+            stacking,
+        });
+    }
+    for child in children {
+        sway_toplevels(child, out);
+    }
+}
+
+/// Ids of the workspaces some monitor shows, special workspaces included.
+fn hyprland_visible_workspaces() -> Vec<i64> {
+    let monitors = run_json("hyprctl", &["-j", "monitors"]);
+    monitors
+        .as_ref()
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .flat_map(|m| [&m["activeWorkspace"]["id"], &m["specialWorkspace"]["id"]])
+        .filter_map(Value::as_i64)
+        .filter(|id| *id != 0)
+        .collect()
+}
+
+/// Toplevels on the workspaces currently shown on any output, in the compositor's own
+/// order.
+pub fn toplevels() -> Vec<Toplevel> {
+    let mut toplevels = match detect() {
+        Some(Compositor::Hyprland) => {
+            let visible = hyprland_visible_workspaces();
+            run_json("hyprctl", &["-j", "clients"])
+                .and_then(|clients| clients.as_array().cloned())
+                .unwrap_or_default()
+                .iter()
+                .filter(|c| {
+                    c["mapped"].as_bool() == Some(true) && c["hidden"].as_bool() != Some(true)
+                })
+                // Pinned windows follow the focused workspace.
+                .filter(|c| {
+                    c["pinned"].as_bool() == Some(true)
+                        || c["workspace"]["id"]
+                            .as_i64()
+                            .is_some_and(|id| visible.contains(&id))
+                })
+                .filter_map(|c| {
+                    // Floating windows sit above tiled ones, and the more recently focused
+                    // above the rest.
+                    let floating = c["floating"].as_bool() == Some(true);
+                    let focus_age = c["focusHistoryID"].as_i64().unwrap_or(i32::MAX as i64);
+                    Some(Toplevel {
+                        window: WindowRef::Hyprland(c["address"].as_str()?.to_string()),
+                        app_id: c["class"].as_str().unwrap_or_default().to_string(),
+                        title: c["title"].as_str().unwrap_or_default().to_string(),
+                        x: c["at"][0].as_f64()? as f32,
+                        y: c["at"][1].as_f64()? as f32,
+                        width: c["size"][0].as_f64()? as f32,
+                        height: c["size"][1].as_f64()? as f32,
+                        stacking: ((floating as i64) << 32) - focus_age,
+                    })
+                })
+                .collect()
+        }
+        Some(Compositor::Sway) => {
+            let mut out = Vec::new();
+            if let Some(tree) = run_json("swaymsg", &["-t", "get_tree", "-r"]) {
+                sway_toplevels(&tree, &mut out);
+            }
+            out
+        }
+        None => Vec::new(),
+    };
+    let placement = crate::placement();
+    for toplevel in &mut toplevels {
+        (toplevel.x, toplevel.y) = placement.to_local((toplevel.x, toplevel.y));
+    }
+    toplevels
+}
+
+/// The visible toplevel on top at a point. Spawns the compositor's client, so keep it off
+/// the UI thread.
+pub fn toplevel_at(x: f32, y: f32) -> Option<Toplevel> {
+    toplevels()
+        .into_iter()
+        .filter(|t| x >= t.x && x < t.x + t.width && y >= t.y && y < t.y + t.height)
+        .max_by_key(|t| t.stacking)
+}
+
+/// The focused window, if it is a visible toplevel.
+pub fn focused_toplevel() -> Option<Toplevel> {
+    let window = focused()?.window;
+    toplevels().into_iter().find(|t| t.window == window)
+}
+
+/// Name of the active xkb layout of the main keyboard, e.g. `English (Dvorak)`.
+pub fn keyboard_layout() -> Option<String> {
+    let name = match detect()? {
+        Compositor::Hyprland => {
+            let devices = run_json("hyprctl", &["-j", "devices"])?;
+            let keyboards = devices["keyboards"].as_array()?;
+            keyboards
+                .iter()
+                .find(|k| k["main"].as_bool() == Some(true))
+                .or(keyboards.first())?["active_keymap"]
+                .as_str()?
+                .to_string()
+        }
+        Compositor::Sway => {
+            let inputs = run_json("swaymsg", &["-t", "get_inputs", "-r"])?;
+            inputs
+                .as_array()?
+                .iter()
+                .find(|i| i["type"].as_str() == Some("keyboard"))?["xkb_active_layout_name"]
+                .as_str()?
+                .to_string()
+        }
+    };
+    Some(name)
+}
+
+/// An enabled output: its origin in the global layout and size in logical pixels, and
+/// its scale.
+pub struct Output {
+    /// `DP-1`, `eDP-1`, ...
+    pub name: String,
+    /// Whether it holds the focus, where the overlay opens by default.
+    pub focused: bool,
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+    pub scale: f32,
+}
+
+pub fn outputs() -> Vec<Output> {
+    match detect() {
+        Some(Compositor::Hyprland) => run_json("hyprctl", &["-j", "monitors"])
+            .and_then(|monitors| monitors.as_array().cloned())
+            .unwrap_or_default()
+            .iter()
+            .filter_map(|m| {
+                let scale = m["scale"].as_f64().filter(|s| *s > 0.0).unwrap_or(1.0);
+                let (width, height) = (m["width"].as_f64()? / scale, m["height"].as_f64()? / scale);
+                // Transforms 1, 3, 5 and 7 rotate by 90 or 270 degrees.
+                let rotated = m["transform"].as_i64().unwrap_or(0) % 2 == 1;
+                let (width, height) = if rotated {
+                    (height, width)
+                } else {
+                    (width, height)
+                };
+                Some(Output {
+                    name: m["name"].as_str()?.to_string(),
+                    focused: m["focused"].as_bool() == Some(true),
+                    x: m["x"].as_f64().unwrap_or(0.0) as f32,
+                    y: m["y"].as_f64().unwrap_or(0.0) as f32,
+                    width: width as f32,
+                    height: height as f32,
+                    scale: scale as f32,
+                })
+            })
+            .collect(),
+        // Sway already reports output rects in logical pixels.
+        Some(Compositor::Sway) => run_json("swaymsg", &["-t", "get_outputs", "-r"])
+            .and_then(|outputs| outputs.as_array().cloned())
+            .unwrap_or_default()
+            .iter()
+            .filter(|o| o["active"].as_bool() == Some(true))
+            .filter_map(|o| {
+                Some(Output {
+                    name: o["name"].as_str()?.to_string(),
+                    focused: o["focused"].as_bool() == Some(true),
+                    x: o["rect"]["x"].as_f64().unwrap_or(0.0) as f32,
+                    y: o["rect"]["y"].as_f64().unwrap_or(0.0) as f32,
+                    width: o["rect"]["width"].as_f64()? as f32,
+                    height: o["rect"]["height"].as_f64()? as f32,
+                    scale: o["scale"].as_f64().filter(|s| *s > 0.0).unwrap_or(1.0) as f32,
+                })
+            })
+            .collect(),
+        None => Vec::new(),
+    }
+}
+
+/// Space panels reserve on the output named `output` through exclusive zones, as
+/// `(top, right, bottom, left)` in pixels.
+pub fn reserved_area(output: &str) -> Option<(f32, f32, f32, f32)> {
+    match detect()? {
+        Compositor::Hyprland => {
+            let monitors = run_json("hyprctl", &["-j", "monitors"])?;
+            let monitor = monitors.as_array()?.iter().find(|m| m["name"] == output)?;
+            // Hyprland reports [left, top, right, bottom].
+            let r = |i: usize| monitor["reserved"][i].as_f64().unwrap_or(0.0) as f32;
+            Some((r(1), r(2), r(3), r(0)))
+        }
+        Compositor::Sway => {
+            let outputs = run_json("swaymsg", &["-t", "get_outputs", "-r"])?;
+            let output = outputs.as_array()?.iter().find(|o| o["name"] == output)?;
+            let workspaces = run_json("swaymsg", &["-t", "get_workspaces", "-r"])?;
+            let workspace = workspaces
+                .as_array()?
+                .iter()
+                .find(|w| w["name"] == output["current_workspace"])?;
+            // The workspace rect is the output rect minus the exclusive zones.
+            let rect = |v: &Value, key: &str| v["rect"][key].as_f64().unwrap_or(0.0) as f32;
+            let (ox, oy) = (rect(output, "x"), rect(output, "y"));
+            let (ow, oh) = (rect(output, "width"), rect(output, "height"));
+            let (wx, wy) = (rect(workspace, "x"), rect(workspace, "y"));
+            let (ww, wh) = (rect(workspace, "width"), rect(workspace, "height"));
+            Some((
+                wy - oy,
+                (ox + ow) - (wx + ww),
+                (oy + oh) - (wy + wh),
+                wx - ox,
+            ))
+        }
+    }
+}
@@ -0,0 +1,118 @@
+use crate::MM_PER_INCH;
+use wayland_client::globals::{GlobalListContents, registry_queue_init};
+use wayland_client::protocol::{wl_output, wl_registry};
+use wayland_client::{Connection, Dispatch, QueueHandle, WEnum};
+
+// --- Pixel Density ---
+// The `auto` grid sizes cells in millimetres, so it needs each output's pixel density.
+// Compositor IPC reports outputs in pixels only; their physical size comes with wl_output,
+// which every client gets, so rowlink reads it on a connection of its own.
+
+/// What wl_output reports about one output.
+#[derive(Default)]
+struct Output {
+    name: Option<String>,
+    /// Physical size in millimetres, in the panel's own orientation.
+    size_mm: (i32, i32),
+    /// Current mode in physical pixels, also unrotated.
+    mode: (i32, i32),
+}
+
+#[derive(Default)]
+struct Outputs(Vec<Output>);
+
+impl Dispatch<wl_registry::WlRegistry, GlobalListContents> for Outputs {
+    fn event(
+        _: &mut Self,
+        _: &wl_registry::WlRegistry,
+        _: wl_registry::Event,
+        _: &GlobalListContents,
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+    }
+}
+
+impl Dispatch<wl_output::WlOutput, usize> for Outputs {
+    fn event(
+        outputs: &mut Self,
+        _: &wl_output::WlOutput,
+        event: wl_output::Event,
+        index: &usize,
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        let output = &mut outputs.0[*index];
+        match event {
+            wl_output::Event::Geometry {
+                physical_width,
+                physical_height,
+                ..
+            } => output.size_mm = (physical_width, physical_height),
+            wl_output::Event::Mode {
+                flags: WEnum::Value(flags),
+                width,
+                height,
+                ..
+            } if flags.contains(wl_output::Mode::Current) => output.mode = (width, height),
+            wl_output::Event::Name { name } => output.name = Some(name),
+            _ => {}
+        }
+    }
+}
+
+/// Physical pixels per inch of the output named `output`, if it reports a believable size.
+/// Takes a Wayland round trip.
+pub fn dpi(output: &str) -> Option<f32> {
+    let connection = Connection::connect_to_env().ok()?;
+    let (globals, mut queue) = registry_queue_init::<Outputs>(&connection).ok()?;
+    let mut outputs = Outputs::default();
+    for global in globals.contents().clone_list() {
+        // Outputs only have names from version 4 on.
+        if global.interface == "wl_output" && global.version >= 4 {
+            let index = outputs.0.len();
+            outputs.0.push(Output::default());
+            globals.registry().bind::<wl_output::WlOutput, _, _>(
+                global.name,
+                4,
+                &queue.handle(),
+                index,
+            );
+        }
+    }
+    queue.roundtrip(&mut outputs).ok()?;
+    let output = outputs
+        .0
+        .iter()
+        .find(|o| o.name.as_deref() == Some(output))?;
+    density(output.mode.0, output.size_mm.0)
+}
+
+/// `pixels` across `mm` in dots per inch. None when projectors and some TVs leave the size
+/// out, or their EDID gives one no screen can have.
+fn density(pixels: i32, mm: i32) -> Option<f32> {
+    if pixels <= 0 || mm <= 0 {
+        return None;
+    }
+    Some(pixels as f32 / (mm as f32 / MM_PER_INCH)).filter(|dpi| (50.0..=800.0).contains(dpi))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn density_from_size_and_mode() {
+        // A 27" 4K monitor and a 14" 1080p laptop panel.
+        assert_eq!(density(3840, 597).map(f32::round), Some(163.0));
+        assert_eq!(density(1920, 309).map(f32::round), Some(158.0));
+    }
+
+    #[test]
+    fn missing_or_impossible_sizes_are_ignored() {
+        assert_eq!(density(1920, 0), None);
+        assert_eq!(density(0, 309), None);
+        assert_eq!(density(3840, 16), None);
+        assert_eq!(density(1920, 10_000), None);
+    }
+}
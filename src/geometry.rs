@@ -0,0 +1,385 @@
+// --- Geometry ---
+// Pure cell <-> pixel math shared by drawing, clicking and hit testing. Everything is in
+// surface-local logical pixels, and every input (surface size, insets, grid dimensions,
+// output scale and origin) is passed in explicitly. Cell edges fall on whole physical
+// pixels at the grid's scale, so lines stay crisp at fractional scales and hit testing
+// agrees with what is drawn; `Placement` maps surface-local points into the compositor's
+// global layout.
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Rect {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+impl Rect {
+    pub fn center(self) -> (f32, f32) {
+        (self.x + self.width / 2.0, self.y + self.height / 2.0)
+    }
+
+    /// Shrinks the rectangle by `(top, right, bottom, left)`, keeping at least 1x1.
+    pub fn inset(self, (top, right, bottom, left): (f32, f32, f32, f32)) -> Self {
+        Self {
+            x: self.x + left,
+            y: self.y + top,
+            width: (self.width - left - right).max(1.0),
+            height: (self.height - top - bottom).max(1.0),
+        }
+    }
+}
+
+/// Where a surface sits: the origin and size of its output in the compositor's global
+/// layout, the top-left corner of that whole layout, and the output's scale.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Placement {
+    pub x: f32,
+    pub y: f32,
+    /// Logical size of the output; zero until it is known.
+    pub width: f32,
+    pub height: f32,
+    /// Top-left corner of the layout, where a pointer pushed up and left comes to rest.
+    pub layout_x: f32,
+    pub layout_y: f32,
+    /// Physical pixels per logical pixel.
+    pub scale: f32,
+    /// Space panels reserve along the output's edges through exclusive zones, as
+    /// `(top, right, bottom, left)`.
+    pub reserved: (f32, f32, f32, f32),
+    /// Logical pixels per inch; zero until it is known.
+    pub dpi: f32,
+}
+
+impl Default for Placement {
+    fn default() -> Self {
+        Self {
+            x: 0.0,
+            y: 0.0,
+            width: 0.0,
+            height: 0.0,
+            layout_x: 0.0,
+            layout_y: 0.0,
+            scale: 1.0,
+            reserved: (0.0, 0.0, 0.0, 0.0),
+            dpi: 0.0,
+        }
+    }
+}
+
+impl Placement {
+    pub fn to_global(self, (x, y): (f32, f32)) -> (f32, f32) {
+        (self.x + x, self.y + y)
+    }
+
+    pub fn to_local(self, (x, y): (f32, f32)) -> (f32, f32) {
+        (x - self.x, y - self.y)
+    }
+
+    /// Relative motion from the layout's top-left corner to a surface-local point.
+    pub fn pointer_motion(self, point: (f32, f32)) -> (f32, f32) {
+        let (x, y) = self.to_global(point);
+        (x - self.layout_x, y - self.layout_y)
+    }
+}
+
+/// `value` rounded to the nearest physical pixel edge at `scale`.
+pub fn snap(value: f32, scale: f32) -> f32 {
+    if scale > 0.0 {
+        (value * scale).round() / scale
+    } else {
+        value
+    }
+}
+
+/// `rows` x `cols` cells covering `area`, equal up to rounding their edges to physical
+/// pixels at `scale`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Grid {
+    pub area: Rect,
+    pub rows: i32,
+    pub cols: i32,
+    pub scale: f32,
+}
+
+impl Grid {
+    /// A grid over a `width` x `height` surface minus `insets` (top, right, bottom, left).
+    pub fn new(
+        width: f32,
+        height: f32,
+        insets: (f32, f32, f32, f32),
+        rows: i32,
+        cols: i32,
+        scale: f32,
+    ) -> Self {
+        let surface = Rect {
+            x: 0.0,
+            y: 0.0,
+            width,
+            height,
+        };
+        Self::over(surface.inset(insets), rows, cols, scale)
+    }
+
+    /// A grid covering `area`. Edges stay unsnapped (scale 0) once cells are thinner than
+    /// a physical pixel, as deep zoom levels and numpad regions get.
+    pub fn over(area: Rect, rows: i32, cols: i32, scale: f32) -> Self {
+        let (rows, cols) = (rows.max(1), cols.max(1));
+        let thinnest = (area.width / cols as f32).min(area.height / rows as f32);
+        Self {
+            area,
+            rows,
+            cols,
+            scale: if thinnest * scale < 1.0 { 0.0 } else { scale },
+        }
+    }
+
+    pub fn cell_size(&self) -> (f32, f32) {
+        (
+            self.area.width / self.cols as f32,
+            self.area.height / self.rows as f32,
+        )
+    }
+
+    /// Left edge of column `col` and top edge of row `row`; `cols`/`rows` give the far
+    /// edges.
+    fn edge_x(&self, col: i32) -> f32 {
+        snap(self.area.x + col as f32 * self.cell_size().0, self.scale)
+    }
+
+    fn edge_y(&self, row: i32) -> f32 {
+        snap(self.area.y + row as f32 * self.cell_size().1, self.scale)
+    }
+
+    pub fn cell(&self, row: i32, col: i32) -> Rect {
+        let (x, y) = (self.edge_x(col), self.edge_y(row));
+        Rect {
+            x,
+            y,
+            width: self.edge_x(col + 1) - x,
+            height: self.edge_y(row + 1) - y,
+        }
+    }
+
+    /// The cell containing a point, if the point is on the grid.
+    pub fn cell_at(&self, x: f32, y: f32) -> Option<(i32, i32)> {
+        let (width, height) = self.cell_size();
+        // The unsnapped estimate is off by at most one cell near a rounded edge.
+        let mut col = ((x - self.area.x) / width).floor() as i32;
+        if x < self.edge_x(col) {
+            col -= 1;
+        } else if x >= self.edge_x(col + 1) {
+            col += 1;
+        }
+        let mut row = ((y - self.area.y) / height).floor() as i32;
+        if y < self.edge_y(row) {
+            row -= 1;
+        } else if y >= self.edge_y(row + 1) {
+            row += 1;
+        }
+        ((0..self.rows).contains(&row) && (0..self.cols).contains(&col)).then_some((row, col))
+    }
+
+    /// A finer grid inside one cell, inset by `padding` on every side.
+    pub fn sub_grid(&self, row: i32, col: i32, rows: i32, cols: i32, padding: f32) -> Grid {
+        let area = self
+            .cell(row, col)
+            .inset((padding, padding, padding, padding));
+        Grid::over(area, rows, cols, self.scale)
+    }
+}
+
+/// Position of the `index`th of `count` points spread evenly from edge to edge of
+/// `extent`; a single point sits in the middle.
+pub fn spread(index: i32, count: usize, extent: f32) -> f32 {
+    if count > 1 {
+        index as f32 * (extent - 1.0) / (count - 1) as f32
+    } else {
+        extent / 2.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SCALES: [f32; 5] = [1.0, 1.25, 1.5, 2.0, 2.4];
+
+    fn grids() -> Vec<Grid> {
+        let mut grids = Vec::new();
+        for scale in SCALES {
+            for (width, height) in [(1920.0, 1080.0), (1366.0, 768.0), (3440.0, 1440.0)] {
+                for insets in [(0.0, 0.0, 0.0, 0.0), (32.5, 0.0, 10.0, 7.25)] {
+                    for (rows, cols) in [(1, 1), (26, 26), (7, 13), (16, 36)] {
+                        grids.push(Grid::new(width, height, insets, rows, cols, scale));
+                    }
+                }
+            }
+        }
+        grids
+    }
+
+    fn on_physical_pixel(value: f32, scale: f32) -> bool {
+        ((value * scale) - (value * scale).round()).abs() < 1e-2
+    }
+
+    #[test]
+    fn cells_tile_the_area_on_physical_pixels() {
+        for grid in grids() {
+            for row in 0..grid.rows {
+                for col in 0..grid.cols {
+                    let cell = grid.cell(row, col);
+                    assert!(cell.width > 0.0 && cell.height > 0.0, "{:?}", grid);
+                    assert!(on_physical_pixel(cell.x, grid.scale), "{:?} {}", grid, col);
+                    assert!(on_physical_pixel(cell.y, grid.scale), "{:?} {}", grid, row);
+                    if col + 1 < grid.cols {
+                        assert!((cell.x + cell.width - grid.cell(row, col + 1).x).abs() < 1e-3);
+                    }
+                    if row + 1 < grid.rows {
+                        assert!((cell.y + cell.height - grid.cell(row + 1, col).y).abs() < 1e-3);
+                    }
+                }
+            }
+            let last = grid.cell(grid.rows - 1, grid.cols - 1);
+            let right = grid.area.x + grid.area.width;
+            let bottom = grid.area.y + grid.area.height;
+            assert!((last.x + last.width - right).abs() <= 1.0 / grid.scale);
+            assert!((last.y + last.height - bottom).abs() <= 1.0 / grid.scale);
+        }
+    }
+
+    #[test]
+    fn cell_at_finds_every_cell_from_its_corners_and_center() {
+        for grid in grids() {
+            for row in 0..grid.rows {
+                for col in 0..grid.cols {
+                    let cell = grid.cell(row, col);
+                    let (center_x, center_y) = cell.center();
+                    let inside = 0.01;
+                    let points = [
+                        (cell.x, cell.y),
+                        (center_x, center_y),
+                        (cell.x + cell.width - inside, cell.y + cell.height - inside),
+                    ];
+                    for (x, y) in points {
+                        assert_eq!(grid.cell_at(x, y), Some((row, col)), "{:?}", grid);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn cell_at_misses_outside_the_area() {
+        let grid = Grid::new(1920.0, 1080.0, (40.0, 0.0, 0.0, 20.0), 26, 26, 1.5);
+        assert_eq!(grid.cell_at(10.0, 500.0), None);
+        assert_eq!(grid.cell_at(500.0, 39.0), None);
+        assert_eq!(grid.cell_at(1920.0, 500.0), None);
+        assert_eq!(grid.cell_at(500.0, 1080.0), None);
+        assert_eq!(grid.cell_at(20.0, 40.0), Some((0, 0)));
+    }
+
+    #[test]
+    fn sub_grids_stay_inside_their_cell() {
+        for grid in grids() {
+            for padding in [0.0, 2.0, 3.5] {
+                let (row, col) = (grid.rows / 2, grid.cols / 2);
+                let cell = grid.cell(row, col);
+                let sub = grid.sub_grid(row, col, 3, 8, padding);
+                assert_eq!(sub.scale, grid.scale);
+                assert_eq!(sub.area, cell.inset((padding, padding, padding, padding)));
+                for sub_row in 0..sub.rows {
+                    for sub_col in 0..sub.cols {
+                        let (x, y) = sub.cell(sub_row, sub_col).center();
+                        assert_eq!(grid.cell_at(x, y), Some((row, col)));
+                        assert_eq!(sub.cell_at(x, y), Some((sub_row, sub_col)));
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn sub_pixel_cells_are_left_unsnapped() {
+        let grid = Grid::new(1920.0, 1080.0, (0.0, 0.0, 0.0, 0.0), 26, 26, 2.0);
+        let mut deep = grid.sub_grid(3, 4, 3, 8, 0.0);
+        for _ in 0..4 {
+            if deep.scale == 0.0 {
+                break;
+            }
+            deep = deep.sub_grid(1, 1, 3, 8, 0.0);
+        }
+        assert_eq!(deep.scale, 0.0);
+        for col in 0..deep.cols {
+            assert!(deep.cell(0, col).width > 0.0);
+        }
+        let (x, y) = deep.cell(2, 7).center();
+        assert_eq!(deep.cell_at(x, y), Some((2, 7)));
+    }
+
+    #[test]
+    fn inset_round_trips_and_keeps_one_pixel() {
+        let rect = Rect {
+            x: 10.0,
+            y: 20.0,
+            width: 300.0,
+            height: 200.0,
+        };
+        let insets = (5.0, 7.5, 2.0, 3.0);
+        let inner = rect.inset(insets);
+        assert_eq!(
+            inner,
+            Rect {
+                x: 13.0,
+                y: 25.0,
+                width: 289.5,
+                height: 193.0,
+            }
+        );
+        let outer = inner.inset((-5.0, -7.5, -2.0, -3.0));
+        assert_eq!(outer, rect);
+        let collapsed = rect.inset((150.0, 200.0, 150.0, 200.0));
+        assert_eq!((collapsed.width, collapsed.height), (1.0, 1.0));
+    }
+
+    #[test]
+    fn placement_maps_between_local_and_global() {
+        let placement = Placement {
+            x: 1920.0,
+            y: -360.5,
+            width: 2560.0,
+            height: 1440.0,
+            layout_x: 0.0,
+            layout_y: -360.5,
+            scale: 1.25,
+            reserved: (0.0, 0.0, 0.0, 0.0),
+            dpi: 0.0,
+        };
+        for point in [(0.0, 0.0), (100.25, 73.5), (-1920.0, 2000.0)] {
+            assert_eq!(placement.to_local(placement.to_global(point)), point);
+        }
+        assert_eq!(placement.to_global((10.0, 10.0)), (1930.0, -350.5));
+        assert_eq!(placement.pointer_motion((10.0, 10.0)), (1930.0, 10.0));
+        let cell = Grid::new(2560.0, 1440.0, (0.0, 0.0, 0.0, 0.0), 4, 4, 1.25).cell(1, 1);
+        assert_eq!(placement.to_global((cell.x, cell.y)), (2560.0, -0.5));
+        assert_eq!(
+            Placement::default().pointer_motion((12.0, 34.0)),
+            (12.0, 34.0)
+        );
+    }
+
+    #[test]
+    fn snap_rounds_to_physical_pixels() {
+        assert_eq!(snap(10.3, 1.0), 10.0);
+        assert_eq!(snap(10.3, 2.0), 10.5);
+        assert_eq!(snap(10.3, 1.25), 10.4);
+        assert_eq!(snap(10.3, 0.0), 10.3);
+    }
+
+    #[test]
+    fn spread_reaches_both_edges() {
+        assert_eq!(spread(0, 3, 1920.0), 0.0);
+        assert_eq!(spread(2, 3, 1920.0), 1919.0);
+        assert_eq!(spread(0, 1, 1920.0), 960.0);
+    }
+}
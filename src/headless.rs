@@ -0,0 +1,277 @@
+use crate::{Message, Mode, Rowlink, update};
+use enigo::{Axis, Button, Coordinate, Direction, InputResult, Mouse};
+use iced::futures::StreamExt;
+use iced::keyboard;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+// --- Headless Driver ---
+// Runs the selection state machine without a compositor: synthetic key events go straight
+// into `update`, every task it returns is resolved in place, and pointer actions land in
+// a `MockPointer` instead of a real device.
+
+/// One pointer call as the backend received it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PointerEvent {
+    Move(i32, i32, Coordinate),
+    Button(Button, Direction),
+    Scroll(i32, Axis),
+}
+
+/// Records every pointer call; the shared log stays readable after the state owns it.
+pub struct MockPointer {
+    log: Rc<RefCell<Vec<PointerEvent>>>,
+    x: i32,
+    y: i32,
+}
+
+impl MockPointer {
+    pub fn new(log: Rc<RefCell<Vec<PointerEvent>>>) -> Self {
+        Self { log, x: 0, y: 0 }
+    }
+}
+
+impl Mouse for MockPointer {
+    fn button(&mut self, button: Button, direction: Direction) -> InputResult<()> {
+        self.log
+            .borrow_mut()
+            .push(PointerEvent::Button(button, direction));
+        Ok(())
+    }
+
+    fn move_mouse(&mut self, x: i32, y: i32, coordinate: Coordinate) -> InputResult<()> {
+        (self.x, self.y) = match coordinate {
+            Coordinate::Abs => (x, y),
+            Coordinate::Rel => (self.x + x, self.y + y),
+        };
+        self.log
+            .borrow_mut()
+            .push(PointerEvent::Move(x, y, coordinate));
+        Ok(())
+    }
+
+    fn scroll(&mut self, length: i32, axis: Axis) -> InputResult<()> {
+        self.log
+            .borrow_mut()
+            .push(PointerEvent::Scroll(length, axis));
+        Ok(())
+    }
+
+    fn main_display(&self) -> InputResult<(i32, i32)> {
+        Ok((
+            crate::cfg().screen_width as i32,
+            crate::cfg().screen_height as i32,
+        ))
+    }
+
+    fn location(&self) -> InputResult<(i32, i32)> {
+        Ok((self.x, self.y))
+    }
+}
+
+/// Parses a key such as `a`, `shift+a`, `ctrl+enter` or `space`.
+fn parse_key(input: &str) -> Option<(keyboard::Key, keyboard::Modifiers)> {
+    let mut modifiers = keyboard::Modifiers::empty();
+    let mut parts: Vec<&str> = input.split('+').collect();
+    let name = parts.pop()?;
+    for modifier in parts {
+        modifiers |= match modifier.to_lowercase().as_str() {
+            "ctrl" | "control" => keyboard::Modifiers::CTRL,
+            "alt" => keyboard::Modifiers::ALT,
+            "shift" => keyboard::Modifiers::SHIFT,
+            "super" | "logo" => keyboard::Modifiers::LOGO,
+            _ => return None,
+        };
+    }
+    use keyboard::key::Named;
+    let key = match name.to_lowercase().as_str() {
+        "enter" => keyboard::Key::Named(Named::Enter),
+        "escape" | "esc" => keyboard::Key::Named(Named::Escape),
+        "space" => keyboard::Key::Named(Named::Space),
+        "tab" => keyboard::Key::Named(Named::Tab),
+        "backspace" => keyboard::Key::Named(Named::Backspace),
+        "up" => keyboard::Key::Named(Named::ArrowUp),
+        "down" => keyboard::Key::Named(Named::ArrowDown),
+        "left" => keyboard::Key::Named(Named::ArrowLeft),
+        "right" => keyboard::Key::Named(Named::ArrowRight),
+        _ if name.chars().count() == 1 => keyboard::Key::Character(name.into()),
+        _ => return None,
+    };
+    Some((key, modifiers))
+}
+
+fn key_event(key: keyboard::Key, modifiers: keyboard::Modifiers, pressed: bool) -> Message {
+    let physical_key =
+        keyboard::key::Physical::Unidentified(keyboard::key::NativeCode::Unidentified);
+    let event = if pressed {
+        keyboard::Event::KeyPressed {
+            modified_key: key.clone(),
+            text: match &key {
+                keyboard::Key::Character(c) => Some(c.clone()),
+                _ => None,
+            },
+            key,
+            physical_key,
+            location: keyboard::Location::Standard,
+            modifiers,
+            repeat: false,
+        }
+    } else {
+        keyboard::Event::KeyReleased {
+            modified_key: key.clone(),
+            key,
+            physical_key,
+            location: keyboard::Location::Standard,
+            modifiers,
+        }
+    };
+    Message::IcedEvent(iced::Event::Keyboard(event))
+}
+
+/// Runs `message` and every message its tasks produce, depth first.
+fn dispatch(state: &mut Rowlink, runtime: &tokio::runtime::Runtime, message: Message) {
+    let task = update(state, message);
+    let Some(stream) = iced_runtime::task::into_stream(task) else {
+        return;
+    };
+    for action in runtime.block_on(stream.collect::<Vec<_>>()) {
+        if let iced_runtime::Action::Output(message) = action {
+            dispatch(state, runtime, message);
+        }
+    }
+}
+
+/// Opens the grid, presses and releases each key in turn and returns the pointer calls
+/// they caused.
+pub fn run(keys: &[String]) -> Result<Vec<PointerEvent>, String> {
+    let keys = keys
+        .iter()
+        .map(|key| parse_key(key).ok_or(format!("invalid key {:?}", key)))
+        .collect::<Result<Vec<_>, _>>()?;
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_time()
+        .build()
+        .map_err(|e| e.to_string())?;
+    // `update` builds timers as it goes, which need the runtime's reactor.
+    let _runtime = runtime.enter();
+    let log = Rc::new(RefCell::new(Vec::new()));
+    let mut state = Rowlink {
+        backend: Some(Box::new(MockPointer::new(log.clone()))),
+        ..Rowlink::default()
+    };
+
+    dispatch(&mut state, &runtime, Message::SignalReceived(Mode::Grid));
+    for (key, modifiers) in keys {
+        let modifiers_changed = |modifiers| {
+            Message::IcedEvent(iced::Event::Keyboard(keyboard::Event::ModifiersChanged(
+                modifiers,
+            )))
+        };
+        if !modifiers.is_empty() {
+            dispatch(&mut state, &runtime, modifiers_changed(modifiers));
+        }
+        dispatch(
+            &mut state,
+            &runtime,
+            key_event(key.clone(), modifiers, true),
+        );
+        dispatch(&mut state, &runtime, key_event(key, modifiers, false));
+        if !modifiers.is_empty() {
+            dispatch(
+                &mut state,
+                &runtime,
+                modifiers_changed(keyboard::Modifiers::empty()),
+            );
+        }
+    }
+    Ok(log.take())
+}
+
+#[cfg(all(test, feature = "headless"))]
+mod tests {
+    use super::*;
+    use enigo::{Button, Coordinate, Direction};
+    use std::sync::Once;
+
+    static ISOLATE: Once = Once::new();
+
+    /// Runs `keys` on the default config, away from the user's config, state and
+    /// compositor. Targets below are for the default 1920x1080 grid.
+    fn run_keys(keys: &[&str]) -> Vec<PointerEvent> {
+        ISOLATE.call_once(|| {
+            let dir = std::env::temp_dir().join(format!("rowlink-tests-{}", std::process::id()));
+            // SAFETY: runs once, before any test reads the environment through `cfg()`.
+            unsafe {
+                std::env::set_var("ROWLINK_CONFIG", dir.join("config.yaml"));
+                std::env::set_var("ROWLINK_STATE_DIR", &dir);
+                std::env::set_var("ROWLINK_SOCKET", dir.join("rowlink.sock"));
+                std::env::remove_var("HYPRLAND_INSTANCE_SIGNATURE");
+                std::env::remove_var("SWAYSOCK");
+            }
+            // The grid layout steps `main` runs on startup.
+            let mut configs = crate::load_config();
+            for config in &mut configs {
+                config.apply_grid_layout();
+                config.apply_auto_grid();
+                config.build_label_table();
+            }
+            let _ = crate::CONFIG.set(configs);
+        });
+        let keys: Vec<String> = keys.iter().map(|key| key.to_string()).collect();
+        run(&keys).unwrap()
+    }
+
+    fn moved_to(x: i32, y: i32) -> Vec<PointerEvent> {
+        vec![
+            PointerEvent::Move(-10000, -10000, Coordinate::Rel),
+            PointerEvent::Move(x, y, Coordinate::Rel),
+        ]
+    }
+
+    fn click() -> PointerEvent {
+        PointerEvent::Button(Button::Left, Direction::Click)
+    }
+
+    #[test]
+    fn main_label_and_sub_key_click_inside_the_cell() {
+        let mut expected = moved_to(41, 21);
+        expected.push(click());
+        assert_eq!(run_keys(&["a", "a", "j"]), expected);
+        assert_eq!(run_keys(&["shift+a", "a", "j"]), expected);
+    }
+
+    #[test]
+    fn last_cell_reaches_the_far_corner() {
+        let mut expected = moved_to(1887, 1059);
+        expected.push(click());
+        assert_eq!(run_keys(&["z", "z", "j"]), expected);
+    }
+
+    #[test]
+    fn move_mode_clicks_where_the_pointer_is() {
+        let mut expected = moved_to(960, 540);
+        expected.push(click());
+        assert_eq!(run_keys(&["'", "space"]), expected);
+        expected.push(click());
+        assert_eq!(run_keys(&["'", "shift+enter"]), expected);
+    }
+
+    #[test]
+    fn coarse_keys_warp_to_a_ninth_then_a_ninth_of_it() {
+        assert_eq!(run_keys(&["tab", "s", "enter"]), moved_to(960, 540));
+        let mut expected = moved_to(960, 540);
+        expected.extend(moved_to(747, 420));
+        assert_eq!(run_keys(&["tab", "s", "q"]), expected);
+    }
+
+    #[test]
+    fn unfinished_or_cancelled_selections_leave_the_pointer_alone() {
+        assert_eq!(run_keys(&["a", "b"]), Vec::new());
+        assert_eq!(run_keys(&["a", "escape"]), Vec::new());
+    }
+
+    #[test]
+    fn unknown_keys_are_rejected() {
+        assert!(run(&["ctrl+nokey".to_string()]).is_err());
+    }
+}
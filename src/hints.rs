@@ -0,0 +1,200 @@
+use crate::{HALF, HINT_ALPHABET, cfg, compositor, label_char, labels, marks};
+use serde::{Deserialize, Serialize};
+
+// --- Hint Providers ---
+// Hint mode (`hotspot_key`) labels the targets of every provider in `hint_providers`, in
+// that order, so earlier providers get the shorter labels. A new targeting source is one
+// more `HintProvider`; sources rowlink doesn't build in, such as AT-SPI or OCR, plug in
+// through `command`, which reads hints as JSON from a script's output.
+
+/// One labelled target: a point, or a rectangle clicked at its center.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct Hint {
+    pub name: String,
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+    /// Hint key; assigned from `HINT_ALPHABET` when omitted.
+    pub key: Option<char>,
+}
+
+impl Hint {
+    pub fn center(&self) -> (f32, f32) {
+        (self.x + self.width / HALF, self.y + self.height / HALF)
+    }
+}
+
+pub trait HintProvider {
+    fn hints(&self) -> Vec<Hint>;
+}
+
+/// An entry of `hint_providers`, e.g. `[hotspots, windows, { command: "ocr-hints" }]`.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(try_from = "RawSource", into = "RawSource")]
+pub enum Source {
+    /// The configured `hotspots`.
+    Hotspots,
+    /// The saved marks.
+    Marks,
+    /// The compositor's visible windows.
+    Windows,
+    /// A shell command printing a JSON array of `{name, x, y, width?, height?, key?}`.
+    Command(String),
+}
+
+/// `Source` as written: serde_yaml only reads `command` as a map through an untagged enum.
+#[derive(Deserialize, Serialize)]
+#[serde(untagged)]
+enum RawSource {
+    Name(String),
+    Command { command: String },
+}
+
+impl TryFrom<RawSource> for Source {
+    type Error = String;
+
+    fn try_from(raw: RawSource) -> Result<Self, String> {
+        match raw {
+            RawSource::Command { command } => Ok(Source::Command(command)),
+            RawSource::Name(name) => match name.as_str() {
+                "hotspots" => Ok(Source::Hotspots),
+                "marks" => Ok(Source::Marks),
+                "windows" => Ok(Source::Windows),
+                _ => Err(format!("unknown hint provider {:?}", name)),
+            },
+        }
+    }
+}
+
+impl From<Source> for RawSource {
+    fn from(source: Source) -> Self {
+        let name = match source {
+            Source::Command(command) => return RawSource::Command { command },
+            Source::Hotspots => "hotspots",
+            Source::Marks => "marks",
+            Source::Windows => "windows",
+        };
+        RawSource::Name(name.to_string())
+    }
+}
+
+impl Source {
+    fn provider(&self) -> Box<dyn HintProvider> {
+        match self {
+            Source::Hotspots => Box::new(Hotspots),
+            Source::Marks => Box::new(Marks),
+            Source::Windows => Box::new(Windows),
+            Source::Command(command) => Box::new(Command(command.clone())),
+        }
+    }
+}
+
+struct Hotspots;
+
+impl HintProvider for Hotspots {
+    fn hints(&self) -> Vec<Hint> {
+        cfg()
+            .hotspots
+            .iter()
+            .map(|hotspot| Hint {
+                name: hotspot.name.clone(),
+                x: hotspot.x,
+                y: hotspot.y,
+                width: hotspot.width,
+                height: hotspot.height,
+                key: hotspot.key,
+            })
+            .collect()
+    }
+}
+
+struct Marks;
+
+impl HintProvider for Marks {
+    fn hints(&self) -> Vec<Hint> {
+        marks::all()
+            .into_iter()
+            .map(|mark| Hint {
+                name: mark.name,
+                x: mark.x,
+                y: mark.y,
+                ..Default::default()
+            })
+            .collect()
+    }
+}
+
+struct Windows;
+
+impl HintProvider for Windows {
+    fn hints(&self) -> Vec<Hint> {
+        compositor::toplevels()
+            .into_iter()
+            .filter(|t| t.is_on_output(cfg().screen_width, cfg().screen_height))
+            .map(|toplevel| Hint {
+                name: toplevel.app_id,
+                x: toplevel.x,
+                y: toplevel.y,
+                width: toplevel.width,
+                height: toplevel.height,
+                key: None,
+            })
+            .collect()
+    }
+}
+
+struct Command(String);
+
+impl HintProvider for Command {
+    fn hints(&self) -> Vec<Hint> {
+        let output = match std::process::Command::new("sh")
+            .args(["-c", &self.0])
+            .output()
+        {
+            Ok(output) if output.status.success() => output.stdout,
+            Ok(output) => {
+                eprintln!("Hint command {:?} failed: {}", self.0, output.status);
+                return Vec::new();
+            }
+            Err(e) => {
+                eprintln!("Hint command {:?} failed: {:?}", self.0, e);
+                return Vec::new();
+            }
+        };
+        serde_json::from_slice(&output).unwrap_or_else(|e| {
+            eprintln!("Ignoring hints from {:?}: {}", self.0, e);
+            Vec::new()
+        })
+    }
+}
+
+/// Hints of every provider, labelled. Explicit keys win; the rest get the shortest labels
+/// left over from `HINT_ALPHABET`, so few hints need one keystroke each and many need two.
+pub fn collect() -> Vec<(String, Hint)> {
+    let hints: Vec<Hint> = cfg()
+        .hint_providers
+        .iter()
+        .flat_map(|source| source.provider().hints())
+        .collect();
+    let taken: Vec<char> = hints.iter().filter_map(|h| h.key.map(label_char)).collect();
+    let free: Vec<char> = HINT_ALPHABET
+        .chars()
+        .filter(|c| !taken.contains(c))
+        .collect();
+    let unkeyed = hints.iter().filter(|h| h.key.is_none()).count();
+    let mut generated = labels::hint_labels(unkeyed, &free).into_iter();
+
+    let mut labelled = Vec::new();
+    for hint in hints {
+        let label = match hint.key {
+            Some(k) => Some(label_char(k).to_string()),
+            None => generated.next(),
+        };
+        if let Some(label) = label {
+            labelled.push((label, hint));
+        }
+    }
+    labelled
+}
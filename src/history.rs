@@ -0,0 +1,82 @@
+use crate::paths;
+use serde::{Deserialize, Serialize};
+
+// --- Click History ---
+// How often each cell was clicked, per application (the one focused when the grid opened),
+// grid size and grid page, kept in `history.json` in the state directory. Adaptive hints put
+// shortcuts on an application's most frequent targets; `rowlink heatmap` draws them all.
+
+pub const FILE: &str = "history.json";
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct History {
+    targets: Vec<Target>,
+}
+
+/// A clicked main cell, or a sub-cell of it, and how often it was clicked.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Target {
+    pub app: String,
+    /// Rows and columns of the main grid the cell belongs to.
+    pub grid: (i32, i32),
+    /// Page of the grid, with `grid_pages` above 1; the same cell is elsewhere on others.
+    #[serde(default)]
+    pub page: u32,
+    pub cell: (i32, i32),
+    pub sub_cell: Option<(i32, i32)>,
+    pub count: u32,
+}
+
+impl History {
+    /// The saved history, if any.
+    pub fn load() -> Self {
+        paths::read_state(FILE)
+    }
+
+    /// Counts one click and saves.
+    pub fn record(
+        &mut self,
+        app: &str,
+        grid: (i32, i32),
+        page: u32,
+        cell: (i32, i32),
+        sub_cell: Option<(i32, i32)>,
+    ) {
+        let known = self.targets.iter_mut().find(|target| {
+            target.app == app
+                && target.grid == grid
+                && target.page == page
+                && target.cell == cell
+                && target.sub_cell == sub_cell
+        });
+        match known {
+            Some(target) => target.count += 1,
+            None => self.targets.push(Target {
+                app: app.to_string(),
+                grid,
+                page,
+                cell,
+                sub_cell,
+                count: 1,
+            }),
+        }
+        paths::write_state(FILE, self);
+    }
+
+    /// Every target on a `grid`, on all its pages, of all apps or only `app`.
+    pub fn targets(&self, app: Option<&str>, grid: (i32, i32)) -> Vec<&Target> {
+        self.targets
+            .iter()
+            .filter(|target| target.grid == grid && app.is_none_or(|app| target.app == app))
+            .collect()
+    }
+
+    /// The `count` most clicked targets of `app` on one `page` of a `grid`, most frequent
+    /// first.
+    pub fn top(&self, app: &str, grid: (i32, i32), page: u32, count: usize) -> Vec<Target> {
+        let mut targets = self.targets(Some(app), grid);
+        targets.retain(|target| target.page == page);
+        targets.sort_by_key(|target| std::cmp::Reverse(target.count));
+        targets.into_iter().take(count).cloned().collect()
+    }
+}
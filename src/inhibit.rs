@@ -0,0 +1,162 @@
+use crate::paths;
+use std::os::fd::AsFd;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering};
+use wayland_client::globals::{GlobalListContents, registry_queue_init};
+use wayland_client::protocol::{
+    wl_buffer, wl_compositor, wl_region, wl_registry, wl_shm, wl_shm_pool, wl_surface,
+};
+use wayland_client::{Connection, Dispatch, EventQueue, QueueHandle, delegate_noop};
+use wayland_protocols::wp::idle_inhibit::zv1::client::{
+    zwp_idle_inhibit_manager_v1, zwp_idle_inhibitor_v1,
+};
+use wayland_protocols_wlr::layer_shell::v1::client::{zwlr_layer_shell_v1, zwlr_layer_surface_v1};
+
+// --- Idle Inhibit ---
+// Keeps the session from dimming or locking while the grid is on screen. The idle-inhibit
+// protocol ties an inhibitor to a visible surface and the layer-shell toolkit doesn't
+// expose the overlay's, so rowlink maps a 1x1 transparent layer surface without input on
+// a connection of its own and inhibits on that. Closing the connection destroys both, also
+// when rowlink dies with the grid up.
+
+struct Surface;
+
+delegate_noop!(Surface: wl_compositor::WlCompositor);
+delegate_noop!(Surface: wl_region::WlRegion);
+delegate_noop!(Surface: wl_shm_pool::WlShmPool);
+delegate_noop!(Surface: zwlr_layer_shell_v1::ZwlrLayerShellV1);
+delegate_noop!(Surface: zwp_idle_inhibit_manager_v1::ZwpIdleInhibitManagerV1);
+delegate_noop!(Surface: zwp_idle_inhibitor_v1::ZwpIdleInhibitorV1);
+delegate_noop!(Surface: ignore wl_buffer::WlBuffer);
+delegate_noop!(Surface: ignore wl_shm::WlShm);
+delegate_noop!(Surface: ignore wl_surface::WlSurface);
+
+impl Dispatch<wl_registry::WlRegistry, GlobalListContents> for Surface {
+    fn event(
+        _: &mut Self,
+        _: &wl_registry::WlRegistry,
+        _: wl_registry::Event,
+        _: &GlobalListContents,
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+    }
+}
+
+impl Dispatch<zwlr_layer_surface_v1::ZwlrLayerSurfaceV1, ()> for Surface {
+    fn event(
+        _: &mut Self,
+        layer_surface: &zwlr_layer_surface_v1::ZwlrLayerSurfaceV1,
+        event: zwlr_layer_surface_v1::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        if let zwlr_layer_surface_v1::Event::Configure { serial, .. } = event {
+            layer_surface.ack_configure(serial);
+        }
+    }
+}
+
+/// The connection the surface and its inhibitor live on.
+struct Inhibitor {
+    _connection: Connection,
+    _queue: EventQueue<Surface>,
+}
+
+static INHIBITOR: Mutex<Option<Inhibitor>> = Mutex::new(None);
+/// Whether the grid still wants an inhibitor once one is set up.
+static WANTED: AtomicBool = AtomicBool::new(false);
+
+/// Starts inhibiting idle, in the background as it takes a few Wayland round trips.
+pub fn acquire() {
+    WANTED.store(true, Ordering::Relaxed);
+    if INHIBITOR
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .is_some()
+    {
+        return;
+    }
+    std::thread::spawn(|| {
+        let inhibitor = match inhibit() {
+            Ok(inhibitor) => inhibitor,
+            Err(e) => {
+                eprintln!("Idle inhibit failed: {}", e);
+                return;
+            }
+        };
+        let mut slot = INHIBITOR.lock().unwrap_or_else(|e| e.into_inner());
+        // Dropped right away when the grid was hidden meanwhile.
+        if WANTED.load(Ordering::Relaxed) && slot.is_none() {
+            *slot = Some(inhibitor);
+        }
+    });
+}
+
+pub fn release() {
+    WANTED.store(false, Ordering::Relaxed);
+    INHIBITOR.lock().unwrap_or_else(|e| e.into_inner()).take();
+}
+
+fn inhibit() -> Result<Inhibitor, String> {
+    let connection = Connection::connect_to_env().map_err(|e| e.to_string())?;
+    let (globals, mut queue) =
+        registry_queue_init::<Surface>(&connection).map_err(|e| e.to_string())?;
+    let qh = queue.handle();
+    let compositor: wl_compositor::WlCompositor =
+        globals.bind(&qh, 1..=4, ()).map_err(|e| e.to_string())?;
+    let shm: wl_shm::WlShm = globals.bind(&qh, 1..=1, ()).map_err(|e| e.to_string())?;
+    let layer_shell: zwlr_layer_shell_v1::ZwlrLayerShellV1 =
+        globals.bind(&qh, 1..=4, ()).map_err(|e| e.to_string())?;
+    let manager: zwp_idle_inhibit_manager_v1::ZwpIdleInhibitManagerV1 =
+        globals.bind(&qh, 1..=1, ()).map_err(|e| e.to_string())?;
+
+    let surface = compositor.create_surface(&qh, ());
+    // An empty input region lets the pointer through to whatever is below.
+    surface.set_input_region(Some(&compositor.create_region(&qh, ())));
+    let layer_surface = layer_shell.get_layer_surface(
+        &surface,
+        None,
+        zwlr_layer_shell_v1::Layer::Overlay,
+        "rowlink-idle-inhibit".to_string(),
+        &qh,
+        (),
+    );
+    layer_surface.set_size(1, 1);
+    layer_surface
+        .set_anchor(zwlr_layer_surface_v1::Anchor::Top | zwlr_layer_surface_v1::Anchor::Left);
+    surface.commit();
+    // The first configure has to be acked before a buffer is attached.
+    queue.roundtrip(&mut Surface).map_err(|e| e.to_string())?;
+
+    surface.attach(Some(&transparent_pixel(&shm, &qh)?), 0, 0);
+    manager.create_inhibitor(&surface, &qh, ());
+    surface.commit();
+    queue.roundtrip(&mut Surface).map_err(|e| e.to_string())?;
+    Ok(Inhibitor {
+        _connection: connection,
+        _queue: queue,
+    })
+}
+
+/// A 1x1 ARGB buffer, all zero and so fully transparent.
+fn transparent_pixel(
+    shm: &wl_shm::WlShm,
+    qh: &QueueHandle<Surface>,
+) -> Result<wl_buffer::WlBuffer, String> {
+    let path = paths::socket().with_file_name(format!("rowlink-pixel-{}", std::process::id()));
+    let file = std::fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(&path)
+        .map_err(|e| e.to_string())?;
+    let _ = std::fs::remove_file(&path);
+    file.set_len(4).map_err(|e| e.to_string())?;
+    let pool = shm.create_pool(file.as_fd(), 4, qh, ());
+    let buffer = pool.create_buffer(0, 1, 1, 4, wl_shm::Format::Argb8888, qh, ());
+    pool.destroy();
+    Ok(buffer)
+}
@@ -0,0 +1,282 @@
+use iced::futures::channel::mpsc::{UnboundedReceiver, UnboundedSender, unbounded};
+use serde_json::{Value, json};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::os::unix::net::UnixStream;
+use std::sync::Mutex;
+
+// --- Control Socket ---
+// Line-based protocol: the client writes one command line and reads the reply until EOF.
+//   list           -> named targets, one per line
+//   pick <entry>   -> "ok" or "error: ..."
+//   repeat         -> "ok"; replays the last click, scroll or drag without the grid
+//   type <text>    -> "ok"; opens the grid and types <text> after the chosen click
+//   quick          -> "ok"; opens the quick targets, like SIGUSR2
+//   profile        -> profile names, the active one marked with `*`
+//   profile <name> -> "ok" or "error: ..."; switches the active profile
+//   theme          -> theme names, the active one marked with `*`
+//   theme <name>   -> "ok" or "error: ..."; recolors the grid
+//   private        -> "on" or "off"
+//   private on|off -> "ok"; stops or resumes recording click history and offsets
+//   purge          -> "ok"; deletes the click history, learned offsets and marks
+//   mark [list]    -> marks as "<name> <x> <y>", one per line
+//   mark add <name> [<x> <y>]
+//                  -> "ok"; marks the point, or where rowlink last put the pointer
+//   mark remove <name>
+//                  -> "ok" or "error: ..."
+//   mark reload    -> "ok"; rereads the saved marks, e.g. after `rowlink import`
+//   subscribe      -> "ok", then keeps the connection open and writes one JSON object per
+//                     state change: {"event": "shown", "mode": "grid"}, {"event": "drawn",
+//                     "ms": 40} for the time from activation to the first frame,
+//                     {"event": "zoomed",
+//                     "row": 2, "col": 5, "depth": 1}, {"event": "cell_selected", "x": ..,
+//                     "y": ..},
+//                     {"event": "clicked", "button": "left", "count": 1, "x": .., "y": ..},
+//                     {"event": "hidden"}
+//
+// A line starting with `{` is a JSON-RPC 2.0 request instead. The methods are the
+// commands above, plus `get_capabilities` for the protocol version and method list.
+// `params`, if given, is an array of strings joined into the command's argument, e.g.
+//   {"jsonrpc": "2.0", "id": 1, "method": "mark", "params": ["add", "inbox"]}
+// The result is the reply's lines as an array of strings; an `error: ...` reply becomes
+// a JSON-RPC error with code -32000. Requests without an `id` get no response. After a
+// JSON-RPC `subscribe`, events arrive as notifications: {"method": "event", "params": {..}}.
+
+/// Sends one command to the running daemon and returns its reply.
+pub fn send(command: &str) -> std::io::Result<String> {
+    let mut stream = UnixStream::connect(crate::paths::socket())?;
+    stream.write_all(command.as_bytes())?;
+    stream.write_all(b"\n")?;
+    stream.shutdown(std::net::Shutdown::Write)?;
+    let mut reply = String::new();
+    stream.read_to_string(&mut reply)?;
+    Ok(reply)
+}
+
+/// Prints the daemon's events as they happen, until it exits.
+pub fn follow() -> std::io::Result<()> {
+    let mut stream = UnixStream::connect(crate::paths::socket())?;
+    stream.write_all(b"subscribe\n")?;
+    // Skips the "ok".
+    for line in BufReader::new(stream).lines().skip(1) {
+        println!("{}", line?);
+    }
+    Ok(())
+}
+
+/// Version of the JSON-RPC surface; bumped when a method changes incompatibly.
+pub const PROTOCOL_VERSION: u64 = 1;
+
+/// The commands, which are also the JSON-RPC method names.
+pub const COMMANDS: &[&str] = &[
+    "list", "pick", "repeat", "type", "quick", "profile", "theme", "private", "purge", "mark",
+];
+
+/// A JSON-RPC request reduced to a command line.
+#[derive(Debug)]
+pub struct Request {
+    /// `None` for a notification, which gets no response.
+    pub id: Option<Value>,
+    pub method: String,
+    pub argument: String,
+}
+
+/// Parses one JSON-RPC request; on failure, the error response to send.
+pub fn parse_request(line: &str) -> Result<Request, String> {
+    let request: Value = serde_json::from_str(line)
+        .map_err(|e| respond(Some(Value::Null), Err((-32700, e.to_string()))))?;
+    let id = request.get("id").cloned();
+    let invalid = |message: &str| respond(Some(Value::Null), Err((-32600, message.to_string())));
+    if request["jsonrpc"] != "2.0" {
+        return Err(invalid("jsonrpc must be \"2.0\""));
+    }
+    let Some(method) = request["method"].as_str() else {
+        return Err(invalid("method must be a string"));
+    };
+    let argument = match &request["params"] {
+        Value::Null => String::new(),
+        Value::Array(params) => {
+            match params.iter().map(Value::as_str).collect::<Option<Vec<_>>>() {
+                Some(words) => words.join(" "),
+                None => {
+                    let message = "params must be strings".to_string();
+                    return Err(respond(id, Err((-32602, message))));
+                }
+            }
+        }
+        _ => {
+            return Err(respond(
+                id,
+                Err((-32602, "params must be an array".to_string())),
+            ));
+        }
+    };
+    Ok(Request {
+        id,
+        method: method.to_string(),
+        argument,
+    })
+}
+
+pub fn capabilities() -> Value {
+    let mut methods = vec!["get_capabilities", "subscribe"];
+    methods.extend(COMMANDS);
+    json!({ "protocol_version": PROTOCOL_VERSION, "methods": methods })
+}
+
+pub fn method_not_found(method: &str) -> (i64, String) {
+    (-32601, format!("no method named {:?}", method))
+}
+
+/// A text reply as a JSON-RPC result: its lines, or an error for `error: ...`.
+pub fn reply_result(reply: &str) -> Result<Value, (i64, String)> {
+    match reply.strip_prefix("error: ") {
+        Some(message) => Err((-32000, message.trim_end().to_string())),
+        None => Ok(reply.lines().collect::<Vec<_>>().into()),
+    }
+}
+
+/// One response line; empty for notifications.
+pub fn respond(id: Option<Value>, result: Result<Value, (i64, String)>) -> String {
+    let Some(id) = id else {
+        return String::new();
+    };
+    let response = match result {
+        Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+        Err((code, message)) => json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "error": { "code": code, "message": message },
+        }),
+    };
+    format!("{}\n", response)
+}
+
+// --- Event Stream ---
+
+/// Connections that asked for events; true for those that subscribed over JSON-RPC.
+static SUBSCRIBERS: Mutex<Vec<(bool, UnboundedSender<String>)>> = Mutex::new(Vec::new());
+
+/// If `line` is a `subscribe` request, the reply to send and the event lines that follow.
+pub fn subscribe(line: &str) -> Option<(String, UnboundedReceiver<String>)> {
+    let (json_rpc, reply) = if line == "subscribe" {
+        (false, "ok\n".to_string())
+    } else if line.starts_with('{') {
+        let request = parse_request(line)
+            .ok()
+            .filter(|r| r.method == "subscribe")?;
+        let reply = respond(request.id, Ok(json!(["ok"])));
+        (true, reply)
+    } else {
+        return None;
+    };
+    let (sender, receiver) = unbounded();
+    let mut subscribers = SUBSCRIBERS.lock().unwrap_or_else(|e| e.into_inner());
+    subscribers.push((json_rpc, sender));
+    Some((reply, receiver))
+}
+
+/// Sends `event` to every subscriber, dropping the ones that went away, and announces it.
+pub fn emit(event: Value) {
+    crate::announce::event(&event);
+    let mut subscribers = SUBSCRIBERS.lock().unwrap_or_else(|e| e.into_inner());
+    if subscribers.is_empty() {
+        return;
+    }
+    let line = format!("{}\n", event);
+    let notification = format!(
+        "{}\n",
+        json!({ "jsonrpc": "2.0", "method": "event", "params": event })
+    );
+    subscribers.retain(|(json_rpc, sender)| {
+        let line = if *json_rpc { &notification } else { &line };
+        sender.unbounded_send(line.clone()).is_ok()
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The JSON object on a response line.
+    fn parsed(line: &str) -> Value {
+        assert!(line.ends_with('\n'));
+        serde_json::from_str(line).expect("responses are JSON")
+    }
+
+    #[test]
+    fn requests_become_command_lines() {
+        let line = r#"{"jsonrpc": "2.0", "id": 7, "method": "mark", "params": ["add", "inbox"]}"#;
+        let request = parse_request(line).expect("valid request");
+        assert_eq!(request.id, Some(json!(7)));
+        assert_eq!(request.method, "mark");
+        assert_eq!(request.argument, "add inbox");
+
+        let request = parse_request(r#"{"jsonrpc": "2.0", "id": "a", "method": "list"}"#)
+            .expect("params are optional");
+        assert_eq!(request.id, Some(json!("a")));
+        assert_eq!(request.argument, "");
+    }
+
+    #[test]
+    fn malformed_requests_get_error_responses() {
+        let error = parse_request("{not json").expect_err("parse error");
+        assert_eq!(parsed(&error)["error"]["code"], -32700);
+        assert_eq!(parsed(&error)["id"], Value::Null);
+
+        for line in [
+            r#"{"id": 1, "method": "list"}"#,
+            r#"{"jsonrpc": "1.0", "id": 1, "method": "list"}"#,
+            r#"{"jsonrpc": "2.0", "id": 1}"#,
+            r#"{"jsonrpc": "2.0", "id": 1, "method": 3}"#,
+        ] {
+            let error = parse_request(line).expect_err(line);
+            assert_eq!(parsed(&error)["error"]["code"], -32600, "{}", line);
+            assert_eq!(parsed(&error)["id"], Value::Null, "{}", line);
+        }
+
+        for line in [
+            r#"{"jsonrpc": "2.0", "id": 2, "method": "pick", "params": [1]}"#,
+            r#"{"jsonrpc": "2.0", "id": 2, "method": "pick", "params": {"entry": "a"}}"#,
+        ] {
+            let error = parse_request(line).expect_err(line);
+            assert_eq!(parsed(&error)["error"]["code"], -32602, "{}", line);
+            assert_eq!(parsed(&error)["id"], 2, "{}", line);
+        }
+    }
+
+    #[test]
+    fn notifications_get_no_response() {
+        let request =
+            parse_request(r#"{"jsonrpc": "2.0", "method": "purge"}"#).expect("valid notification");
+        assert_eq!(request.id, None);
+        assert_eq!(respond(request.id, Ok(json!(["ok"]))), "");
+        assert_eq!(respond(None, Err(method_not_found("nope"))), "");
+        // Invalid params are only reported to requests that have an id.
+        let line = r#"{"jsonrpc": "2.0", "method": "pick", "params": [1]}"#;
+        assert_eq!(parse_request(line).err(), Some(String::new()));
+    }
+
+    #[test]
+    fn replies_become_results_or_errors() {
+        assert_eq!(reply_result("ok\n"), Ok(json!(["ok"])));
+        assert_eq!(
+            reply_result("a 1 2\nb 3 4\n"),
+            Ok(json!(["a 1 2", "b 3 4"]))
+        );
+        assert_eq!(reply_result(""), Ok(json!([])));
+        assert_eq!(
+            reply_result("error: no mark named \"x\"\n"),
+            Err((-32000, "no mark named \"x\"".to_string()))
+        );
+
+        let response = parsed(&respond(Some(json!(3)), reply_result("ok\n")));
+        assert_eq!(
+            response,
+            json!({ "jsonrpc": "2.0", "id": 3, "result": ["ok"] })
+        );
+        let response = parsed(&respond(Some(json!(4)), Err(method_not_found("nope"))));
+        assert_eq!(response["id"], 4);
+        assert_eq!(response["error"]["code"], -32601);
+        assert_eq!(response["error"]["message"], "no method named \"nope\"");
+    }
+}
@@ -0,0 +1,180 @@
+use std::collections::HashSet;
+use std::os::fd::OwnedFd;
+use std::os::unix::fs::FileExt;
+use wayland_client::globals::{GlobalListContents, registry_queue_init};
+use wayland_client::protocol::{wl_keyboard, wl_registry, wl_seat};
+use wayland_client::{Connection, Dispatch, Proxy, QueueHandle, WEnum};
+use xkbcommon::xkb;
+
+// --- Keymap ---
+// Sub-grid keys follow the active xkb layout. `sub_labels` are written for the first
+// layout of the seat's keymap; under any other layout of it each label becomes what its key
+// types there, so the sub-grid keeps its shape on the keyboard after a layout switch.
+// `layout_sub_labels` entries override this per layout.
+
+/// Collects the keymap the compositor sends with a new keyboard.
+#[derive(Default)]
+struct Seat {
+    keymap: Option<(OwnedFd, u32)>,
+}
+
+impl Dispatch<wl_registry::WlRegistry, GlobalListContents> for Seat {
+    fn event(
+        _: &mut Self,
+        _: &wl_registry::WlRegistry,
+        _: wl_registry::Event,
+        _: &GlobalListContents,
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+    }
+}
+
+impl Dispatch<wl_seat::WlSeat, ()> for Seat {
+    fn event(
+        _: &mut Self,
+        _: &wl_seat::WlSeat,
+        _: wl_seat::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+    }
+}
+
+impl Dispatch<wl_keyboard::WlKeyboard, ()> for Seat {
+    fn event(
+        seat: &mut Self,
+        _: &wl_keyboard::WlKeyboard,
+        event: wl_keyboard::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        if let wl_keyboard::Event::Keymap {
+            format: WEnum::Value(wl_keyboard::KeymapFormat::XkbV1),
+            fd,
+            size,
+        } = event
+        {
+            seat.keymap = Some((fd, size));
+        }
+    }
+}
+
+/// The seat's keymap, with all its layouts, as clients get it. Takes a Wayland round trip.
+fn current() -> Option<xkb::Keymap> {
+    let connection = Connection::connect_to_env().ok()?;
+    let (globals, mut queue) = registry_queue_init::<Seat>(&connection).ok()?;
+    let seat: wl_seat::WlSeat = globals.bind(&queue.handle(), 1..=7, ()).ok()?;
+    let keyboard = seat.get_keyboard(&queue.handle(), ());
+    let mut state = Seat::default();
+    queue.roundtrip(&mut state).ok()?;
+    if keyboard.version() >= 3 {
+        keyboard.release();
+    }
+    let (fd, size) = state.keymap?;
+    let mut text = vec![0; size as usize];
+    // Read at an offset: the compositor may hand every client the same file.
+    std::fs::File::from(fd).read_exact_at(&mut text, 0).ok()?;
+    let text = String::from_utf8(text).ok()?;
+    xkb::Keymap::new_from_string(
+        &xkb::Context::new(xkb::CONTEXT_NO_FLAGS),
+        text.trim_end_matches('\0').to_string(),
+        xkb::KEYMAP_FORMAT_TEXT_V1,
+        xkb::KEYMAP_COMPILE_NO_FLAGS,
+    )
+}
+
+/// `labels` as their keys type them under the layout named `layout`. None under the first
+/// layout, without a keymap, or when some key types nothing or the same as another.
+pub fn sub_labels(layout: &str, labels: &[String]) -> Option<Vec<String>> {
+    translate(&current()?, layout, labels)
+}
+
+fn translate(keymap: &xkb::Keymap, layout: &str, labels: &[String]) -> Option<Vec<String>> {
+    let index = (1..keymap.num_layouts()).find(|&i| keymap.layout_get_name(i) == layout)?;
+    let mut seen = HashSet::new();
+    labels
+        .iter()
+        .map(|row| {
+            row.chars()
+                .map(|c| {
+                    let key = key_typing(keymap, c)?;
+                    let typed = typed_char(keymap, key, index)?;
+                    seen.insert(typed).then_some(typed)
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// The key that types `c`, in either case, under the first layout.
+fn key_typing(keymap: &xkb::Keymap, c: char) -> Option<xkb::Keycode> {
+    let c = crate::label_char(c);
+    let mut found = None;
+    keymap.key_for_each(|keymap, key| {
+        if found.is_none() && typed_char(keymap, key, 0) == Some(c) {
+            found = Some(key);
+        }
+    });
+    found
+}
+
+/// What `key` types without modifiers under a layout, in a label's case.
+fn typed_char(keymap: &xkb::Keymap, key: xkb::Keycode, layout: u32) -> Option<char> {
+    let sym = *keymap.key_get_syms_by_level(key, layout, 0).first()?;
+    char::from_u32(xkb::keysym_to_utf32(sym))
+        .filter(|c| !c.is_control() && !c.is_whitespace())
+        .map(crate::label_char)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keymap(layouts: &str) -> xkb::Keymap {
+        xkb::Keymap::new_from_names(
+            &xkb::Context::new(xkb::CONTEXT_NO_FLAGS),
+            "evdev",
+            "pc105",
+            layouts,
+            "",
+            None,
+            xkb::KEYMAP_COMPILE_NO_FLAGS,
+        )
+        .expect("xkeyboard-config is installed")
+    }
+
+    fn rows(rows: &[&str]) -> Vec<String> {
+        rows.iter().map(|row| row.to_string()).collect()
+    }
+
+    #[test]
+    fn labels_follow_keys_into_other_layouts() {
+        let keymap = keymap("us,de");
+        let german = keymap.layout_get_name(1).to_string();
+        let labels = translate(&keymap, &german, &rows(&["QWERTY", "ASD", "ZXC"]));
+        assert_eq!(labels, Some(rows(&["QWERTZ", "ASD", "YXC"])));
+    }
+
+    #[test]
+    fn first_layout_keeps_configured_labels() {
+        let keymap = keymap("us,de");
+        let english = keymap.layout_get_name(0).to_string();
+        assert_eq!(translate(&keymap, &english, &rows(&["QWE"])), None);
+        assert_eq!(translate(&keymap, "Klingon", &rows(&["QWE"])), None);
+    }
+
+    #[test]
+    fn untypable_or_repeated_keys_drop_the_translation() {
+        let keymap = keymap("us,ru");
+        let russian = keymap.layout_get_name(1).to_string();
+        assert_eq!(
+            translate(&keymap, &russian, &rows(&["QWE"])),
+            Some(rows(&["ЙЦУ"]))
+        );
+        assert_eq!(translate(&keymap, &russian, &rows(&["Q☃"])), None);
+        assert_eq!(translate(&keymap, &russian, &rows(&["QW", "Q"])), None);
+    }
+}
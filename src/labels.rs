@@ -0,0 +1,297 @@
+use std::collections::HashMap;
+
+// --- Main Grid Label Table ---
+// Every cell's label, resolved once when the config is loaded. Cells default to their
+// row label followed by their column label; `cell_labels` can rename or blank them.
+
+#[derive(Debug, Clone, Default)]
+pub struct LabelTable {
+    cols: i32,
+    labels: Vec<Option<String>>,
+    /// Every character that appears in some label.
+    chars: Vec<char>,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Lookup {
+    Cell(i32, i32),
+    /// Typed characters start at least one label; wait for more.
+    Prefix,
+    NoMatch,
+}
+
+impl LabelTable {
+    /// `overrides` maps a cell's default label to its replacement; an empty replacement
+    /// blanks the cell so it is neither drawn nor selectable.
+    pub fn build(
+        rows: i32,
+        cols: i32,
+        default_label: impl Fn(i32, i32) -> String,
+        overrides: &HashMap<String, String>,
+    ) -> Self {
+        let mut labels = Vec::with_capacity((rows * cols) as usize);
+        for row in 0..rows {
+            for col in 0..cols {
+                let label = default_label(row, col);
+                let label = match overrides.get(&label) {
+                    Some(custom) => custom.trim().to_uppercase(),
+                    None => label,
+                };
+                labels.push((!label.is_empty()).then_some(label));
+            }
+        }
+        let mut chars: Vec<char> = labels.iter().flatten().flat_map(|l| l.chars()).collect();
+        chars.sort_unstable();
+        chars.dedup();
+        Self {
+            cols,
+            labels,
+            chars,
+        }
+    }
+
+    pub fn get(&self, row: i32, col: i32) -> Option<&str> {
+        if col >= self.cols {
+            return None;
+        }
+        self.labels
+            .get((row * self.cols + col) as usize)?
+            .as_deref()
+    }
+
+    pub fn uses_char(&self, c: char) -> bool {
+        self.chars.binary_search(&c).is_ok()
+    }
+
+    /// A label completes once typed in full. With `unique_prefix`, a prefix only one label
+    /// starts with completes that label early. Labels that start other labels, leaving
+    /// those unreachable, are config problems; see `conflicts`.
+    pub fn lookup(&self, typed: &str, unique_prefix: bool) -> Lookup {
+        let mut exact = None;
+        let mut extending = Vec::new();
+        for (index, label) in self.labels.iter().enumerate() {
+            let Some(label) = label else {
+                continue;
+            };
+            if label == typed {
+                exact = Some(index as i32);
+            } else if label.starts_with(typed) {
+                extending.push(index as i32);
+            }
+        }
+        let cell = |index: i32| Lookup::Cell(index / self.cols, index % self.cols);
+        match (exact, extending.as_slice()) {
+            (Some(index), _) => cell(index),
+            (None, [index]) if unique_prefix => cell(*index),
+            (None, []) => Lookup::NoMatch,
+            _ => Lookup::Prefix,
+        }
+    }
+
+    /// Labels that can't all be typed: one used by several cells, or one that starts
+    /// another. Only `cell_labels` overrides can cause these.
+    pub fn conflicts(&self) -> Vec<String> {
+        let mut labels: Vec<&str> = self.labels.iter().flatten().map(String::as_str).collect();
+        labels.sort_unstable();
+        // Any label between a label and one it starts also starts with it, so comparing
+        // neighbours finds every clash.
+        let mut conflicts: Vec<String> = labels
+            .windows(2)
+            .filter_map(|pair| match pair {
+                [a, b] if a == b => Some(format!("{:?} labels more than one cell", a)),
+                [a, b] if b.starts_with(a) => {
+                    Some(format!("{:?} starts {:?}, which can't be typed then", a, b))
+                }
+                _ => None,
+            })
+            .collect();
+        conflicts.dedup();
+        conflicts
+    }
+}
+
+/// Rank of each of `count` rows or columns by distance from the middle, so the center gets
+/// rank 0 and the two edges the last ranks.
+pub fn center_ranks(count: i32) -> Vec<i32> {
+    let mut order: Vec<i32> = (0..count).collect();
+    order.sort_by_key(|index| ((2 * index - (count - 1)).abs(), *index));
+    let mut ranks = vec![0; count as usize];
+    for (rank, index) in order.into_iter().enumerate() {
+        ranks[index as usize] = rank as i32;
+    }
+    ranks
+}
+
+/// Rows and columns of the blocks for two-key block labels over `keys` keys: blocks as
+/// square as possible, with at most `keys` blocks of at most `keys` cells each.
+pub fn block_size(rows: i32, cols: i32, keys: i32) -> Option<(i32, i32)> {
+    let mut best: Option<(i32, i32)> = None;
+    for block_rows in 1..=rows.min(keys) {
+        for block_cols in 1..=cols.min(keys / block_rows) {
+            let block_count =
+                ((rows + block_rows - 1) / block_rows) * ((cols + block_cols - 1) / block_cols);
+            if block_count > keys {
+                continue;
+            }
+            let squareness = |(r, c): (i32, i32)| ((r - c).abs(), -(r * c));
+            if best.is_none_or(|best| squareness((block_rows, block_cols)) < squareness(best)) {
+                best = Some((block_rows, block_cols));
+            }
+        }
+    }
+    best
+}
+
+/// Block sizes for three-key labels: cells in blocks of at most `keys` cells, and those
+/// blocks in turn grouped into at most `keys` blocks of at most `keys` blocks each.
+pub fn triple_block_sizes(rows: i32, cols: i32, keys: i32) -> Option<[(i32, i32); 2]> {
+    let mut inner: Vec<(i32, i32)> = (1..=rows.min(keys))
+        .flat_map(|r| (1..=cols.min(keys / r)).map(move |c| (r, c)))
+        .collect();
+    inner.sort_by_key(|&(r, c)| ((r - c).abs(), -(r * c)));
+    inner.into_iter().find_map(|(r, c)| {
+        let outer = block_size((rows + r - 1) / r, (cols + c - 1) / c, keys)?;
+        Some([(r, c), outer])
+    })
+}
+
+/// Block label of a cell: one key per block level, outermost first, then the cell within
+/// the innermost block. `levels` lists the block sizes innermost first, each level in
+/// units of the one before.
+pub fn nested_label(
+    row: i32,
+    col: i32,
+    cols: i32,
+    levels: &[(i32, i32)],
+    alphabet: &[char],
+) -> String {
+    let (mut row, mut col, mut cols) = (row, col, cols);
+    let mut label = Vec::new();
+    for &(block_rows, block_cols) in levels {
+        label.push(alphabet[((row % block_rows) * block_cols + col % block_cols) as usize]);
+        row /= block_rows;
+        col /= block_cols;
+        cols = (cols + block_cols - 1) / block_cols;
+    }
+    label.push(alphabet[(row * cols + col) as usize]);
+    label.into_iter().rev().collect()
+}
+
+// --- Hint Labels ---
+
+/// Shortest prefix-free labels for `count` targets, Vimium style: leaves of a trie over
+/// `alphabet` are split front to back until there are enough, so as many targets as
+/// possible keep a single keystroke and earlier alphabet characters come first.
+pub fn hint_labels(count: usize, alphabet: &[char]) -> Vec<String> {
+    if alphabet.len() < 2 {
+        return alphabet.iter().take(count).map(char::to_string).collect();
+    }
+    let mut leaves: std::collections::VecDeque<String> =
+        alphabet.iter().map(char::to_string).collect();
+    while leaves.len() < count {
+        let Some(parent) = leaves.pop_front() else {
+            break;
+        };
+        leaves.extend(alphabet.iter().map(|c| format!("{}{}", parent, c)));
+    }
+    leaves.into_iter().take(count).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn table(overrides: &[(&str, &str)]) -> LabelTable {
+        let overrides = overrides
+            .iter()
+            .map(|(from, to)| (from.to_string(), to.to_string()))
+            .collect();
+        let alphabet = ['A', 'B', 'C'];
+        LabelTable::build(
+            2,
+            3,
+            |row, col| format!("{}{}", alphabet[row as usize], alphabet[col as usize]),
+            &overrides,
+        )
+    }
+
+    #[test]
+    fn build_applies_and_blanks_overrides() {
+        let table = table(&[("AB", " x "), ("BC", "")]);
+        assert_eq!(table.get(0, 0), Some("AA"));
+        assert_eq!(table.get(0, 1), Some("X"));
+        assert_eq!(table.get(1, 2), None);
+        assert_eq!(table.get(0, 3), None);
+        assert_eq!(table.get(2, 0), None);
+        assert!(table.uses_char('X'));
+        assert!(!table.uses_char('Z'));
+    }
+
+    #[test]
+    fn lookup_completes_full_labels_and_waits_on_prefixes() {
+        let table = table(&[]);
+        assert_eq!(table.lookup("BA", false), Lookup::Cell(1, 0));
+        assert_eq!(table.lookup("B", false), Lookup::Prefix);
+        assert_eq!(table.lookup("C", false), Lookup::NoMatch);
+        assert_eq!(table.lookup("AZ", false), Lookup::NoMatch);
+    }
+
+    #[test]
+    fn lookup_takes_unique_prefixes_early() {
+        let table = table(&[("AA", "XY"), ("AB", "")]);
+        assert_eq!(table.lookup("X", true), Lookup::Cell(0, 0));
+        assert_eq!(table.lookup("X", false), Lookup::Prefix);
+        assert_eq!(table.lookup("A", true), Lookup::Cell(0, 2));
+        assert_eq!(table.lookup("B", true), Lookup::Prefix);
+    }
+
+    #[test]
+    fn lookup_picks_a_short_override_over_longer_labels() {
+        let table = table(&[("AC", "B")]);
+        assert_eq!(table.lookup("B", false), Lookup::Cell(0, 2));
+    }
+
+    #[test]
+    fn conflicts_report_duplicates_and_prefixes() {
+        assert!(table(&[("AA", "XY")]).conflicts().is_empty());
+        assert_eq!(
+            table(&[("AC", "B")]).conflicts(),
+            vec![r#""B" starts "BA", which can't be typed then"#]
+        );
+        assert_eq!(
+            table(&[("AC", "AA")]).conflicts(),
+            vec![r#""AA" labels more than one cell"#]
+        );
+    }
+
+    #[test]
+    fn center_ranks_start_in_the_middle() {
+        assert_eq!(center_ranks(5), vec![3, 1, 0, 2, 4]);
+        assert_eq!(center_ranks(4), vec![2, 0, 1, 3]);
+        assert_eq!(center_ranks(1), vec![0]);
+        assert!(center_ranks(0).is_empty());
+    }
+
+    #[test]
+    fn block_size_keeps_blocks_square_and_within_the_keys() {
+        assert_eq!(block_size(4, 4, 4), Some((2, 2)));
+        assert_eq!(block_size(9, 9, 9), Some((3, 3)));
+        assert_eq!(block_size(1, 6, 3), Some((1, 2)));
+        assert_eq!(block_size(10, 10, 4), None);
+    }
+
+    #[test]
+    fn nested_labels_name_the_block_then_the_cell() {
+        let alphabet = ['A', 'B', 'C', 'D'];
+        let levels = [(2, 2)];
+        assert_eq!(nested_label(0, 0, 4, &levels, &alphabet), "AA");
+        assert_eq!(nested_label(1, 1, 4, &levels, &alphabet), "AD");
+        assert_eq!(nested_label(0, 2, 4, &levels, &alphabet), "BA");
+        assert_eq!(nested_label(3, 3, 4, &levels, &alphabet), "DD");
+        let labels: std::collections::HashSet<String> = (0..4)
+            .flat_map(|row| (0..4).map(move |col| (row, col)))
+            .map(|(row, col)| nested_label(row, col, 4, &levels, &alphabet))
+            .collect();
+        assert_eq!(labels.len(), 16);
+    }
+}
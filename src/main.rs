@@ -1,5 +1,8 @@
-use enigo::{Button, Coordinate, Direction, Enigo, InputResult, Mouse, Settings as EnigoSettings};
-use iced::futures::sink::SinkExt;
+use enigo::{
+    Button, Coordinate, Direction, Enigo, InputResult, Keyboard, Mouse, Settings as EnigoSettings,
+};
+use iced::futures::channel::oneshot;
+use iced::futures::{SinkExt, StreamExt};
 use iced::widget::canvas::{self, Canvas, Style, Text};
 use iced::{
     Color, Element, Event, Fill, Font, Point, Rectangle, Renderer, Subscription, Theme, keyboard,
@@ -7,18 +10,73 @@ use iced::{
 };
 use iced_layershell::actions::ActionCallback;
 use iced_layershell::reexport::{
-    Anchor, IcedId, KeyboardInteractivity, Layer, NewLayerShellSettings,
+    Anchor, IcedId, KeyboardInteractivity, Layer, NewLayerShellSettings, OutputOption,
 };
 use iced_layershell::settings::{LayerShellSettings, Settings};
 use iced_layershell::{application, to_layer_message};
-use serde::Deserialize;
-use std::sync::OnceLock;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::io::IsTerminal;
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicUsize, Ordering};
+use std::sync::{OnceLock, RwLock};
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
 use tokio::signal::unix::{SignalKind, signal};
 
+mod announce;
+mod bindings;
+mod cli;
+mod compositor;
+mod density;
+mod geometry;
+#[cfg(feature = "headless")]
+mod headless;
+mod hints;
+mod history;
+mod inhibit;
+mod ipc;
+mod keymap;
+mod labels;
+mod marks;
+mod migrate;
+mod offsets;
+mod paths;
+mod picker;
+mod pointer;
+mod render;
+mod setup;
+mod transfer;
+#[cfg(feature = "uinput")]
+mod uinput;
+mod validate;
+#[cfg(feature = "websocket")]
+mod websocket;
+
 // --- Global Config Singleton ---
-static CONFIG: OnceLock<AppConfig> = OnceLock::new();
+// Every profile is resolved into a full config at startup; switching profiles only moves
+// the active index, so `cfg()` can keep handing out `&'static` references.
+static CONFIG: OnceLock<Vec<AppConfig>> = OnceLock::new();
+static ACTIVE_PROFILE: AtomicUsize = AtomicUsize::new(0);
+/// Theme picked through `rowlink theme`; `None` uses each profile's own colors.
+static ACTIVE_THEME: RwLock<Option<String>> = RwLock::new(None);
+/// Private mode: `private_mode` from the base config, then `rowlink private`.
+static PRIVATE: AtomicBool = AtomicBool::new(false);
+/// Page of the grid area shown, with `grid_pages` above 1.
+static ACTIVE_PAGE: AtomicU32 = AtomicU32::new(0);
+/// The overlay's output in the global layout, looked up each time the grid opens.
+static PLACEMENT: RwLock<geometry::Placement> = RwLock::new(geometry::Placement {
+    x: 0.0,
+    y: 0.0,
+    width: 0.0,
+    height: 0.0,
+    layout_x: 0.0,
+    layout_y: 0.0,
+    scale: 1.0,
+    reserved: (0.0, 0.0, 0.0, 0.0),
+    dpi: 0.0,
+});
 
-#[derive(Debug, Deserialize, Clone, Copy)]
+#[derive(Debug, Deserialize, Serialize, Clone, Copy)]
 struct ConfigColor {
     r: f32,
     g: f32,
@@ -37,93 +95,252 @@ impl ConfigColor {
     }
 }
 
-#[derive(Debug, Deserialize, Clone)]
-struct AppConfig {
-    screen_width: f32,
-    screen_height: f32,
-    main_grid_size: f32,
-    sub_rows: i32,
-    sub_cols: i32,
-    sub_padding: f32,
-    font_size: f32,
-    delay_surface_destroy_ms: u64,
-    delay_wayland_zero_ms: u64,
-    delay_wayland_move_ms: u64,
-    delay_double_click_ms: u64,
-    scroll_lines: i32,
-    scroll_page_lines: i32,
-    scroll_natural: bool,
-    // Colors
-    color_grid_border: ConfigColor,
-    color_main_text: ConfigColor,
-    color_sub_home_row: ConfigColor,
-    color_sub_default: ConfigColor,
-    color_row_highlight: ConfigColor,
-    color_text_dimmed: ConfigColor,
-    color_border_dimmed: ConfigColor,
-    // Labels (Dynamic 2D Grid)
-    sub_labels: Vec<String>,
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+enum Backend {
+    /// Virtual pointer through enigo, or the configured Sway seat.
+    Pointer,
+    /// Taps on a uinput touchscreen, for apps that only react to touch.
+    Touch,
+    /// Pen taps on a uinput tablet, for drawing apps that treat pen input specially.
+    Stylus,
 }
 
-impl AppConfig {
-    fn get_main_cell_size(&self, width: f32, height: f32) -> (f32, f32) {
-        (
-            width / self.main_grid_size,
-            height / self.main_grid_size,
-        )
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+enum RendererBackend {
+    /// iced's default: wgpu, falling back to software. `ICED_BACKEND` is honoured.
+    Auto,
+    /// wgpu.
+    Gpu,
+    /// tiny-skia on the CPU; often cheaper than a GPU context for a transparent grid.
+    Software,
+}
+
+impl RendererBackend {
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "auto" => Some(Self::Auto),
+            "gpu" => Some(Self::Gpu),
+            "software" => Some(Self::Software),
+            _ => None,
+        }
     }
 
-    fn get_main_cell_center(&self, width: f32, height: f32, row: i32, col: i32) -> (f32, f32) {
-        let (w, h) = self.get_main_cell_size(width, height);
-        (
-            (col as f32 * w) + (w / HALF),
-            (row as f32 * h) + (h / HALF),
-        )
+    /// Value for iced's `ICED_BACKEND` selection, if this forces one.
+    fn iced_backend(self) -> Option<&'static str> {
+        match self {
+            Self::Auto => None,
+            Self::Gpu => Some("wgpu"),
+            Self::Software => Some("tiny-skia"),
+        }
     }
+}
 
-    fn get_precision_target(
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+enum KeyboardMode {
+    /// Start with OnDemand and fall back to Exclusive if no keys arrive.
+    Auto,
+    OnDemand,
+    Exclusive,
+}
+
+/// Tremor filter for continuous movement: the direction is averaged over the last
+/// `window_ms`, and the pointer holds still while that average is weaker than
+/// `threshold` (0 to 1), so brief taps and flickering keys don't jerk it around.
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, Default)]
+#[serde(default)]
+struct Smoothing {
+    window_ms: u64,
+    threshold: f32,
+}
+
+impl Smoothing {
+    /// Records this tick's unit `direction` and returns the averaged one to move along.
+    fn filter(
         &self,
-        width: f32,
-        height: f32,
-        main_row: i32,
-        main_col: i32,
-        sub_row: i32,
-        sub_col: i32,
-    ) -> (f32, f32) {
-        let (cell_w, cell_h) = self.get_main_cell_size(width, height);
-        let main_x = main_col as f32 * cell_w;
-        let main_y = main_row as f32 * cell_h;
+        history: &mut std::collections::VecDeque<(Instant, (f32, f32))>,
+        now: Instant,
+        direction: (f32, f32),
+    ) -> Option<(f32, f32)> {
+        let window = Duration::from_millis(self.window_ms);
+        history.retain(|(at, _)| now.duration_since(*at) < window);
+        history.push_back((now, direction));
+        let count = history.len() as f32;
+        let (x, y) = history
+            .iter()
+            .fold((0.0, 0.0), |(x, y), (_, (dx, dy))| (x + dx, y + dy));
+        let (x, y) = (x / count, y / count);
+        let strength = (x * x + y * y).sqrt();
+        (strength > 0.0 && strength >= self.threshold).then_some((x, y))
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+enum NumpadStyle {
+    /// Each digit narrows the area to that ninth of it (7 top left, 3 bottom right, 0 the
+    /// center), down to any precision; 5 clicks the area's center.
+    Regions,
+    /// Each digit nudges the pointer `numpad_step` pixels that way; 5 clicks.
+    Directions,
+}
+
+/// Where in the final sub-cell or numpad region the click lands.
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+enum ClickPoint {
+    Center,
+    TopLeft,
+    /// `click_offset` pixels right of and below the top left corner, kept inside the
+    /// region; for checkboxes and tree expanders at the left of their row.
+    Offset,
+}
 
-        let sub_container_w = cell_w - (self.sub_padding * DOUBLE);
-        let sub_container_h = cell_h - (self.sub_padding * DOUBLE);
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+enum ScrollStyle {
+    /// Wheel clicks, one per line, from a uinput wheel device. Without access to
+    /// /dev/uinput the virtual pointer sends a click's worth of axis motion instead, which
+    /// some apps treat as smooth scrolling.
+    Discrete,
+    /// Continuous axis motion, `scroll_smooth_step` units per line. Only the virtual
+    /// pointer can send it; the other backends keep to wheel clicks.
+    Smooth,
+}
 
-        let sub_w = sub_container_w / self.sub_cols as f32;
-        let sub_h = sub_container_h / self.sub_rows as f32;
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+enum SubLayout {
+    /// Use `sub_labels`/`sub_rows`/`sub_cols` as configured.
+    Custom,
+    /// 4x3 on the left half of the keyboard (QWER/ASDF/ZXCV).
+    OneHandedLeft,
+    /// 4x3 on the right half of the keyboard (UIOP/JKL;/M,./).
+    OneHandedRight,
+}
 
-        let target_x = main_x + self.sub_padding + (sub_col as f32 * sub_w) + (sub_w / HALF);
-        let target_y = main_y + self.sub_padding + (sub_row as f32 * sub_h) + (sub_h / HALF);
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+enum GridLayout {
+    /// Use `main_grid_size`/`main_grid_rows`/`main_grid_cols` as configured.
+    Custom,
+    /// For 21:9 and 32:9 displays: the configured rows, at most one label key each, and
+    /// as many columns as keep cells square, which get two-key labels once there are more
+    /// columns than label keys.
+    Ultrawide,
+    /// For low vision: a 4x6 grid with large labels and thick lines, zooming in at least
+    /// three levels to reach small targets.
+    LowVision,
+}
 
-        (target_x, target_y)
+impl SubLayout {
+    fn labels(self) -> Option<&'static [&'static str]> {
+        match self {
+            SubLayout::Custom => None,
+            SubLayout::OneHandedLeft => Some(&["QWER", "ASDF", "ZXCV"]),
+            SubLayout::OneHandedRight => Some(&["UIOP", "JKL;", "M,./"]),
+        }
     }
 }
 
-impl Default for AppConfig {
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+enum LabelLayout {
+    /// Row label then column label.
+    RowColumn,
+    /// First key picks a block of cells, second key a cell in the block; both follow the
+    /// label key order, home row first unless `label_preference` says otherwise.
+    Blocks,
+    /// Blocks of blocks: three keys each, so even a 100x100 grid fits in one stage.
+    Triples,
+}
+
+/// The main grid laid out once for the configured screen size: every cell outline in one
+/// path and every label positioned, so building a static grid layer only copies them.
+#[derive(Debug, Clone)]
+struct MainGridLayout {
+    outline: canvas::Path,
+    labels: Vec<Text>,
+    /// The margins it was laid out within; panels may have changed them since.
+    margins: Margins,
+}
+
+/// A fixed, frequently clicked UI element. Width/height turn the point into a rectangle
+/// whose center is clicked.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+struct Hotspot {
+    name: String,
+    x: f32,
+    y: f32,
+    #[serde(default)]
+    width: f32,
+    #[serde(default)]
+    height: f32,
+    /// Hint key; assigned from `HINT_ALPHABET` when omitted.
+    key: Option<char>,
+}
+
+impl Hotspot {
+    fn center(&self) -> (f32, f32) {
+        (self.x + self.width / HALF, self.y + self.height / HALF)
+    }
+}
+
+/// Speed ramp for continuous movement, in pixels per second: starts at
+/// `initial_speed` and reaches `max_speed` after the key is held for `ramp_ms`.
+#[derive(Debug, Deserialize, Serialize, Clone, Copy)]
+#[serde(default)]
+struct Acceleration {
+    initial_speed: f32,
+    max_speed: f32,
+    ramp_ms: u64,
+}
+
+impl Default for Acceleration {
+    fn default() -> Self {
+        Self {
+            initial_speed: 200.0,
+            max_speed: 1200.0,
+            ramp_ms: 600,
+        }
+    }
+}
+
+impl Acceleration {
+    fn speed_at(&self, held: Duration) -> f32 {
+        if self.ramp_ms == 0 {
+            return self.max_speed;
+        }
+        let t = (held.as_secs_f32() * 1000.0 / self.ramp_ms as f32).min(1.0);
+        self.initial_speed + (self.max_speed - self.initial_speed) * t
+    }
+}
+
+/// Everything that colors the grid; see `themes`.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(default)]
+struct GridTheme {
+    color_grid_border: ConfigColor,
+    color_main_text: ConfigColor,
+    color_sub_home_row: ConfigColor,
+    color_sub_default: ConfigColor,
+    color_row_highlight: ConfigColor,
+    color_text_dimmed: ConfigColor,
+    color_border_dimmed: ConfigColor,
+    color_dwell_ring: ConfigColor,
+    // Sub-grid cell styling; fully transparent by default so the target stays visible.
+    color_sub_fill: ConfigColor,
+    color_sub_border: ConfigColor,
+    sub_border_width: f32,
+    /// Main grid line width.
+    border_width: f32,
+}
+
+impl Default for GridTheme {
     fn default() -> Self {
         Self {
-            screen_width: 1920.0,
-            screen_height: 1080.0,
-            main_grid_size: 26.0,
-            sub_rows: 3,
-            sub_cols: 8,
-            sub_padding: 4.0,
-            font_size: 11.0,
-            delay_surface_destroy_ms: 60,
-            delay_wayland_zero_ms: 5,
-            delay_wayland_move_ms: 20,
-            delay_double_click_ms: 120,
-            scroll_lines: 1,
-            scroll_page_lines: 10,
-            scroll_natural: true,
             color_grid_border: ConfigColor {
                 r: 1.0,
                 g: 1.0,
@@ -166,241 +383,3633 @@ impl Default for AppConfig {
                 b: 1.0,
                 a: 0.02,
             },
-            // Default QWERTY 8x3
-            sub_labels: vec![
-                "QWERUIOP".to_string(),
-                "ASDFJKL;".to_string(),
-                "ZXCVNM,.".to_string(),
-            ],
+            color_dwell_ring: ConfigColor {
+                r: 0.0,
+                g: 1.0,
+                b: 0.5,
+                a: 0.9,
+            },
+            color_sub_fill: ConfigColor {
+                r: 0.0,
+                g: 0.0,
+                b: 0.0,
+                a: 0.0,
+            },
+            color_sub_border: ConfigColor {
+                r: 1.0,
+                g: 1.0,
+                b: 1.0,
+                a: 0.0,
+            },
+            sub_border_width: 1.0,
+            border_width: 1.0,
         }
     }
 }
 
-// Math Constants
-const HALF: f32 = 2.0;
-const DOUBLE: f32 = 2.0;
-const BASE_CHAR: u32 = 'A' as u32;
-const BASE_BYTE: u8 = b'A';
-
-// --- Config Loader ---
-fn load_config() -> AppConfig {
-    if let Some(proj_dirs) = directories::ProjectDirs::from("com", "rowlink", "rowlink") {
-        let config_path = proj_dirs.config_dir().join("config.yaml");
-        if config_path.exists()
-            && let Ok(file) = std::fs::File::open(config_path)
-            && let Ok(cfg) = serde_yaml::from_reader(file)
-        {
-            println!("Loaded config from file.");
-            return cfg;
-        }
-    }
-    println!("Using default config.");
-    AppConfig::default()
+/// Screen edges excluded from the grid, e.g. for a top bar or a dock.
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, Default, PartialEq)]
+#[serde(default)]
+struct Margins {
+    top: f32,
+    right: f32,
+    bottom: f32,
+    left: f32,
 }
 
-fn cfg() -> &'static AppConfig {
-    CONFIG.get_or_init(load_config)
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(default)]
+struct AppConfig {
+    /// Config format version; older configs are migrated on load, see `migrate`.
+    version: u64,
+    screen_width: f32,
+    screen_height: f32,
+    main_grid_size: f32,
+    /// Override `main_grid_size` per axis, e.g. 16 rows x 36 columns on ultrawides.
+    main_grid_rows: Option<i32>,
+    main_grid_cols: Option<i32>,
+    /// `auto` grid: pick rows/columns so cells are about this many millimetres across.
+    grid_auto_cell_mm: Option<f32>,
+    grid_layout: GridLayout,
+    /// On an output taller than wide (as detected at startup, else `screen_width` and
+    /// `screen_height`), swaps `main_grid_rows` and `main_grid_cols` when they
+    /// have more columns than rows, and stacks `grid_pages` vertically.
+    portrait_auto: bool,
+    /// Pixel density for the `auto` grid when the output doesn't report its physical size.
+    screen_dpi: f32,
+    sub_rows: i32,
+    sub_cols: i32,
+    sub_padding: f32,
+    sub_layout: SubLayout,
+    /// Sub-grid levels: at 2 and up, picking a sub-cell zooms into it with another sub-grid
+    /// until the last level, whose pick clicks.
+    zoom_depth: u32,
+    /// A sub-cell smaller than this many pixels on both sides is precise enough: picking
+    /// it clicks, even with zoom levels left.
+    precision_px: f32,
+    /// In numpad regions, clicks as soon as the area is narrowed below `precision_px`,
+    /// without waiting for 5.
+    auto_confirm: bool,
+    click_point: ClickPoint,
+    click_offset: (f32, f32),
+    /// A selected point within this many pixels of a window's edge lands exactly on it;
+    /// 0 turns snapping off.
+    snap_px: f32,
+    /// Height of the title bar at the top of each window's geometry, whose middle line
+    /// points in it snap to; 0 for none.
+    title_bar_px: f32,
+    grid_margins: Margins,
+    /// Also keep the grid off the space panels reserve on the overlay's output, looked up
+    /// each time the grid opens.
+    respect_exclusive_zones: bool,
+    /// Splits the grid area into this many side-by-side pages, each with the full grid,
+    /// so super-ultrawide monitors keep small cells; `page_key` moves to the next page.
+    grid_pages: u32,
+    page_key: String,
+    font_size: f32,
+    /// Shrinks labels that would overflow their cells, down to `min_font_size`.
+    fit_labels: bool,
+    min_font_size: f32,
+    /// Where even `min_font_size` overflows, labels only every other main cell, in a
+    /// checkerboard; the unlabelled cells still take their labels.
+    checkerboard_labels: bool,
+    delay_surface_destroy_ms: u64,
+    delay_wayland_zero_ms: u64,
+    delay_wayland_move_ms: u64,
+    delay_double_click_ms: u64,
+    delay_type_ms: u64,
+    drag_steps: u32,
+    delay_drag_step_ms: u64,
+    scroll_lines: i32,
+    scroll_page_lines: i32,
+    /// Scroll amounts scaled per app id (the app focused when the grid opened), e.g.
+    /// `{ org.pwmt.zathura: 3.0, Alacritty: 0.5 }`; a scroll never shrinks to nothing.
+    scroll_app_multipliers: std::collections::HashMap<String, f32>,
+    /// Held scroll keys repeat every `scroll_repeat_interval_ms` after the initial delay,
+    /// gaining `scroll_acceleration` lines per tick for every second held.
+    scroll_repeat_delay_ms: u64,
+    scroll_repeat_interval_ms: u64,
+    scroll_acceleration: f32,
+    scroll_max_lines: i32,
+    /// `d`/`u` page bursts are spread over ticks of this many lines.
+    scroll_burst_lines: i32,
+    scroll_natural: bool,
+    /// Maps and zoomable canvases often react very differently to wheel steps and to
+    /// smooth scrolling.
+    scroll_style: ScrollStyle,
+    scroll_smooth_step: i32,
+    /// Flash the mode's name in the center of the screen for this long when it changes,
+    /// then fade it out; 0 turns it off.
+    mode_osd_ms: u64,
+    /// Name the overlay surface `rowlink-<mode>` (e.g. `rowlink-move`) instead of
+    /// `rowlink`, for compositor rules keyed on the mode. A layer surface can't be renamed,
+    /// so the overlay is reopened on every mode change.
+    mode_in_namespace: bool,
+    /// Arrows around the point being scrolled while the overlay stays up, as a reminder
+    /// that keys still go to rowlink.
+    scroll_indicator: bool,
+    pause_on_fullscreen: bool,
+    /// Keep the session from idling while the overlay is up, through the compositor's
+    /// idle-inhibit protocol, which swayidle and hypridle both follow.
+    idle_inhibit: bool,
+    keyboard_mode: KeyboardMode,
+    keyboard_grab_timeout_ms: u64,
+    restore_focus: bool,
+    /// While zoomed, name the window under the zoomed cell below the sub-grid, to catch
+    /// clicks about to land in the wrong one of overlapping windows.
+    show_target_app: bool,
+    /// Report each click's button and position in a desktop notification (`notify-send`)
+    /// shown for `notify_click_ms`.
+    notify_clicks: bool,
+    notify_click_ms: u64,
+    /// Shell commands run when the overlay opens and when it closes, e.g. to pause
+    /// notifications meanwhile. `ROWLINK_MODE` holds the mode (`grid`, `move`, ...).
+    on_show: Option<String>,
+    on_hide: Option<String>,
+    /// Screen reader announcements: state changes sent over AT-SPI for Orca to speak.
+    announce: bool,
+    /// Learn per-cell offsets from move-mode clicks made within `click_offset_window_ms`
+    /// of a cell's click, and apply their average to the cell's later clicks.
+    learn_click_offsets: bool,
+    click_offset_window_ms: u64,
+    /// Count clicked cells per focused app in the state directory, for `adaptive_hints`
+    /// and `rowlink heatmap`. Off by default: it keeps a lasting record of where you click
+    /// in which app, and asks the compositor for the focused app on every activation.
+    click_history: bool,
+    /// Record nothing: no click history and no learned offsets, whatever the options
+    /// above say. Read from the base config; toggled at runtime with `rowlink private`.
+    private_mode: bool,
+    /// Shortcuts on this many of the focused app's most clicked targets, labelled from
+    /// `adaptive_hint_keys` like hints: one key each while there are enough, the most
+    /// clicked first; 0 turns them off.
+    adaptive_hints: usize,
+    adaptive_hint_keys: String,
+    /// Serve the control socket's commands over a WebSocket on this localhost port too;
+    /// needs a build with the `websocket` feature. Clients pass the secret the daemon
+    /// writes to `rowlink-websocket.token` beside the socket as `?token=`. Read from the
+    /// base config.
+    websocket_port: Option<u16>,
+    /// Browser origins (e.g. `http://localhost:8080`) allowed to connect to it; clients
+    /// that send no Origin header only need the token.
+    websocket_origins: Vec<String>,
+    seat: Option<String>,
+    /// Output to open the overlay on; the compositor picks (normally the focused output)
+    /// when unset.
+    output: Option<String>,
+    backend: Backend,
+    /// Renderer for the overlay surface; the process-wide choice comes from the base config.
+    renderer: RendererBackend,
+    /// Let the overlay take touch input: tap a cell to zoom, tap a sub-cell to click it.
+    touch_select: bool,
+    /// Highlight the cell under the physical mouse; a physical click selects it like a tap.
+    pointer_select: bool,
+    /// Contact pressure of the stylus backend, from 0.0 to 1.0.
+    stylus_pressure: f32,
+    swap_buttons: bool,
+    dwell_click_ms: u64,
+    chord_window_ms: u64,
+    /// Holding Space this long on the final selection presses the button and keeps it
+    /// down until Space is released; 0, the default, disables hold-to-drag so Space clicks
+    /// on press.
+    hold_drag_ms: u64,
+    repeat_key: String,
+    /// Keys that confirm on the grid, like Space did: click the cell's center, or hold to
+    /// drag. Only the listed keys do, e.g. `[Space, Enter]`.
+    confirm_keys: Vec<String>,
+    /// Keys that back out a step, and close the overlay from the idle grid, e.g.
+    /// `[Escape, q]`.
+    cancel_keys: Vec<String>,
+    /// Typed after an Alt+selection click; the clipboard is used when unset.
+    type_text: Option<String>,
+    /// App ids the `paste` binding pastes into with a middle click (the primary selection)
+    /// instead of clicking and pressing Shift+Insert.
+    middle_click_paste_apps: Vec<String>,
+    coarse_key: String,
+    coarse_labels: Vec<String>,
+    quick_labels: Vec<String>,
+    hotspot_key: String,
+    coordinate_key: String,
+    selection_key: String,
+    move_key: String,
+    window_move_key: String,
+    window_resize_key: String,
+    /// Enters numpad mode, for a right hand resting on the numpad.
+    numpad_key: String,
+    numpad_style: NumpadStyle,
+    numpad_step: f32,
+    /// Pointer speed curve in move mode; Ctrl and Shift pick the slow and fast tiers.
+    move_acceleration: Acceleration,
+    move_slow_factor: f32,
+    move_fast_factor: f32,
+    /// Steadies move mode for users with tremor; off until `window_ms` is set.
+    move_smoothing: Smoothing,
+    hotspots: Vec<Hotspot>,
+    /// Where hint mode's targets come from, in label order; see `hints::Source`.
+    hint_providers: Vec<hints::Source>,
+    /// Grid colors, written at the top level of the config.
+    #[serde(flatten)]
+    colors: GridTheme,
+    /// Named color sets layered over `colors`, switched at runtime with `rowlink theme`,
+    /// e.g. `themes: { light: { color_main_text: { ... } } }`.
+    themes: std::collections::HashMap<String, serde_yaml::Mapping>,
+    #[serde(skip)]
+    resolved_themes: Vec<(String, GridTheme)>,
+    // Labels (Dynamic 2D Grid)
+    sub_labels: Vec<String>,
+    /// Manual `sub_labels` per keyboard layout. Without an entry, the sub-grid under a
+    /// layout other than the keymap's first uses what the `sub_labels` keys type there,
+    /// rederived each time the grid opens. Keys match the layout name the compositor
+    /// reports, or a word of it: `{ dvorak: ["',.PGCRL", "AOEUHTNS", ";QJKBMWV"] }`.
+    layout_sub_labels: std::collections::HashMap<String, Vec<String>>,
+    /// Characters main-grid labels are built from, in order; any script works, e.g.
+    /// `"ФЫВАПРОЛДЖЭЙЦУКЕНГШЩЗХ"` for a Russian layout.
+    label_alphabet: String,
+    #[serde(skip)]
+    label_chars: Vec<char>,
+    /// Falls back to `row_column` when the grid has more cells than the block keys can
+    /// label.
+    label_layout: LabelLayout,
+    /// Label keys from easiest to most awkward, e.g. `"FJDKSLA"`. When set, rows and
+    /// columns nearest the screen center get the easiest keys and the edges the rest.
+    label_preference: String,
+    #[serde(skip)]
+    label_center_bias: bool,
+    /// Per-cell label overrides keyed by the cell's generated label, e.g. `{ AA: X }`;
+    /// an empty value blanks the cell. Each label must be unique and not start another.
+    cell_labels: std::collections::HashMap<String, String>,
+    #[serde(skip)]
+    label_table: labels::LabelTable,
+    #[serde(skip)]
+    main_grid_layout: Option<MainGridLayout>,
+    /// Selects a cell as soon as the typed prefix matches no other label, saving a
+    /// keystroke when labels are uneven. Off by default so fully typed labels never spill
+    /// into the sub-grid.
+    label_unique_prefix: bool,
+    /// `bind "<keys>" <action>` lines, e.g. `bind "ctrl+enter" right_click`.
+    bindings: Vec<String>,
+    #[serde(skip)]
+    compiled_bindings: Vec<bindings::Binding>,
+    /// Named partial configs layered over this one, e.g. `profiles: { media: { ... } }`.
+    profiles: serde_yaml::Mapping,
+    default_profile: Option<String>,
+    /// Profile picked automatically when the overlay opens on an output, by output name.
+    output_profiles: std::collections::HashMap<String, String>,
+    /// Cycles through the profiles from the idle main grid.
+    profile_key: String,
+    #[serde(skip)]
+    profile_name: String,
 }
 
-pub fn main() -> Result<(), iced_layershell::Error> {
-    let _ = cfg();
-    application(Rowlink::default, namespace, update, view)
-        .subscription(subscription)
-        .style(style)
-        .settings(Settings {
-            id: Some(namespace()),
-            layer_settings: LayerShellSettings {
-                size: Some((1, 1)),
-                anchor: Anchor::Top | Anchor::Left,
-                layer: Layer::Background,
-                keyboard_interactivity: KeyboardInteractivity::None,
-                events_transparent: true,
-                ..Default::default()
-            },
-            ..Default::default()
-        })
-        .run()
-}
+impl AppConfig {
+    /// Replaces the sub-grid labels and dimensions with the chosen preset, if any.
+    fn apply_sub_layout(&mut self) {
+        if let Some(rows) = self.sub_layout.labels() {
+            self.sub_labels = rows.iter().map(|row| row.to_string()).collect();
+            self.sub_rows = rows.len() as i32;
+            self.sub_cols = rows[0].len() as i32;
+        }
+    }
 
-fn namespace() -> String {
-    String::from("rowlink")
-}
+    /// Uppercases and dedups `label_alphabet`; falls back to A-Z if fewer than two
+    /// characters remain.
+    fn apply_label_alphabet(&mut self) {
+        let mut chars: Vec<char> = Vec::new();
+        for c in self.label_alphabet.chars().filter(|c| !c.is_whitespace()) {
+            let c = label_char(c);
+            if !chars.contains(&c) {
+                chars.push(c);
+            }
+        }
+        if chars.len() < 2 {
+            chars = ('A'..='Z').collect();
+        }
+        // Preferred keys move to the front; unlisted ones keep their alphabet order.
+        let preference = match self.label_layout {
+            LabelLayout::Blocks | LabelLayout::Triples if self.label_preference.is_empty() => {
+                HINT_ALPHABET
+            }
+            _ => &self.label_preference,
+        };
+        let preferred: Vec<char> = preference
+            .chars()
+            .map(label_char)
+            .filter(|c| chars.contains(c))
+            .collect();
+        self.label_center_bias = !self.label_preference.is_empty();
+        for c in preferred.into_iter().rev() {
+            chars.retain(|other| *other != c);
+            chars.insert(0, c);
+        }
+        self.label_chars = chars;
+    }
 
-struct Rowlink {
-    input_buffer: String,
-    enigo: Option<Enigo>,
-    visible: bool,
-    grid_cache: canvas::Cache,
-    current_id: Option<IcedId>,
-    zoomed_cell: Option<(i32, i32)>,
-    last_mouse_pos: Option<(f32, f32)>,
-}
+    /// Layers each entry of `themes` over `colors`, skipping the ones that don't parse;
+    /// config validation reports them.
+    fn resolve_themes(&mut self) {
+        let Ok(colors) = serde_yaml::to_value(&self.colors) else {
+            return;
+        };
+        let mut themes: Vec<(String, GridTheme)> = self
+            .themes
+            .iter()
+            .filter_map(|(name, overrides)| {
+                let mut merged = colors.clone();
+                merge_yaml(&mut merged, serde_yaml::Value::Mapping(overrides.clone()));
+                Some((name.clone(), serde_yaml::from_value(merged).ok()?))
+            })
+            .collect();
+        themes.sort_by(|(a, _), (b, _)| a.cmp(b));
+        self.resolved_themes = themes;
+    }
 
-impl Rowlink {
-    fn perform_enigo_action<F>(&mut self, mut action: F)
-    where
-        F: FnMut(&mut Enigo) -> InputResult<()>,
-    {
-        if self.enigo.is_none() {
-            self.enigo = Enigo::new(&EnigoSettings::default()).ok();
+    /// Resolves every main-grid cell's label. Run whenever the grid dimensions change.
+    fn build_label_table(&mut self) {
+        let (rows, cols) = (self.grid_rows(), self.grid_cols());
+        let (row_width, col_width) = (self.row_label_width(), self.col_label_width());
+        let (row_rank, col_rank) = if self.label_center_bias {
+            (labels::center_ranks(rows), labels::center_ranks(cols))
+        } else {
+            ((0..rows).collect(), (0..cols).collect())
+        };
+        let alphabet = &self.label_chars;
+        let keys = alphabet.len() as i32;
+        let blocks: Option<Vec<(i32, i32)>> = match self.label_layout {
+            LabelLayout::Blocks => labels::block_size(rows, cols, keys).map(|size| vec![size]),
+            LabelLayout::Triples => labels::triple_block_sizes(rows, cols, keys).map(Vec::from),
+            LabelLayout::RowColumn => None,
+        };
+        if blocks.is_none() && self.label_layout != LabelLayout::RowColumn {
+            eprintln!("Grid too large for block labels, using row/column labels.");
         }
+        self.label_table = labels::LabelTable::build(
+            rows,
+            cols,
+            |row, col| match &blocks {
+                Some(levels) => labels::nested_label(row, col, cols, levels, alphabet),
+                None => {
+                    index_label(row_rank[row as usize], row_width, alphabet)
+                        + &index_label(col_rank[col as usize], col_width, alphabet)
+                }
+            },
+            &self.cell_labels,
+        );
+    }
 
-        if let Some(enigo) = self.enigo.as_mut()
-            && let Err(e) = action(enigo)
-        {
-            eprintln!("Enigo Error (will retry): {:?}", e);
-
-            self.enigo = Enigo::new(&EnigoSettings::default()).ok();
+    /// Precomputes `main_grid_layout` from the label table. Run after `build_label_table`.
+    fn layout_main_grid(&mut self) {
+        let (width, height) = (self.screen_width, self.screen_height);
+        let (cell_width, cell_height) = self.get_main_cell_size(width, height);
+        let cells: Vec<(i32, i32)> = (0..self.grid_rows())
+            .flat_map(|r| (0..self.grid_cols()).map(move |c| (r, c)))
+            .collect();
+        let outline = canvas::Path::new(|builder| {
+            for (r, c) in &cells {
+                let (x, y) = self.get_main_cell_origin(width, height, *r, *c);
+                builder.rectangle(Point::new(x, y), iced::Size::new(cell_width, cell_height));
+            }
+        });
+        let labels = cells
+            .iter()
+            .filter_map(|(r, c)| {
+                let label = self.label_table.get(*r, *c)?;
+                let chars = label.chars().count();
+                let cell_size = (cell_width, cell_height);
+                if self.label_hidden(cell_size, chars, *r, *c) {
+                    return None;
+                }
+                let (center_x, center_y) = self.get_main_cell_center(width, height, *r, *c);
+                Some(Text {
+                    content: label.to_string(),
+                    position: Point::new(center_x, center_y),
+                    size: self.label_size(cell_size, chars).into(),
+                    align_x: iced::widget::text::Alignment::Center,
+                    align_y: iced::alignment::Vertical::Center,
+                    font: Font::MONOSPACE,
+                    ..Default::default()
+                })
+            })
+            .collect();
+        self.main_grid_layout = Some(MainGridLayout {
+            outline,
+            labels,
+            margins: self.margins(),
+        });
+    }
 
-            if let Some(enigo_retry) = self.enigo.as_mut() {
-                let _ = action(enigo_retry);
+    /// Derives the row/column counts from the `grid_layout` preset and the screen's
+    /// orientation. Run after margins are final.
+    fn apply_grid_layout(&mut self) {
+        match self.grid_layout {
+            GridLayout::Custom => {}
+            GridLayout::Ultrawide => {
+                let rows = self.grid_rows().min(self.label_chars.len() as i32);
+                let (_, _, width, height) =
+                    self.get_grid_area(self.screen_width, self.screen_height);
+                let cols = (width / (height / rows as f32)).round().max(1.0) as i32;
+                self.main_grid_rows = Some(rows);
+                self.main_grid_cols = Some(cols);
+                self.label_layout = LabelLayout::RowColumn;
+                eprintln!("Ultrawide grid: {}x{} cells.", rows, cols);
+            }
+            GridLayout::LowVision => {
+                self.main_grid_rows = Some(4);
+                self.main_grid_cols = Some(6);
+                self.label_layout = LabelLayout::RowColumn;
+                self.font_size = self.font_size.max(48.0);
+                self.zoom_depth = self.zoom_depth.max(3);
+                let themes = self.resolved_themes.iter_mut().map(|(_, theme)| theme);
+                for theme in std::iter::once(&mut self.colors).chain(themes) {
+                    theme.border_width = theme.border_width.max(4.0);
+                    theme.sub_border_width = theme.sub_border_width.max(4.0);
+                    theme.color_grid_border.a = theme.color_grid_border.a.max(0.6);
+                    theme.color_sub_border.a = theme.color_sub_border.a.max(0.6);
+                }
             }
         }
+        if self.is_portrait() && self.grid_rows() < self.grid_cols() {
+            let (rows, cols) = (self.grid_rows(), self.grid_cols());
+            self.main_grid_rows = Some(cols);
+            self.main_grid_cols = Some(rows);
+            eprintln!("Portrait screen: {}x{} grid.", cols, rows);
+        }
     }
-}
 
-impl Default for Rowlink {
-    fn default() -> Self {
-        Self {
-            input_buffer: String::new(),
-            enigo: Some(Enigo::new(&EnigoSettings::default()).expect("Enigo init failed")),
-            visible: false,
-            grid_cache: canvas::Cache::default(),
-            current_id: None,
-            zoomed_cell: None,
-            last_mouse_pos: None,
-        }
+    /// Derives the row/column counts for the `auto` grid from the screen's DPI, keeping
+    /// target density the same across monitors. Run after margins are final.
+    fn apply_auto_grid(&mut self) {
+        let Some(cell_mm) = self.grid_auto_cell_mm else {
+            return;
+        };
+        let cell_px = cell_mm / MM_PER_INCH * self.output_dpi();
+        let (_, _, width, height) = self.get_grid_area(self.screen_width, self.screen_height);
+        self.main_grid_rows = Some((height / cell_px).round().max(1.0) as i32);
+        self.main_grid_cols = Some((width / cell_px).round().max(1.0) as i32);
+        eprintln!(
+            "Auto grid: {}x{} cells of ~{}mm.",
+            self.grid_rows(),
+            self.grid_cols(),
+            cell_mm
+        );
     }
-}
 
-#[to_layer_message(multi)]
-#[derive(Debug, Clone)]
-enum Message {
-    Startup,
-    SignalReceived,
-    ExecuteMovePrecision(i32, i32, i32, i32, bool),
-    ExecuteMoveCenter(Option<(i32, i32)>, bool),
-    ExecuteScroll(Option<(i32, i32)>, i32, i32),
-    IcedEvent(Event),
-}
+    /// Top-left corner and size of the area the main grid covers, after margins.
+    fn get_grid_area(&self, width: f32, height: f32) -> (f32, f32, f32, f32) {
+        let area = self.main_grid(width, height).area;
+        (area.x, area.y, area.width, area.height)
+    }
 
-fn get_layer_settings(interactive: bool) -> NewLayerShellSettings {
-    if interactive {
-        NewLayerShellSettings {
-            size: None,
-            anchor: Anchor::all(),
-            layer: Layer::Overlay,
-            exclusive_zone: Some(-1),
-            events_transparent: true,
-            keyboard_interactivity: KeyboardInteractivity::OnDemand,
-            ..Default::default()
-        }
-    } else {
-        NewLayerShellSettings {
-            anchor: Anchor::all(),
-            layer: Layer::Background,
-            keyboard_interactivity: KeyboardInteractivity::None,
-            events_transparent: true,
-            ..Default::default()
+    /// `grid_margins`, plus the exclusive zones on the current output if respected.
+    fn margins(&self) -> Margins {
+        let mut margins = self.grid_margins;
+        if self.respect_exclusive_zones {
+            let (top, right, bottom, left) = placement().reserved;
+            margins.top += top;
+            margins.right += right;
+            margins.bottom += bottom;
+            margins.left += left;
         }
+        margins
     }
-}
 
-fn map_key_to_subgrid(c: char) -> Option<(i32, i32)> {
-    let input_char = c.to_ascii_uppercase();
+    /// The main grid over a `width` x `height` surface, after margins.
+    fn main_grid(&self, width: f32, height: f32) -> geometry::Grid {
+        let m = self.margins();
+        let pages = self.grid_pages.max(1);
+        let page = ACTIVE_PAGE.load(Ordering::Relaxed).min(pages - 1) as f32;
+        let insets = if self.is_portrait() {
+            let page_height = (height - m.top - m.bottom) / pages as f32;
+            let top = m.top + page_height * page;
+            (top, m.right, height - top - page_height, m.left)
+        } else {
+            let page_width = (width - m.left - m.right) / pages as f32;
+            let left = m.left + page_width * page;
+            (m.top, width - left - page_width, m.bottom, left)
+        };
+        geometry::Grid::new(
+            width,
+            height,
+            insets,
+            self.grid_rows(),
+            self.grid_cols(),
+            placement().scale,
+        )
+    }
 
-    for (r_idx, row_str) in cfg().sub_labels.iter().enumerate() {
-        for (c_idx, key_char) in row_str.chars().enumerate() {
-            if key_char.to_ascii_uppercase() == input_char {
-                return Some((r_idx as i32, c_idx as i32));
-            }
-        }
+    /// The sub-grid of one main cell.
+    fn sub_grid(&self, width: f32, height: f32, main_row: i32, main_col: i32) -> geometry::Grid {
+        self.main_grid(width, height).sub_grid(
+            main_row,
+            main_col,
+            self.sub_rows,
+            self.sub_cols,
+            self.sub_padding,
+        )
     }
-    None
-}
 
-fn move_sequence(enigo: &mut Enigo, x: f32, y: f32) -> InputResult<()> {
+    /// Portrait when `portrait_auto` is on and the detected output is taller than wide.
+    fn is_portrait(&self) -> bool {
+        let (width, height) = self.output_size();
+        self.portrait_auto && height > width
+    }
+
+    /// Logical pixels per inch of the output the overlay opens on, else `screen_dpi`.
+    fn output_dpi(&self) -> f32 {
+        let dpi = placement().dpi;
+        if dpi > 0.0 { dpi } else { self.screen_dpi }
+    }
+
+    /// Logical size of the output the overlay opens on, else the configured screen size.
+    fn output_size(&self) -> (f32, f32) {
+        let placement = placement();
+        if placement.width > 0.0 && placement.height > 0.0 {
+            (placement.width, placement.height)
+        } else {
+            (self.screen_width, self.screen_height)
+        }
+    }
+
+    fn grid_rows(&self) -> i32 {
+        self.main_grid_rows
+            .unwrap_or(self.main_grid_size as i32)
+            .max(1)
+    }
+
+    fn grid_cols(&self) -> i32 {
+        self.main_grid_cols
+            .unwrap_or(self.main_grid_size as i32)
+            .max(1)
+    }
+
+    fn row_label_width(&self) -> usize {
+        label_width(self.grid_rows(), self.label_chars.len() as i32)
+    }
+
+    fn col_label_width(&self) -> usize {
+        label_width(self.grid_cols(), self.label_chars.len() as i32)
+    }
+
+    /// Font size of a `chars` long label in a cell of this size; see `fit_labels`.
+    fn label_size(&self, (cell_width, cell_height): (f32, f32), chars: usize) -> f32 {
+        if !self.fit_labels {
+            return self.font_size;
+        }
+        let fits = (cell_height * LABEL_FILL)
+            .min(cell_width * LABEL_FILL / (GLYPH_WIDTH * chars.max(1) as f32));
+        self.font_size.min(fits).max(self.min_font_size)
+    }
+
+    /// Whether a main cell's label is left out under `checkerboard_labels`.
+    fn label_hidden(
+        &self,
+        (cell_width, cell_height): (f32, f32),
+        chars: usize,
+        r: i32,
+        c: i32,
+    ) -> bool {
+        self.checkerboard_labels
+            && (r + c) % 2 == 1
+            && (cell_height * LABEL_FILL < self.min_font_size
+                || cell_width * LABEL_FILL < GLYPH_WIDTH * chars as f32 * self.min_font_size)
+    }
+
+    fn get_main_cell_size(&self, width: f32, height: f32) -> (f32, f32) {
+        self.main_grid(width, height).cell_size()
+    }
+
+    fn get_main_cell_origin(&self, width: f32, height: f32, row: i32, col: i32) -> (f32, f32) {
+        let cell = self.main_grid(width, height).cell(row, col);
+        (cell.x, cell.y)
+    }
+
+    fn get_main_cell_center(&self, width: f32, height: f32, row: i32, col: i32) -> (f32, f32) {
+        self.main_grid(width, height).cell(row, col).center()
+    }
+
+    /// Whether the overlay surface must receive pointer/touch input instead of passing it
+    /// through.
+    fn overlay_takes_input(&self) -> bool {
+        self.touch_select || self.pointer_select
+    }
+
+    /// Main grid cell under a point, if any.
+    fn get_main_cell_at(&self, width: f32, height: f32, x: f32, y: f32) -> Option<(i32, i32)> {
+        // Blanked cells can't be picked.
+        self.main_grid(width, height)
+            .cell_at(x, y)
+            .filter(|(row, col)| self.label_table.get(*row, *col).is_some())
+    }
+
+    /// The coarse grid laid out by `coarse_labels` over `area`, the screen or the region
+    /// being refined. Drawing and picking both go through it; short rows leave their
+    /// trailing cells empty.
+    fn coarse_grid(&self, area: geometry::Rect) -> geometry::Grid {
+        let rows = self.coarse_labels.len() as i32;
+        let cols = self
+            .coarse_labels
+            .iter()
+            .map(|row| row.chars().count())
+            .max()
+            .unwrap_or(1);
+        geometry::Grid::over(area, rows, cols as i32, placement().scale)
+    }
+
+    /// Quick targets spread evenly from edge to edge: with 3x3 labels these are the
+    /// corners, the edge midpoints and the center.
+    fn get_quick_target(&self, width: f32, height: f32, row: i32, col: i32) -> (f32, f32) {
+        let rows = self.quick_labels.len();
+        let cols = self
+            .quick_labels
+            .get(row as usize)
+            .map_or(1, |r| r.chars().count());
+        (
+            geometry::spread(col, cols, width),
+            geometry::spread(row, rows, height),
+        )
+    }
+
+    fn get_precision_target(
+        &self,
+        width: f32,
+        height: f32,
+        main_row: i32,
+        main_col: i32,
+        sub_row: i32,
+        sub_col: i32,
+    ) -> (f32, f32) {
+        self.click_point(
+            self.sub_grid(width, height, main_row, main_col)
+                .cell(sub_row, sub_col),
+        )
+    }
+
+    /// The point of a final region that gets clicked, per `click_point`.
+    fn click_point(&self, area: geometry::Rect) -> (f32, f32) {
+        match self.click_point {
+            ClickPoint::Center => area.center(),
+            ClickPoint::TopLeft => (area.x, area.y),
+            ClickPoint::Offset => {
+                let (dx, dy) = self.click_offset;
+                (
+                    area.x + dx.clamp(0.0, area.width),
+                    area.y + dy.clamp(0.0, area.height),
+                )
+            }
+        }
+    }
+}
+
+impl Default for AppConfig {
+    fn default() -> Self {
+        Self {
+            version: migrate::CURRENT,
+            screen_width: 1920.0,
+            screen_height: 1080.0,
+            main_grid_size: 26.0,
+            main_grid_rows: None,
+            grid_layout: GridLayout::Custom,
+            portrait_auto: true,
+            main_grid_cols: None,
+            grid_auto_cell_mm: None,
+            screen_dpi: 96.0,
+            sub_rows: 3,
+            sub_cols: 8,
+            sub_padding: 4.0,
+            sub_layout: SubLayout::Custom,
+            zoom_depth: 1,
+            precision_px: 4.0,
+            auto_confirm: false,
+            click_point: ClickPoint::Center,
+            click_offset: (8.0, 8.0),
+            snap_px: 0.0,
+            title_bar_px: 0.0,
+            grid_margins: Margins::default(),
+            respect_exclusive_zones: true,
+            grid_pages: 1,
+            page_key: "=".to_string(),
+            font_size: 11.0,
+            fit_labels: true,
+            min_font_size: 7.0,
+            checkerboard_labels: false,
+            delay_surface_destroy_ms: 60,
+            delay_wayland_zero_ms: 5,
+            delay_wayland_move_ms: 20,
+            delay_double_click_ms: 120,
+            delay_type_ms: 50,
+            drag_steps: 20,
+            delay_drag_step_ms: 5,
+            scroll_lines: 1,
+            scroll_page_lines: 10,
+            scroll_app_multipliers: std::collections::HashMap::new(),
+            scroll_repeat_delay_ms: 250,
+            scroll_repeat_interval_ms: 30,
+            scroll_acceleration: 2.0,
+            scroll_max_lines: 8,
+            scroll_burst_lines: 2,
+            scroll_natural: true,
+            scroll_style: ScrollStyle::Discrete,
+            // A wheel step is 15 axis units on Wayland.
+            scroll_smooth_step: 15,
+            scroll_indicator: true,
+            mode_osd_ms: 500,
+            mode_in_namespace: false,
+            pause_on_fullscreen: false,
+            idle_inhibit: true,
+            keyboard_mode: KeyboardMode::Auto,
+            keyboard_grab_timeout_ms: 400,
+            restore_focus: true,
+            show_target_app: true,
+            notify_clicks: false,
+            notify_click_ms: 1500,
+            on_show: None,
+            on_hide: None,
+            announce: false,
+            learn_click_offsets: false,
+            click_offset_window_ms: 10_000,
+            click_history: false,
+            private_mode: false,
+            adaptive_hints: 0,
+            adaptive_hint_keys: "1234567890".to_string(),
+            websocket_port: None,
+            websocket_origins: Vec::new(),
+            seat: None,
+            output: None,
+            backend: Backend::Pointer,
+            renderer: RendererBackend::Auto,
+            touch_select: false,
+            pointer_select: false,
+            stylus_pressure: 0.5,
+            swap_buttons: false,
+            dwell_click_ms: 0,
+            chord_window_ms: 0,
+            hold_drag_ms: 0,
+            repeat_key: ".".to_string(),
+            confirm_keys: vec!["Space".to_string(), "Enter".to_string()],
+            cancel_keys: vec!["Escape".to_string()],
+            type_text: None,
+            middle_click_paste_apps: Vec::new(),
+            coarse_key: "Tab".to_string(),
+            coarse_labels: vec!["QWE".to_string(), "ASD".to_string(), "ZXC".to_string()],
+            quick_labels: vec!["QWE".to_string(), "ASD".to_string(), "ZXC".to_string()],
+            hotspot_key: ";".to_string(),
+            coordinate_key: ":".to_string(),
+            selection_key: "/".to_string(),
+            move_key: "'".to_string(),
+            window_move_key: "[".to_string(),
+            window_resize_key: "]".to_string(),
+            numpad_key: "*".to_string(),
+            numpad_style: NumpadStyle::Regions,
+            numpad_step: 50.0,
+            move_acceleration: Acceleration::default(),
+            move_slow_factor: 0.25,
+            move_fast_factor: 3.0,
+            move_smoothing: Smoothing::default(),
+            hotspots: Vec::new(),
+            hint_providers: vec![hints::Source::Hotspots],
+            colors: GridTheme::default(),
+            themes: std::collections::HashMap::new(),
+            resolved_themes: Vec::new(),
+            // Default QWERTY 8x3
+            layout_sub_labels: std::collections::HashMap::new(),
+            sub_labels: vec![
+                "QWERUIOP".to_string(),
+                "ASDFJKL;".to_string(),
+                "ZXCVNM,.".to_string(),
+            ],
+            label_alphabet: "ABCDEFGHIJKLMNOPQRSTUVWXYZ".to_string(),
+            label_chars: Vec::new(),
+            label_layout: LabelLayout::RowColumn,
+            label_preference: String::new(),
+            label_center_bias: false,
+            cell_labels: std::collections::HashMap::new(),
+            label_table: labels::LabelTable::default(),
+            main_grid_layout: None,
+            label_unique_prefix: false,
+            bindings: Vec::new(),
+            compiled_bindings: Vec::new(),
+            profiles: serde_yaml::Mapping::new(),
+            default_profile: None,
+            output_profiles: std::collections::HashMap::new(),
+            profile_key: "F2".to_string(),
+            profile_name: DEFAULT_PROFILE.to_string(),
+        }
+    }
+}
+
+// Math Constants
+const HALF: f32 = 2.0;
+const MM_PER_INCH: f32 = 25.4;
+const DEFAULT_PROFILE: &str = "default";
+const DEFAULT_THEME: &str = "default";
+const DWELL_RING_RADIUS: f32 = 14.0;
+const COARSE_FONT_SCALE: f32 = 4.0;
+// Home row first, so the first few hotspots get the easiest keys.
+const HINT_ALPHABET: &str = "ASDFGHJKLQWERTYUIOPZXCVBNM";
+const QUICK_FONT_SCALE: f32 = 2.0;
+const TARGET_APP_TITLE_CHARS: usize = 60;
+const MODE_OSD_FADE_MS: u64 = 200;
+const MODE_OSD_FONT_SCALE: f32 = 3.0;
+/// Monospace glyph width and the share of a cell a label may fill, relative to font size
+/// and cell size.
+const GLYPH_WIDTH: f32 = 0.6;
+const LABEL_FILL: f32 = 0.85;
+
+// --- Grid Labels ---
+// By default rows and columns are labelled independently with fixed-width A-Z labels, so
+// grids with more than 26 rows or columns get multi-character labels (AA, AB, ...).
+// `cell_labels` then renames or blanks individual cells; see `labels::LabelTable`.
+
+/// Characters needed to give each of `count` rows/columns a distinct label.
+fn label_width(count: i32, radix: i32) -> usize {
+    let radix = radix.max(2);
+    let mut width = 1;
+    let mut capacity = radix;
+    while capacity < count {
+        width += 1;
+        capacity = capacity.saturating_mul(radix);
+    }
+    width
+}
+
+/// Fixed-width label for `index`, as digits in `label_alphabet`.
+fn index_label(index: i32, width: usize, alphabet: &[char]) -> String {
+    let radix = alphabet.len() as i32;
+    let mut label = vec![alphabet[0]; width];
+    let mut rest = index;
+    for slot in label.iter_mut().rev() {
+        *slot = alphabet[(rest % radix) as usize];
+        rest /= radix;
+    }
+    label.into_iter().collect()
+}
+
+/// Uppercase form of a typed label character, for any script with case.
+fn label_char(c: char) -> char {
+    c.to_uppercase().next().unwrap_or(c)
+}
+
+// --- Config Loader ---
+/// The base config followed by one resolved config per entry of `profiles`. Problems are
+/// printed; anything that doesn't parse falls back to the defaults.
+fn load_config() -> Vec<AppConfig> {
+    let (configs, problems) = load_config_checked();
+    for problem in &problems {
+        eprintln!("{}", problem);
+    }
+    configs
+}
+
+/// Like `load_config`, but returns every problem found as a `file:line: message`
+/// diagnostic instead of printing it.
+fn load_config_checked() -> (Vec<AppConfig>, Vec<String>) {
+    let defaults = || vec![finish_config(AppConfig::default(), DEFAULT_PROFILE)];
+    let Some(path) = paths::config_file().filter(|path| path.exists()) else {
+        eprintln!("Using default config.");
+        return (defaults(), Vec::new());
+    };
+    let text = match std::fs::read_to_string(&path) {
+        Ok(text) => text,
+        Err(e) => {
+            return (defaults(), vec![format!("{}: {}", path.display(), e)]);
+        }
+    };
+    let report = validate::Report::new(&path, &text);
+    let mut raw = match serde_yaml::from_str::<serde_yaml::Value>(&text) {
+        Ok(raw) => raw,
+        Err(e) => {
+            let problem = report.file(format!("{}; using the default config", e));
+            return (defaults(), vec![problem]);
+        }
+    };
+    let written_for = migrate::version(&raw);
+    let migrated = migrate::migrate(&mut raw);
+    // Parsing the text directly keeps serde's line and column in errors, but a migrated
+    // config no longer matches the text.
+    let base = if migrated.is_empty() {
+        serde_yaml::from_str::<AppConfig>(&text)
+    } else {
+        serde_yaml::from_value::<AppConfig>(raw.clone())
+    };
+    let base = match base {
+        Ok(base) => base,
+        Err(e) => {
+            let problem = report.file(format!("{}; using the default config", e));
+            return (defaults(), vec![problem]);
+        }
+    };
+    eprintln!("Loaded config from file.");
+    for note in &migrated {
+        eprintln!("Config migrated from version {}: {}.", written_for, note);
+    }
+    if !migrated.is_empty() {
+        eprintln!(
+            "Apply these changes to {} to silence this message.",
+            path.display()
+        );
+    }
+
+    let mut problems = Vec::new();
+    if base.version > migrate::CURRENT {
+        let message = format!(
+            "{} is newer than this rowlink reads ({}); unknown options are ignored",
+            base.version,
+            migrate::CURRENT
+        );
+        problems.push(report.problem(None, &validate::problem("version", message)));
+    }
+    let mut configs = vec![finish_config(base, DEFAULT_PROFILE)];
+    for problem in validate::check(&configs[0]) {
+        problems.push(report.problem(None, &problem));
+    }
+    for (name, overrides) in &configs[0].profiles.clone() {
+        let Some(name) = name.as_str() else {
+            continue;
+        };
+        let mut merged = raw.clone();
+        merge_yaml(&mut merged, overrides.clone());
+        let profile = match serde_yaml::from_value::<AppConfig>(merged) {
+            Ok(profile) => finish_config(profile, name),
+            Err(e) => {
+                problems.push(report.profile(name, format!("{}; ignoring it", e)));
+                continue;
+            }
+        };
+        // Problems inherited from the base config were reported above.
+        for problem in validate::check(&profile) {
+            if overrides
+                .as_mapping()
+                .is_some_and(|o| o.contains_key(problem.option))
+            {
+                problems.push(report.problem(Some(name), &problem));
+            }
+        }
+        configs.push(profile);
+    }
+
+    let base = &configs[0];
+    let unknown = |name: &String| !configs.iter().any(|c| &c.profile_name == name);
+    if let Some(name) = base.default_profile.as_ref().filter(|n| unknown(n)) {
+        let message = format!("profile {} is not defined", name);
+        problems.push(report.problem(None, &validate::problem("default_profile", message)));
+    }
+    for (output, name) in base.output_profiles.iter().filter(|(_, n)| unknown(n)) {
+        let message = format!("profile {} for output {} is not defined", name, output);
+        problems.push(report.problem(None, &validate::problem("output_profiles", message)));
+    }
+    (configs, problems)
+}
+
+fn finish_config(mut config: AppConfig, name: &str) -> AppConfig {
+    config.apply_sub_layout();
+    config.apply_label_alphabet();
+    config.compiled_bindings = bindings::compile(&config.bindings);
+    config.resolve_themes();
+    config.profile_name = name.to_string();
+    config
+}
+
+/// Recursively layers `overlay` onto `base`; mappings merge key by key, anything else
+/// is replaced.
+fn merge_yaml(base: &mut serde_yaml::Value, overlay: serde_yaml::Value) {
+    match (base, overlay) {
+        (serde_yaml::Value::Mapping(base), serde_yaml::Value::Mapping(overlay)) => {
+            for (key, value) in overlay {
+                match base.get_mut(&key) {
+                    Some(existing) => merge_yaml(existing, value),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (base, overlay) => *base = overlay,
+    }
+}
+
+// --- Environment Overrides ---
+// `ROWLINK_*` variables override config values for one session, e.g. from a compositor
+// exec line, and win over every profile; command-line flags still win over them.
+//   ROWLINK_GRID      26, or 16x36 for rows x columns
+//   ROWLINK_BACKEND   pointer, touch or stylus
+//   ROWLINK_OUTPUT    output to open the overlay on
+//   ROWLINK_SEAT      seat for pointer emulation
+//   ROWLINK_RENDERER  gpu, software or auto
+//   ROWLINK_PROFILE   profile active at startup
+
+#[derive(Debug, Default)]
+struct EnvOverrides {
+    grid: Option<(i32, i32)>,
+    backend: Option<Backend>,
+    output: Option<String>,
+    seat: Option<String>,
+    renderer: Option<RendererBackend>,
+    profile: Option<String>,
+}
+
+impl EnvOverrides {
+    /// Reads the variables; invalid values are reported and ignored.
+    fn from_env() -> Self {
+        Self {
+            grid: env_override("ROWLINK_GRID", |value| match value.split_once('x') {
+                Some((rows, cols)) => Some((rows.trim().parse().ok()?, cols.trim().parse().ok()?)),
+                None => value.parse().ok().map(|size| (size, size)),
+            })
+            .filter(|(rows, cols)| *rows >= 1 && *cols >= 1),
+            backend: env_override("ROWLINK_BACKEND", enum_value),
+            output: env_override("ROWLINK_OUTPUT", |value| Some(value.to_string())),
+            seat: env_override("ROWLINK_SEAT", |value| Some(value.to_string())),
+            renderer: env_override("ROWLINK_RENDERER", RendererBackend::from_name),
+            profile: env_override("ROWLINK_PROFILE", |value| Some(value.to_string())),
+        }
+    }
+
+    fn apply(&self, config: &mut AppConfig) {
+        if let Some((rows, cols)) = self.grid {
+            config.main_grid_rows = Some(rows);
+            config.main_grid_cols = Some(cols);
+            config.grid_auto_cell_mm = None;
+        }
+        if let Some(backend) = self.backend {
+            config.backend = backend;
+        }
+        if self.output.is_some() {
+            config.output = self.output.clone();
+        }
+        if self.seat.is_some() {
+            config.seat = self.seat.clone();
+        }
+        if let Some(renderer) = self.renderer {
+            config.renderer = renderer;
+        }
+        if self.profile.is_some() {
+            config.default_profile = self.profile.clone();
+        }
+    }
+}
+
+/// Parses a set, non-empty variable; reports values `parse` rejects.
+fn env_override<T>(name: &str, parse: impl FnOnce(&str) -> Option<T>) -> Option<T> {
+    let value = std::env::var(name).ok().filter(|value| !value.is_empty())?;
+    let parsed = parse(value.trim());
+    if parsed.is_none() {
+        eprintln!("Ignoring {}={:?}: invalid value.", name, value);
+    }
+    parsed
+}
+
+/// A config enum from its name as written in the config file.
+fn enum_value<T: serde::de::DeserializeOwned>(name: &str) -> Option<T> {
+    serde_yaml::from_value(serde_yaml::Value::String(name.to_string())).ok()
+}
+
+fn cfg() -> &'static AppConfig {
+    let configs = CONFIG.get_or_init(load_config);
+    &configs[ACTIVE_PROFILE
+        .load(Ordering::Relaxed)
+        .min(configs.len() - 1)]
+}
+
+/// The base config; settings that pick between profiles live here, not per profile.
+fn base_config() -> &'static AppConfig {
+    &CONFIG.get_or_init(load_config)[0]
+}
+
+/// Page of the grid area shown, within `grid_pages`.
+fn active_page() -> u32 {
+    ACTIVE_PAGE
+        .load(Ordering::Relaxed)
+        .min(cfg().grid_pages.max(1) - 1)
+}
+
+fn placement() -> geometry::Placement {
+    *PLACEMENT.read().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Looks up the output the overlay opens on, `output` or else the focused one, and its place
+/// in the layout; with `exclusive_zones` also the space panels reserve on it, with
+/// `pixel_density` its DPI. Keeps the last placement when the compositor can't say. Returns
+/// the focused output's name.
+fn update_placement(
+    output: Option<&str>,
+    exclusive_zones: bool,
+    pixel_density: bool,
+) -> Option<String> {
+    let outputs = compositor::outputs();
+    let focused = outputs.iter().find(|o| o.focused).map(|o| o.name.clone());
+    let name = output.or(focused.as_deref());
+    let Some(output) = outputs.iter().find(|o| Some(o.name.as_str()) == name) else {
+        return focused;
+    };
+    let placement = geometry::Placement {
+        x: output.x,
+        y: output.y,
+        width: output.width,
+        height: output.height,
+        layout_x: outputs.iter().map(|o| o.x).fold(output.x, f32::min),
+        layout_y: outputs.iter().map(|o| o.y).fold(output.y, f32::min),
+        scale: output.scale,
+        reserved: exclusive_zones
+            .then(|| compositor::reserved_area(&output.name))
+            .flatten()
+            .unwrap_or(placement().reserved),
+        dpi: pixel_density
+            .then(|| density::dpi(&output.name))
+            .flatten()
+            .map_or(placement().dpi, |dpi| dpi / output.scale),
+    };
+    *PLACEMENT.write().unwrap_or_else(|e| e.into_inner()) = placement;
+    focused
+}
+
+/// What an activation needs from the compositor, asked only when the config uses it.
+#[derive(Debug, Clone, Default)]
+struct Focus {
+    output: Option<String>,
+    window: Option<compositor::Focused>,
+    /// The active layout and its sub-grid keys, if they differ from `sub_labels`.
+    keyboard_layout: Option<(String, Option<Vec<String>>)>,
+    /// The overlay's output, its scale or its panels changed since the last activation.
+    placement_changed: bool,
+}
+
+/// Each compositor query spawns its client, so activation runs them in the background.
+fn query_focus() -> Focus {
+    let config = cfg();
+    let wants_window = config.pause_on_fullscreen
+        || config.restore_focus
+        || config.click_history
+        || config.adaptive_hints > 0
+        || !config.scroll_app_multipliers.is_empty();
+    let last = placement();
+    Focus {
+        output: update_placement(
+            config.output.as_deref(),
+            config.respect_exclusive_zones,
+            false,
+        ),
+        placement_changed: placement() != last,
+        window: wants_window.then(compositor::focused).flatten(),
+        keyboard_layout: compositor::keyboard_layout().map(|layout| {
+            let labels = layout_sub_labels(&layout);
+            (layout, labels)
+        }),
+    }
+}
+
+/// Colors of the active theme in the active profile.
+fn theme() -> &'static GridTheme {
+    let config = cfg();
+    let active = ACTIVE_THEME.read().unwrap_or_else(|e| e.into_inner());
+    active
+        .as_deref()
+        .and_then(|name| config.resolved_themes.iter().find(|(n, _)| n == name))
+        .map_or(&config.colors, |(_, theme)| theme)
+}
+
+/// Theme names defined in the active profile, with `default` for its own colors.
+fn theme_names() -> Vec<&'static str> {
+    let mut names = vec![DEFAULT_THEME];
+    names.extend(cfg().resolved_themes.iter().map(|(name, _)| name.as_str()));
+    names
+}
+
+/// Makes `name` the active theme; false if there is no such theme.
+fn switch_theme(name: &str) -> bool {
+    if !theme_names().contains(&name) {
+        return false;
+    }
+    let theme = (name != DEFAULT_THEME).then(|| name.to_string());
+    *ACTIVE_THEME.write().unwrap_or_else(|e| e.into_inner()) = theme;
+    println!("Switched to theme {}.", name);
+    true
+}
+
+fn profile_index(name: &str) -> Option<usize> {
+    CONFIG
+        .get()?
+        .iter()
+        .position(|config| config.profile_name == name)
+}
+
+/// Makes `name` the active profile; false if there is no such profile.
+fn switch_profile(name: &str) -> bool {
+    let Some(index) = profile_index(name) else {
+        return false;
+    };
+    ACTIVE_PROFILE.store(index, Ordering::Relaxed);
+    println!("Switched to profile {}.", name);
+    true
+}
+
+fn profile_names() -> Vec<&'static str> {
+    CONFIG
+        .get()
+        .map(|configs| configs.iter().map(|c| c.profile_name.as_str()).collect())
+        .unwrap_or_default()
+}
+
+pub fn main() -> Result<(), iced_layershell::Error> {
+    let args = cli::parse();
+    paths::set_overrides(paths::Overrides {
+        config: args.config.clone(),
+        state_dir: args.state_dir.clone(),
+        socket: args.socket.clone(),
+    });
+    if let cli::Command::CheckConfig = args.command {
+        let (_, problems) = load_config_checked();
+        for problem in &problems {
+            eprintln!("{}", problem);
+        }
+        if !problems.is_empty() {
+            eprintln!("{} problem(s) found.", problems.len());
+            std::process::exit(1);
+        }
+        println!("Config OK.");
+        return Ok(());
+    }
+    if let cli::Command::Setup = args.command {
+        let written = paths::config_file().is_some_and(|path| setup::run(&path));
+        std::process::exit(if written { 0 } else { 1 });
+    }
+    // First launch from a terminal: offer to write a config before loading it.
+    if matches!(args.command, cli::Command::Daemon)
+        && std::io::stdin().is_terminal()
+        && let Some(path) = paths::config_file().filter(|path| !path.exists())
+    {
+        println!("No rowlink config found.");
+        setup::run(&path);
+    }
+    let mut configs = load_config();
+    // Client commands only talk to the daemon; skip the compositor round trips and grid
+    // layout they never use.
+    let is_daemon = matches!(args.command, cli::Command::Daemon);
+    let env_overrides = EnvOverrides::from_env();
+    // Portrait layouts and panel margins follow the detected output, so find it before
+    // laying out grids.
+    if is_daemon {
+        update_placement(
+            env_overrides
+                .output
+                .as_deref()
+                .or(configs[0].output.as_deref()),
+            configs.iter().any(|c| c.respect_exclusive_zones),
+            configs.iter().any(|c| c.grid_auto_cell_mm.is_some()),
+        );
+    }
+    for config in &mut configs {
+        env_overrides.apply(config);
+        if args.seat.is_some() {
+            config.seat = args.seat.clone();
+        }
+        config.apply_grid_layout();
+        config.apply_auto_grid();
+        config.build_label_table();
+        if is_daemon {
+            config.layout_main_grid();
+        }
+    }
+    if is_daemon {
+        marks::load();
+    }
+    let default_profile = configs[0].default_profile.clone();
+    PRIVATE.store(configs[0].private_mode, Ordering::Relaxed);
+    let _ = CONFIG.set(configs);
+    // An undefined default profile in the config was reported while loading.
+    if let Some(name) = default_profile
+        && !switch_profile(&name)
+        && env_overrides.profile.is_some()
+    {
+        eprintln!("ROWLINK_PROFILE: profile {} is not defined.", name);
+    }
+
+    match args.command {
+        cli::Command::Daemon | cli::Command::CheckConfig | cli::Command::Setup => {}
+        cli::Command::List => {
+            marks::load();
+            for entry in picker::entries() {
+                println!("{}", entry.label);
+            }
+            return Ok(());
+        }
+        cli::Command::Picker(None) => {
+            marks::load();
+            // Row options for rofi: the prompt shown in the input bar.
+            println!("\0prompt\x1frowlink");
+            for entry in picker::entries() {
+                println!("{}", entry.label);
+            }
+            return Ok(());
+        }
+        cli::Command::Picker(Some(entry)) => {
+            // Anything printed would become rofi's next menu, so only errors are reported,
+            // on stderr.
+            match ipc::send(&format!("pick {}", entry.trim())) {
+                Ok(reply) if reply.starts_with("error") => eprint!("{}", reply),
+                Ok(_) => {}
+                Err(e) => eprintln!("Could not reach the rowlink daemon: {}", e),
+            }
+            return Ok(());
+        }
+        cli::Command::Pick(entry) => {
+            let entry = entry.unwrap_or_else(|| {
+                let mut line = String::new();
+                let _ = std::io::stdin().read_line(&mut line);
+                line
+            });
+            send_command(&format!("pick {}", entry.trim()));
+            return Ok(());
+        }
+        cli::Command::Type(text) => {
+            send_command(&format!("type {}", text));
+            return Ok(());
+        }
+        cli::Command::Repeat => {
+            send_command("repeat");
+            return Ok(());
+        }
+        cli::Command::Profile(name) => {
+            send_command(&format!("profile {}", name.unwrap_or_default()));
+            return Ok(());
+        }
+        cli::Command::Theme(name) => {
+            send_command(&format!("theme {}", name.unwrap_or_default()));
+            return Ok(());
+        }
+        cli::Command::Private(on) => {
+            let argument = on.map_or("", |on| if on { "on" } else { "off" });
+            send_command(&format!("private {}", argument));
+            return Ok(());
+        }
+        cli::Command::Mark(words) => {
+            send_command(&format!("mark {}", words.join(" ")));
+            return Ok(());
+        }
+        cli::Command::Purge => {
+            match ipc::send("purge") {
+                Ok(reply) => print!("{}", reply),
+                // Without a daemon nothing holds the data in memory; delete the files.
+                Err(_) => {
+                    purge_state();
+                    println!("ok");
+                }
+            }
+            return Ok(());
+        }
+        cli::Command::Quick => {
+            send_command("quick");
+            return Ok(());
+        }
+        cli::Command::Subscribe => {
+            if let Err(e) = ipc::follow() {
+                eprintln!("Could not reach the rowlink daemon: {}", e);
+                std::process::exit(1);
+            }
+            return Ok(());
+        }
+        cli::Command::Render { out, theme } => {
+            if let Some(name) = theme
+                && !switch_theme(&name)
+            {
+                eprintln!("Unknown theme {}.", name);
+                std::process::exit(1);
+            }
+            if let Err(e) = render::run(&out) {
+                eprintln!("Could not write {}: {}", out.display(), e);
+                std::process::exit(1);
+            }
+            println!("Wrote {}.", out.display());
+            return Ok(());
+        }
+        cli::Command::Heatmap { out, app } => {
+            if let Err(e) = render::heatmap(&out, app.as_deref()) {
+                eprintln!("Could not write {}: {}", out.display(), e);
+                std::process::exit(1);
+            }
+            println!("Wrote {}.", out.display());
+            return Ok(());
+        }
+        cli::Command::Export(out) => {
+            if let Err(e) = transfer::export(&out) {
+                eprintln!("Could not export to {}: {}", out.display(), e);
+                std::process::exit(1);
+            }
+            return Ok(());
+        }
+        cli::Command::Import(file) => {
+            if let Err(e) = transfer::import(&file) {
+                eprintln!("Could not import {}: {}", file.display(), e);
+                std::process::exit(1);
+            }
+            return Ok(());
+        }
+        cli::Command::Config(command) => {
+            print_config(command);
+            return Ok(());
+        }
+        #[cfg(feature = "headless")]
+        cli::Command::Headless(keys) => {
+            match headless::run(&keys) {
+                Ok(events) => events.iter().for_each(|event| println!("{:?}", event)),
+                Err(e) => {
+                    eprintln!("Headless run failed: {}", e);
+                    std::process::exit(1);
+                }
+            }
+            return Ok(());
+        }
+    }
+
+    let renderer = match args.renderer.as_deref() {
+        Some(name) => RendererBackend::from_name(name).unwrap_or_else(|| {
+            eprintln!("Unknown renderer {}, using the configured one.", name);
+            base_config().renderer
+        }),
+        None => base_config().renderer,
+    };
+    if let Some(backend) = renderer.iced_backend() {
+        // SAFETY: nothing else runs yet; iced reads this when it creates the compositor.
+        unsafe { std::env::set_var("ICED_BACKEND", backend) };
+    }
+
+    application(Rowlink::default, namespace, update, view)
+        .subscription(subscription)
+        .style(style)
+        .settings(Settings {
+            id: Some(namespace()),
+            layer_settings: LayerShellSettings {
+                size: Some((1, 1)),
+                anchor: Anchor::Top | Anchor::Left,
+                layer: Layer::Background,
+                keyboard_interactivity: KeyboardInteractivity::None,
+                events_transparent: true,
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+        .run()
+}
+
+/// `rowlink config ...`: where the config lives, what it resolves to, and a starting point.
+fn print_config(command: cli::ConfigCommand) {
+    let config = match command {
+        cli::ConfigCommand::Path => match paths::config_file() {
+            Some(path) => return println!("{}", path.display()),
+            None => {
+                eprintln!("No home directory to keep the config in.");
+                std::process::exit(1);
+            }
+        },
+        cli::ConfigCommand::Dirs => {
+            let show = |path: Option<std::path::PathBuf>| {
+                path.map_or("(no home directory)".to_string(), |p| {
+                    p.display().to_string()
+                })
+            };
+            println!("config: {}", show(paths::config_file()));
+            println!("state:  {}", show(paths::state_dir()));
+            println!("socket: {}", paths::socket().display());
+            return;
+        }
+        cli::ConfigCommand::Dump(name) => {
+            let name = name.unwrap_or_else(|| cfg().profile_name.clone());
+            let Some(index) = profile_index(&name) else {
+                eprintln!("Unknown profile {}.", name);
+                std::process::exit(1);
+            };
+            &CONFIG.get_or_init(load_config)[index]
+        }
+        cli::ConfigCommand::Default => &AppConfig::default(),
+    };
+    // Only the YAML goes to stdout, so `config default > config.yaml` gives a loadable file.
+    match serde_yaml::to_string(config) {
+        Ok(yaml) => print!("{}", yaml),
+        Err(e) => {
+            eprintln!("Could not serialize the config: {}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Forwards one control-socket command to the running daemon and prints its reply.
+/// Deletes the recorded click positions: the history the heatmap is drawn from, the
+/// learned offsets and the marks.
+fn purge_state() {
+    for name in [history::FILE, offsets::FILE, marks::FILE] {
+        paths::remove_state(name);
+    }
+}
+
+fn send_command(command: &str) {
+    match ipc::send(command) {
+        Ok(reply) => print!("{}", reply),
+        Err(e) => {
+            eprintln!("Could not reach the rowlink daemon: {}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+fn namespace() -> String {
+    String::from("rowlink")
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Mode {
+    /// Two-letter main grid, then the sub-grid.
+    Grid,
+    /// One keystroke warps to a ninth of the screen; a second one to a ninth of that and
+    /// closes, or Enter closes right away.
+    Coarse,
+    /// Opened by SIGUSR2: one keystroke warps to a screen corner, edge midpoint or the
+    /// center, no grid drawn.
+    Quick,
+    /// Hints on the targets of the `hint_providers`; typing a hint clicks its target.
+    Hotspot,
+    /// Literal `x,y` coordinates (pixels or percentages) typed into the buffer, confirmed
+    /// with Enter. They are relative to the output or, after Tab, to the focused window.
+    Coordinate,
+    /// keynav-style continuous movement: WASD glides the pointer while held.
+    Move,
+    /// Hints on the visible windows; the picked window is then moved so its top-left
+    /// corner lands on the main grid cell typed next.
+    WindowMove,
+    /// Like `WindowMove`, but the typed cell's far corner becomes the window's new
+    /// bottom-right corner.
+    WindowResize,
+    /// Numpad digits pick ninths of the screen or nudge the pointer; see `NumpadStyle`.
+    Numpad,
+}
+
+impl Mode {
+    /// Name used in events, hooks, the mode OSD and with `mode_in_namespace`.
+    fn name(self) -> &'static str {
+        match self {
+            Mode::Grid => "grid",
+            Mode::Coarse => "coarse",
+            Mode::Quick => "quick",
+            Mode::Hotspot => "hotspot",
+            Mode::Coordinate => "coordinate",
+            Mode::Move => "move",
+            Mode::WindowMove => "window_move",
+            Mode::WindowResize => "window_resize",
+            Mode::Numpad => "numpad",
+        }
+    }
+
+    /// Labels and target for the single-keystroke region modes.
+    fn region(self, width: f32, height: f32, c: char) -> Option<(f32, f32)> {
+        match self {
+            Mode::Quick => map_key_to_label(&cfg().quick_labels, c)
+                .map(|(row, col)| cfg().get_quick_target(width, height, row, col)),
+            Mode::Grid
+            | Mode::Coarse
+            | Mode::Hotspot
+            | Mode::Coordinate
+            | Mode::Move
+            | Mode::WindowMove
+            | Mode::WindowResize
+            | Mode::Numpad => None,
+        }
+    }
+}
+
+struct Rowlink {
+    mode: Mode,
+    input_buffer: String,
+    /// In coarse mode, the region the first key picked, which the second refines.
+    coarse_area: Option<geometry::Rect>,
+    /// The focused window typed coordinates are relative to, in window-scoped coordinate
+    /// entry.
+    coordinate_window: Option<geometry::Rect>,
+    enigo: Option<Enigo>,
+    /// When the last activation signal came in, until its first frame is drawn.
+    activated_at: std::cell::Cell<Option<Instant>>,
+    /// Replaces enigo for pointer actions when a seat or another backend is configured.
+    backend: Option<Box<dyn Mouse>>,
+    /// Wheel for `discrete` scrolls under the virtual pointer, opened on the first one.
+    wheel: std::cell::OnceCell<Option<Box<dyn Mouse>>>,
+    visible: bool,
+    /// Everything that changes while the overlay is open: modes, highlights, rings.
+    grid_cache: canvas::Cache,
+    /// The idle main grid and its fully dimmed variant, drawn above `grid_cache`. They
+    /// only change with the profile or surface size, so typing never rebuilds them.
+    main_grid_cache: canvas::Cache,
+    dimmed_grid_cache: canvas::Cache,
+    current_id: Option<IcedId>,
+    zoomed_cell: Option<(i32, i32)>,
+    /// Sub-cells zoomed into below `zoomed_cell`, with `zoom_depth` above 1.
+    zoom_path: Vec<(i32, i32)>,
+    /// `app_id: title` of the window under the zoomed cell.
+    target_app: Option<String>,
+    last_mouse_pos: Option<(f32, f32)>,
+    keyboard_interactivity: KeyboardInteractivity,
+    key_seen: bool,
+    activation: u64,
+    previous_focus: Option<compositor::WindowRef>,
+    dwell: Option<DwellClick>,
+    chord_keys: Vec<(char, Instant)>,
+    /// The last action, with the grid page its cell was on.
+    last_action: Option<(Message, u32)>,
+    modifiers: keyboard::Modifiers,
+    /// Text sent with `type` over the control socket, typed after the next click.
+    pending_text: Option<String>,
+    selection: Option<Selection>,
+    window_pick: Option<WindowPick>,
+    /// Labelled targets of hint mode, collected when it opens.
+    hints: Vec<(String, hints::Hint)>,
+    /// Loaded on the first cell click with `learn_click_offsets` on.
+    offsets: Option<offsets::Offsets>,
+    /// The last cell click with its target before the learned offset, for move mode to
+    /// learn a correction from.
+    last_cell_click: Option<(offsets::Cell, (f32, f32), Instant)>,
+    /// Loaded on the first recorded click.
+    history: Option<history::History>,
+    /// App focused when the grid opened, which clicks are counted for.
+    focused_app: Option<String>,
+    /// Keyboard layout when the grid opened, and its `layout_sub_labels` entry.
+    keyboard_layout: Option<(String, Option<Vec<String>>)>,
+    /// Adaptive hint targets and their labels.
+    adaptive: Vec<(String, history::Target)>,
+    /// The start of an adaptive hint label typed so far.
+    adaptive_typed: String,
+    scroll_hold: Option<ScrollHold>,
+    scroll_burst: Option<ScrollBurst>,
+    /// Where the last scroll of this activation happened, for the scroll indicator.
+    scroll_point: Option<(f32, f32)>,
+    /// Mode whose name is flashing in the center of the screen, and since when.
+    mode_osd: Option<(Mode, Instant)>,
+    /// Area the numpad keys divide in the `regions` style.
+    numpad_area: geometry::Rect,
+    /// Mode the overlay surface was opened in, which its namespace names.
+    surface_mode: Mode,
+    /// WASD keys currently held in move mode, since when, and when the pointer last moved.
+    move_keys: Vec<char>,
+    move_started: Instant,
+    move_last_tick: Option<Instant>,
+    /// Recent move directions, for `move_smoothing`.
+    move_history: std::collections::VecDeque<(Instant, (f32, f32))>,
+    /// Physical cursor position over the overlay, with `pointer_select`.
+    hover: Option<Point>,
+    /// Cell highlighted with the arrow keys.
+    cursor: Option<CellCursor>,
+    space_hold: Option<SpaceHold>,
+    held_keys: Vec<keyboard::key::Physical>,
+    /// Key events that form the start of a multi-key binding, replayed as ordinary input
+    /// if the sequence goes nowhere.
+    binding_keys: Vec<keyboard::Event>,
+    replaying_keys: bool,
+}
+
+/// A cell picked with the arrow keys, on the main grid or in the sub-grid of `zoomed`. It
+/// is out of view once the grid changes level.
+#[derive(Debug, Clone, Copy)]
+struct CellCursor {
+    zoomed: Option<(i32, i32)>,
+    cell: (i32, i32),
+}
+
+/// Space on the final selection: a tap clicks, holding past `hold_drag_ms` drags.
+enum SpaceHold {
+    /// Pressed, not yet known to be a tap or a hold.
+    Pending {
+        target: (f32, f32),
+        action: Message,
+        started: Instant,
+    },
+    /// The button is down and the grid is hidden until Space is released.
+    Dragging,
+}
+
+/// A held scroll key, repeated and accelerated by the scroll tick instead of autorepeat.
+struct ScrollHold {
+    key: String,
+    direction: (i32, i32),
+    started: Instant,
+}
+
+/// The rest of a `d`/`u` page scroll, delivered a few lines per tick.
+struct ScrollBurst {
+    direction: (i32, i32),
+    remaining: i32,
+}
+
+/// Progress through a window mode: first a hint picks the window, then the main grid picks
+/// where it goes.
+enum WindowPick {
+    Choosing(Vec<(String, compositor::Toplevel)>),
+    Placing(compositor::Toplevel),
+}
+
+/// Two-point selection: the first pick presses the button, the grid reopens, and the
+/// second pick drags there and releases.
+#[derive(Debug, Clone, Copy)]
+enum Selection {
+    Armed,
+    Dragging((f32, f32)),
+}
+
+/// A pending dwell click: the pointer already sits on `target`, `action` fires once the
+/// countdown ring completes.
+struct DwellClick {
+    target: (f32, f32),
+    action: Message,
+    started: Instant,
+}
+
+impl Rowlink {
+    fn perform_pointer_action<F>(&mut self, mut action: F)
+    where
+        F: FnMut(&mut dyn Mouse) -> InputResult<()>,
+    {
+        if let Some(backend) = self.backend.as_mut() {
+            if let Err(e) = action(backend.as_mut()) {
+                eprintln!("{:?} backend error: {:?}", cfg().backend, e);
+            }
+            return;
+        }
+
+        if let Some(enigo) = self.enigo()
+            && let Err(e) = action(enigo)
+        {
+            eprintln!("Enigo Error (will retry): {:?}", e);
+
+            self.enigo = None;
+
+            if let Some(enigo_retry) = self.enigo() {
+                let _ = action(enigo_retry);
+            }
+        }
+    }
+
+    /// The enigo connection, made on first use since connecting costs a Wayland round
+    /// trip and a keymap upload.
+    fn enigo(&mut self) -> Option<&mut Enigo> {
+        if self.enigo.is_none() {
+            match Enigo::new(&EnigoSettings::default()) {
+                Ok(enigo) => self.enigo = Some(enigo),
+                Err(e) => eprintln!("Enigo init failed: {:?}", e),
+            }
+        }
+        self.enigo.as_mut()
+    }
+
+    /// Switches modes, flashing the new mode's name when it changes.
+    fn enter_mode(&mut self, mode: Mode) {
+        if self.mode != mode && cfg().mode_osd_ms > 0 {
+            self.mode_osd = Some((mode, Instant::now()));
+        }
+        if self.mode != mode {
+            announce::mode(mode.name());
+        }
+        self.mode = mode;
+    }
+
+    /// Sub-grid keys for the keyboard layout the grid opened with.
+    fn sub_labels(&self) -> &[String] {
+        match &self.keyboard_layout {
+            Some((_, Some(labels))) => labels,
+            _ => &cfg().sub_labels,
+        }
+    }
+
+    /// Switches the sub-grid keys to those of the active keyboard layout.
+    fn update_keyboard_layout(&mut self, layout: String, labels: Option<Vec<String>>) {
+        let changed = self
+            .keyboard_layout
+            .as_ref()
+            .is_none_or(|(name, _)| *name != layout);
+        match &labels {
+            _ if !changed => {}
+            Some(labels) => println!("Keyboard layout {}: sub-grid keys {:?}.", layout, labels),
+            None => println!("Keyboard layout {}: default sub-grid keys.", layout),
+        }
+        self.keyboard_layout = Some((layout, labels));
+    }
+
+    /// Shows the sub-grid of a main cell, and looks up the window its click would land on.
+    fn zoom_into(&mut self, (row, col): (i32, i32)) -> iced::Task<Message> {
+        self.zoomed_cell = Some((row, col));
+        self.zoom_path.clear();
+        ipc::emit(json!({ "event": "zoomed", "row": row, "col": col, "depth": 1 }));
+        self.input_buffer.clear();
+        self.grid_cache.clear();
+        self.target_app = None;
+        if !cfg().show_target_app {
+            return iced::Task::none();
+        }
+        let (x, y) = center_target(Some((row, col)));
+        let (dx, dy) = self.learned_offset(&offset_cell((row, col), None));
+        let activation = self.activation;
+        with_toplevel_at((x + dx, y + dy), move |toplevel| {
+            let name = toplevel.map(|toplevel| {
+                let title: String = toplevel
+                    .title
+                    .chars()
+                    .take(TARGET_APP_TITLE_CHARS)
+                    .collect();
+                format!("{}: {}", toplevel.app_id, title)
+            });
+            Message::TargetAppFound((row, col), activation, name)
+        })
+    }
+
+    /// Drops the cached main grid layers after the active config changed.
+    fn clear_static_layers(&mut self) {
+        self.main_grid_cache.clear();
+        self.dimmed_grid_cache.clear();
+    }
+
+    /// Shows grid page `page`, with `grid_pages` above 1.
+    fn set_page(&mut self, page: u32) {
+        if page != active_page() {
+            ACTIVE_PAGE.store(page, Ordering::Relaxed);
+            self.grid_cache.clear();
+            self.clear_static_layers();
+        }
+    }
+
+    /// Remembers `action` for repeating, with the page its cell is on.
+    fn remember_action(&mut self, action: &Message) {
+        self.last_action = Some((action.clone(), active_page()));
+    }
+
+    /// Adaptive hints on the focused app's most clicked targets on the active page.
+    fn load_adaptive(&mut self) {
+        self.adaptive.clear();
+        self.adaptive_typed.clear();
+        if cfg().adaptive_hints > 0
+            && let Some(app) = &self.focused_app
+        {
+            let grid = (cfg().grid_rows(), cfg().grid_cols());
+            let targets = self.history.get_or_insert_with(history::History::load).top(
+                app,
+                grid,
+                active_page(),
+                cfg().adaptive_hints,
+            );
+            let mut keys: Vec<char> = Vec::new();
+            for c in cfg().adaptive_hint_keys.chars() {
+                if !keys.contains(&c) {
+                    keys.push(c);
+                }
+            }
+            let labels = labels::hint_labels(targets.len(), &keys);
+            self.adaptive = labels.into_iter().zip(targets).collect();
+        }
+    }
+
+    /// Scroll length per line through the pointer: one click for the other backends, axis
+    /// units for the virtual pointer.
+    fn scroll_step(&self) -> i32 {
+        match cfg().scroll_style {
+            _ if self.backend.is_some() => 1,
+            ScrollStyle::Discrete => WHEEL_AXIS_STEP,
+            ScrollStyle::Smooth => cfg().scroll_smooth_step,
+        }
+    }
+
+    /// The uinput wheel, when `discrete` scrolls go through it rather than the pointer.
+    fn wheel(&mut self) -> Option<&mut Box<dyn Mouse>> {
+        if self.backend.is_some() || cfg().scroll_style != ScrollStyle::Discrete {
+            return None;
+        }
+        self.wheel.get_or_init(init_wheel);
+        self.wheel.get_mut()?.as_mut()
+    }
+
+    /// Scroll lines scaled by the focused app's `scroll_app_multipliers` entry.
+    fn app_scroll(&self, lines: i32) -> i32 {
+        let Some(factor) = self
+            .focused_app
+            .as_ref()
+            .and_then(|app| cfg().scroll_app_multipliers.get(app))
+        else {
+            return lines;
+        };
+        let scaled = (lines as f32 * factor).round() as i32;
+        if lines != 0 && scaled == 0 {
+            lines.signum()
+        } else {
+            scaled
+        }
+    }
+
+    /// `target` on `cell` shifted by the offset learned for it, remembered so that a
+    /// correction right after can be learned.
+    fn learned_target(&mut self, cell: offsets::Cell, target: (f32, f32)) -> (f32, f32) {
+        if !cfg().learn_click_offsets {
+            return target;
+        }
+        let (dx, dy) = self.learned_offset(&cell);
+        self.last_cell_click = Some((cell, target, Instant::now()));
+        (target.0 + dx, target.1 + dy)
+    }
+
+    /// The offset learned for `cell`; zero with `learn_click_offsets` off.
+    fn learned_offset(&mut self, cell: &offsets::Cell) -> (f32, f32) {
+        if !cfg().learn_click_offsets {
+            return (0.0, 0.0);
+        }
+        self.offsets
+            .get_or_insert_with(offsets::Offsets::load)
+            .get(cell)
+    }
+
+    /// Counts a click on `cell` for the focused app.
+    fn record_click(&mut self, cell: (i32, i32), sub_cell: Option<(i32, i32)>) {
+        let recording = cfg().click_history && !PRIVATE.load(Ordering::Relaxed);
+        let Some(app) = self.focused_app.as_deref().filter(|_| recording) else {
+            return;
+        };
+        let grid = (cfg().grid_rows(), cfg().grid_cols());
+        self.history
+            .get_or_insert_with(history::History::load)
+            .record(app, grid, active_page(), cell, sub_cell);
+    }
+
+    /// Learns a move-mode click at `(x, y)` as a correction of the last cell click, if it
+    /// came within `click_offset_window_ms` and stayed within a main cell of the target.
+    fn learn_correction(&mut self, (x, y): (f32, f32)) {
+        let Some((cell, (target_x, target_y), clicked)) = self.last_cell_click.take() else {
+            return;
+        };
+        if PRIVATE.load(Ordering::Relaxed) {
+            return;
+        }
+        let (width, height) = cfg().output_size();
+        let (cell_width, cell_height) = cfg().get_main_cell_size(width, height);
+        let (dx, dy) = (x - target_x, y - target_y);
+        if clicked.elapsed() > Duration::from_millis(cfg().click_offset_window_ms)
+            || dx.abs() > cell_width
+            || dy.abs() > cell_height
+        {
+            return;
+        }
+        self.offsets
+            .get_or_insert_with(offsets::Offsets::load)
+            .record(cell, dx, dy);
+    }
+
+    /// Cell under the physical cursor: a sub-cell while zoomed, else a main cell.
+    fn hovered_cell(&self) -> Option<(i32, i32)> {
+        if let Some(cursor) = self.cursor
+            && cursor.zoomed == self.zoomed_cell
+        {
+            return Some(cursor.cell);
+        }
+        let position = self.hover?;
+        let (width, height) = (cfg().screen_width, cfg().screen_height);
+        match self.zoom_grid(width, height) {
+            Some(grid) => grid.cell_at(position.x, position.y),
+            None => cfg().get_main_cell_at(width, height, position.x, position.y),
+        }
+    }
+
+    /// The sub-grid on screen: the zoomed cell's, narrowed down by each deeper pick.
+    fn zoom_grid(&self, width: f32, height: f32) -> Option<geometry::Grid> {
+        let (row, col) = self.zoomed_cell?;
+        let mut grid = cfg().sub_grid(width, height, row, col);
+        for &(sub_row, sub_col) in &self.zoom_path {
+            grid = grid.sub_grid(sub_row, sub_col, cfg().sub_rows, cfg().sub_cols, 0.0);
+        }
+        Some(grid)
+    }
+
+    /// Picks a sub-cell of the sub-grid on screen: zooms into it while `zoom_depth` allows
+    /// and it isn't yet precise, otherwise clicks it.
+    fn pick_sub_cell(
+        &mut self,
+        (sub_row, sub_col): (i32, i32),
+        is_double: bool,
+    ) -> iced::Task<Message> {
+        let (width, height) = (cfg().screen_width, cfg().screen_height);
+        let (Some((main_row, main_col)), Some(grid)) =
+            (self.zoomed_cell, self.zoom_grid(width, height))
+        else {
+            return iced::Task::none();
+        };
+        let cell = grid.cell(sub_row, sub_col);
+        let precise = is_precise(&cell);
+        let depth = self.zoom_path.len() as u32 + 1;
+        if depth < cfg().zoom_depth && !precise {
+            self.zoom_path.push((sub_row, sub_col));
+            self.input_buffer.clear();
+            self.cursor = None;
+            self.grid_cache.clear();
+            ipc::emit(json!({
+                "event": "zoomed",
+                "row": main_row,
+                "col": main_col,
+                "depth": depth + 1,
+            }));
+            return iced::Task::none();
+        }
+        let Some(&first) = self.zoom_path.first() else {
+            return self.confirm_selection(
+                precision_target(main_row, main_col, sub_row, sub_col),
+                Message::ExecuteMovePrecision(main_row, main_col, sub_row, sub_col, is_double),
+            );
+        };
+        // Deeper picks are counted under the first-level sub-cell they lie in.
+        self.record_click((main_row, main_col), Some(first));
+        let (x, y) = cfg().click_point(cell);
+        self.confirm_selection((x, y), Message::ExecuteClick(x, y, is_double))
+    }
+
+    /// Keyboard emulation always goes through enigo; seat binding only covers the pointer.
+    fn type_text(&mut self, text: &str) {
+        if let Some(enigo) = self.enigo()
+            && let Err(e) = enigo.text(text)
+        {
+            eprintln!("Enigo Error while typing: {:?}", e);
+        }
+    }
+
+    /// Presses `key` while holding `modifier`, through enigo like `type_text`.
+    fn press_shortcut(&mut self, modifier: enigo::Key, key: enigo::Key) {
+        if let Some(enigo) = self.enigo() {
+            let pressed = enigo
+                .key(modifier, Direction::Press)
+                .and_then(|_| enigo.key(key, Direction::Click));
+            // Release the modifier even when the key failed, so it isn't left stuck.
+            let released = enigo.key(modifier, Direction::Release);
+            if let Err(e) = pressed.and(released) {
+                eprintln!("Enigo Error while pressing a shortcut: {:?}", e);
+            }
+        }
+    }
+
+    /// Replaces the current surface with an interactive overlay using the current
+    /// keyboard interactivity.
+    fn open_overlay(&mut self) -> iced::Task<Message> {
+        self.surface_mode = self.mode;
+        let mut settings = get_layer_settings(self.keyboard_interactivity);
+        if cfg().mode_in_namespace {
+            settings.namespace = Some(format!("{}-{}", namespace(), self.mode.name()));
+        }
+        let (new_id, spawn_task) = Message::layershell_open(settings);
+        let old_id = self.current_id.replace(new_id).unwrap_or(IcedId::unique());
+        iced::Task::batch(vec![
+            iced::Task::done(Message::RemoveWindow(old_id)),
+            spawn_task,
+        ])
+    }
+
+    /// Tears down the interactive surface and swaps in the idle background one.
+    /// `action` runs between the removal and the respawn, once the overlay is gone.
+    fn hide_overlay(&mut self, action: Option<Message>) -> iced::Task<Message> {
+        self.visible = false;
+        self.zoom_path.clear();
+        ipc::emit(json!({ "event": "hidden" }));
+        run_hook(cfg().on_hide.as_deref(), self.mode);
+        self.mode = Mode::Grid;
+        self.input_buffer.clear();
+        self.zoomed_cell = None;
+        self.dwell = None;
+        self.chord_keys.clear();
+        self.modifiers = keyboard::Modifiers::default();
+        self.pending_text = None;
+        self.scroll_hold = None;
+        self.scroll_burst = None;
+        self.scroll_point = None;
+        self.mode_osd = None;
+        self.move_keys.clear();
+        self.hover = None;
+        self.cursor = None;
+        self.held_keys.clear();
+        self.binding_keys.clear();
+        self.window_pick = None;
+        self.hints.clear();
+        self.adaptive.clear();
+        self.adaptive_typed.clear();
+        self.grid_cache.clear();
+        inhibit::release();
+        let dragging = matches!(self.space_hold.take(), Some(SpaceHold::Dragging));
+        if matches!(self.selection.take(), Some(Selection::Dragging(_))) || dragging {
+            // Cancelled halfway; don't leave the button held down.
+            self.perform_pointer_action(|pointer| {
+                pointer.button(primary_button(), Direction::Release)
+            });
+        }
+
+        let (new_id, spawn_task) =
+            Message::layershell_open(get_layer_settings(KeyboardInteractivity::None));
+        let old_id = self.current_id.replace(new_id).unwrap();
+
+        let mut tasks = vec![
+            iced::Task::done(Message::RemoveWindow(old_id)),
+            iced::Task::done(Message::RestoreFocus),
+        ];
+        tasks.extend(action.map(iced::Task::done));
+        tasks.push(spawn_task);
+        iced::Task::batch(tasks)
+    }
+
+    /// Finishes a selection whose click lands on `target`. With dwell clicking enabled the
+    /// pointer is warped first and `action` only runs once the countdown completes.
+    /// Alt, or text pending from the control socket, turns the click into click-and-type.
+    fn confirm_selection(&mut self, target: (f32, f32), action: Message) -> iced::Task<Message> {
+        let snapped = snap_to_windows(target);
+        let action = match action {
+            Message::ExecuteMovePrecision(.., is_double)
+            | Message::ExecuteMoveCenter(_, is_double)
+            | Message::ExecuteClick(_, _, is_double)
+                if snapped != target =>
+            {
+                Message::ExecuteClick(snapped.0, snapped.1, is_double)
+            }
+            action => action,
+        };
+        let target = snapped;
+        ipc::emit(json!({ "event": "cell_selected", "x": target.0, "y": target.1 }));
+        let action = match (self.selection.take(), self.pending_text.take()) {
+            (Some(Selection::Armed), _) => Message::ExecuteSelectStart(target.0, target.1),
+            (Some(Selection::Dragging(from)), _) => {
+                Message::ExecuteSelectEnd(from.0, from.1, target.0, target.1)
+            }
+            (None, Some(text)) => Message::ExecuteClickType(target.0, target.1, Some(text)),
+            (None, None) if self.modifiers.alt() => {
+                Message::ExecuteClickType(target.0, target.1, None)
+            }
+            (None, None) => action,
+        };
+        if cfg().dwell_click_ms == 0 {
+            return self.hide_overlay(Some(action));
+        }
+        self.input_buffer.clear();
+        self.zoomed_cell = None;
+        self.zoom_path.clear();
+        self.grid_cache.clear();
+        self.dwell = Some(DwellClick {
+            target,
+            action,
+            started: Instant::now(),
+        });
+        iced::Task::done(Message::ExecuteMove(target.0, target.1))
+    }
+}
+
+fn init_backend() -> Option<Box<dyn Mouse>> {
+    if cfg().backend == Backend::Pointer {
+        return init_seat_pointer().map(|seat| Box::new(seat) as Box<dyn Mouse>);
+    }
+    match init_uinput_device() {
+        Ok(device) => Some(device),
+        Err(e) => {
+            eprintln!(
+                "uinput {:?} device unavailable, using the pointer: {:?}",
+                cfg().backend,
+                e
+            );
+            init_seat_pointer().map(|seat| Box::new(seat) as Box<dyn Mouse>)
+        }
+    }
+}
+
+#[cfg(feature = "uinput")]
+fn init_uinput_device() -> std::io::Result<Box<dyn Mouse>> {
+    let (width, height) = (cfg().screen_width, cfg().screen_height);
+    match cfg().backend {
+        Backend::Touch => uinput::TouchScreen::new(width, height).map(|d| Box::new(d) as _),
+        Backend::Stylus => {
+            uinput::Stylus::new(width, height, cfg().stylus_pressure).map(|d| Box::new(d) as _)
+        }
+        Backend::Pointer => unreachable!("the pointer backend needs no uinput device"),
+    }
+}
+
+#[cfg(not(feature = "uinput"))]
+fn init_uinput_device() -> std::io::Result<Box<dyn Mouse>> {
+    Err(std::io::Error::new(
+        std::io::ErrorKind::Unsupported,
+        "rowlink was built without the uinput feature",
+    ))
+}
+
+#[cfg(feature = "uinput")]
+fn init_wheel() -> Option<Box<dyn Mouse>> {
+    match uinput::Wheel::new() {
+        Ok(wheel) => Some(Box::new(wheel)),
+        Err(e) => {
+            eprintln!(
+                "uinput wheel unavailable, scrolling with axis motion: {:?}",
+                e
+            );
+            None
+        }
+    }
+}
+
+#[cfg(not(feature = "uinput"))]
+fn init_wheel() -> Option<Box<dyn Mouse>> {
+    None
+}
+
+/// The virtual pointer protocol has no notion of seats, so a non-default seat is only
+/// honored where the compositor can drive a seat's cursor over IPC (Sway).
+fn init_seat_pointer() -> Option<pointer::SwaySeat> {
+    let seat = cfg().seat.clone()?;
+    if compositor::detect() == Some(compositor::Compositor::Sway) {
+        println!("Binding pointer emulation to seat {}.", seat);
+        Some(pointer::SwaySeat::new(seat))
+    } else {
+        eprintln!(
+            "Seat {} requested, but seat selection needs Sway; using the default seat.",
+            seat
+        );
+        None
+    }
+}
+
+impl Default for Rowlink {
+    fn default() -> Self {
+        Self {
+            mode: Mode::Grid,
+            input_buffer: String::new(),
+            coarse_area: None,
+            coordinate_window: None,
+            enigo: None,
+            activated_at: std::cell::Cell::new(None),
+            backend: init_backend(),
+            wheel: std::cell::OnceCell::new(),
+            visible: false,
+            grid_cache: canvas::Cache::default(),
+            main_grid_cache: canvas::Cache::default(),
+            dimmed_grid_cache: canvas::Cache::default(),
+            current_id: None,
+            zoomed_cell: None,
+            zoom_path: Vec::new(),
+            target_app: None,
+            last_mouse_pos: None,
+            keyboard_interactivity: match cfg().keyboard_mode {
+                KeyboardMode::Exclusive => KeyboardInteractivity::Exclusive,
+                KeyboardMode::Auto | KeyboardMode::OnDemand => KeyboardInteractivity::OnDemand,
+            },
+            key_seen: false,
+            activation: 0,
+            previous_focus: None,
+            dwell: None,
+            chord_keys: Vec::new(),
+            last_action: None,
+            modifiers: keyboard::Modifiers::default(),
+            pending_text: None,
+            selection: None,
+            window_pick: None,
+            hints: Vec::new(),
+            scroll_hold: None,
+            scroll_burst: None,
+            scroll_point: None,
+            mode_osd: None,
+            surface_mode: Mode::Grid,
+            numpad_area: screen_rect(),
+            move_keys: Vec::new(),
+            move_started: Instant::now(),
+            move_last_tick: None,
+            move_history: std::collections::VecDeque::new(),
+            hover: None,
+            cursor: None,
+            space_hold: None,
+            held_keys: Vec::new(),
+            binding_keys: Vec::new(),
+            replaying_keys: false,
+            offsets: None,
+            last_cell_click: None,
+            history: None,
+            focused_app: None,
+            keyboard_layout: None,
+            adaptive: Vec::new(),
+            adaptive_typed: String::new(),
+        }
+    }
+}
+
+#[to_layer_message(multi)]
+#[derive(Debug, Clone)]
+enum Message {
+    Startup,
+    SignalReceived(Mode),
+    /// Shows the overlay in a mode once the compositor said what has the focus.
+    Activate(Mode, Focus),
+    ExecuteMovePrecision(i32, i32, i32, i32, bool),
+    ExecuteMoveCenter(Option<(i32, i32)>, bool),
+    ExecuteScroll(Option<(i32, i32)>, i32, i32),
+    ExecuteMove(f32, f32),
+    ExecuteClick(f32, f32, bool),
+    /// Selects a whole line or paragraph in most apps.
+    ExecuteTripleClick(f32, f32),
+    ExecuteButtonClick(f32, f32, Button),
+    /// Click, then type the given text (or the configured text / clipboard when `None`).
+    ExecuteClickType(f32, f32, Option<String>),
+    /// Paste at a point: a middle click for `middle_click_paste_apps`, otherwise a click
+    /// followed by Shift+Insert.
+    ExecutePaste(f32, f32),
+    /// Pastes at a point once the window there is known: a middle click when true.
+    PasteAt(f32, f32, bool),
+    /// Shift+Insert, a moment after the paste click.
+    PasteShortcut,
+    /// The window under a zoomed cell's target, for `show_target_app`, looked up in the
+    /// background during one activation.
+    TargetAppFound((i32, i32), u64, Option<String>),
+    /// The focused window's area, looked up for window-scoped coordinate entry.
+    CoordinateWindowFound(Option<geometry::Rect>),
+    TypeRequested(String),
+    /// Opens the quick targets, from the control socket.
+    QuickRequested,
+    SwitchProfile(String),
+    SwitchTheme(String),
+    /// Deletes the click history and learned offsets, on disk and in memory.
+    PurgeHistory,
+    /// Marks the last position rowlink moved the pointer to.
+    MarkPointer(String),
+    ExecuteSelectStart(f32, f32),
+    ExecuteSelectEnd(f32, f32, f32, f32),
+    PickTarget(picker::Target),
+    MoveWindow(compositor::WindowRef, f32, f32),
+    /// A window, its output-local top-left corner and its new size.
+    ResizeWindow(compositor::WindowRef, (f32, f32), (f32, f32)),
+    DwellTick,
+    OsdTick,
+    ScrollTick,
+    MoveTick,
+    KeyboardGrabCheck(u64),
+    HoldDragCheck,
+    RestoreFocus,
+    /// Replays the last action, closing the overlay first if it is open.
+    RepeatLastAction,
+    IcedEvent(Event),
+}
+
+/// `KeyboardInteractivity::None` yields the idle background surface, anything else the
+/// fullscreen overlay with that interactivity.
+fn get_layer_settings(keyboard: KeyboardInteractivity) -> NewLayerShellSettings {
+    if keyboard != KeyboardInteractivity::None {
+        NewLayerShellSettings {
+            size: None,
+            anchor: Anchor::all(),
+            layer: Layer::Overlay,
+            exclusive_zone: Some(-1),
+            events_transparent: !cfg().overlay_takes_input(),
+            keyboard_interactivity: keyboard,
+            output_option: cfg()
+                .output
+                .clone()
+                .map_or(OutputOption::None, OutputOption::OutputName),
+            ..Default::default()
+        }
+    } else {
+        NewLayerShellSettings {
+            anchor: Anchor::all(),
+            layer: Layer::Background,
+            keyboard_interactivity: KeyboardInteractivity::None,
+            events_transparent: true,
+            ..Default::default()
+        }
+    }
+}
+
+// --- Click Notifications ---
+// With `notify_clicks` on, every click is reported through a desktop notification, to
+// learn the grid by and to debug coordinates that land off target.
+fn notify_click(button: Button, count: u32, x: f32, y: f32) {
+    let name = format!("{:?}", button).to_lowercase();
+    ipc::emit(json!({ "event": "clicked", "button": name, "count": count, "x": x, "y": y }));
+    if !cfg().notify_clicks {
+        return;
+    }
+    let kind = match count {
+        1 => "",
+        2 => "double ",
+        _ => "triple ",
+    };
+    let body = format!("{:?} {}click at {}, {}", button, kind, x.round(), y.round());
+    let timeout = format!("--expire-time={}", cfg().notify_click_ms);
+    // Waited on in the background so the click path doesn't block on the daemon.
+    std::thread::spawn(move || {
+        let status = std::process::Command::new("notify-send")
+            .args(["--app-name=rowlink", &timeout, "rowlink", &body])
+            .status();
+        if let Err(e) = status {
+            eprintln!("Click notification failed: {:?}", e);
+        }
+    });
+}
+
+// --- Lifecycle Hooks ---
+fn run_hook(command: Option<&str>, mode: Mode) {
+    let Some(command) = command.map(str::to_string) else {
+        return;
+    };
+    // In the background, like click notifications, so a slow hook can't stall the grid.
+    std::thread::spawn(move || {
+        let status = std::process::Command::new("sh")
+            .args(["-c", &command])
+            .env("ROWLINK_MODE", mode.name())
+            .status();
+        match status {
+            Ok(status) if !status.success() => eprintln!("Hook {:?} failed: {}", command, status),
+            Ok(_) => {}
+            Err(e) => eprintln!("Hook {:?} failed: {:?}", command, e),
+        }
+    });
+}
+
+/// Matches a key against a config key name: a single character (case-insensitive) or a
+/// named key such as `Tab`, `Enter` or `Space`.
+fn key_matches(key: &keyboard::Key, name: &str) -> bool {
+    match key {
+        keyboard::Key::Character(c) => c.eq_ignore_ascii_case(name),
+        keyboard::Key::Named(named) => format!("{:?}", named).eq_ignore_ascii_case(name),
+        _ => false,
+    }
+}
+
+/// Turns `cancel_keys` into Escape, and on the grid `confirm_keys` into Space, which is
+/// what the key handlers look for. Escape and Space themselves only count when listed.
+fn remap_confirm_cancel(mode: Mode, mut event: keyboard::Event) -> keyboard::Event {
+    let (keyboard::Event::KeyPressed { key, .. } | keyboard::Event::KeyReleased { key, .. }) =
+        &mut event
+    else {
+        return event;
+    };
+    let listed = |names: &[String]| names.iter().any(|name| key_matches(key, name));
+    let escape = keyboard::Key::Named(keyboard::key::Named::Escape);
+    let space = keyboard::Key::Named(keyboard::key::Named::Space);
+    let grid = mode == Mode::Grid;
+    if listed(&cfg().cancel_keys) {
+        *key = escape;
+    } else if grid && listed(&cfg().confirm_keys) {
+        *key = space;
+    } else if *key == escape || (grid && *key == space) {
+        *key = keyboard::Key::Unidentified;
+    }
+    event
+}
+
+/// Sub-grid keys for a keyboard layout: its `layout_sub_labels` entry by exact name, else
+/// the first key contained in the name, else what the `sub_labels` keys type under it.
+/// Fitted to the sub-grid.
+fn layout_sub_labels(layout: &str) -> Option<Vec<String>> {
+    let lower = layout.to_lowercase();
+    let layouts = &cfg().layout_sub_labels;
+    let labels = layouts
+        .get(layout)
+        .or_else(|| {
+            let mut keys: Vec<&String> = layouts.keys().collect();
+            keys.sort();
+            keys.into_iter()
+                .find(|key| lower.contains(&key.to_lowercase()))
+                .map(|key| &layouts[key])
+        })
+        .cloned()
+        .or_else(|| keymap::sub_labels(layout, &cfg().sub_labels))?;
+    Some(fit_sub_labels(&labels))
+}
+
+/// `labels` cut to `sub_rows` rows of `sub_cols` keys; missing keys are taken from
+/// `sub_labels`, so every cell stays reachable.
+fn fit_sub_labels(labels: &[String]) -> Vec<String> {
+    let (rows, cols) = (
+        cfg().sub_rows.max(0) as usize,
+        cfg().sub_cols.max(0) as usize,
+    );
+    (0..rows)
+        .map(|row| {
+            let keys: Vec<char> = labels.get(row).map_or(Vec::new(), |r| r.chars().collect());
+            let defaults: Vec<char> = cfg()
+                .sub_labels
+                .get(row)
+                .map_or(Vec::new(), |r| r.chars().collect());
+            (0..cols)
+                .filter_map(|col| keys.get(col).or(defaults.get(col)))
+                .collect()
+        })
+        .collect()
+}
+
+fn map_key_to_label(labels: &[String], c: char) -> Option<(i32, i32)> {
+    let input_char = label_char(c);
+
+    for (r_idx, row_str) in labels.iter().enumerate() {
+        for (c_idx, key_char) in row_str.chars().enumerate() {
+            if label_char(key_char) == input_char {
+                return Some((r_idx as i32, c_idx as i32));
+            }
+        }
+    }
+    None
+}
+
+/// Two letters held down together whose presses landed within the chord window select and
+/// click a cell in one stroke; the row is whichever key went down first.
+fn is_chord(held: &[(char, Instant)]) -> bool {
+    match held {
+        [(_, first), (_, second)] => {
+            second.duration_since(*first) <= Duration::from_millis(cfg().chord_window_ms)
+        }
+        _ => false,
+    }
+}
+
+fn center_target(target_cell: Option<(i32, i32)>) -> (f32, f32) {
+    match target_cell {
+        Some((r, c)) => cfg().get_main_cell_center(cfg().screen_width, cfg().screen_height, r, c),
+        None => (cfg().screen_width / HALF, cfg().screen_height / HALF),
+    }
+}
+
+/// Runs `query` on a thread, as compositor queries spawn its client and would stall the
+/// UI, and turns the result into a message.
+fn in_background<T: Default + Send + 'static>(
+    query: impl FnOnce() -> T + Send + 'static,
+    then: impl FnOnce(T) -> Message + Send + 'static,
+) -> iced::Task<Message> {
+    let (sender, receiver) = oneshot::channel();
+    std::thread::spawn(move || {
+        let _ = sender.send(query());
+    });
+    iced::Task::perform(receiver, move |result| then(result.unwrap_or_default()))
+}
+
+/// The window on top at `(x, y)`, looked up in the background.
+fn with_toplevel_at(
+    (x, y): (f32, f32),
+    then: impl FnOnce(Option<compositor::Toplevel>) -> Message + Send + 'static,
+) -> iced::Task<Message> {
+    in_background(move || compositor::toplevel_at(x, y), then)
+}
+
+/// Where an adaptive hint clicks, and the click itself.
+fn adaptive_action(target: &history::Target, is_double: bool) -> ((f32, f32), Message) {
+    let (row, col) = target.cell;
+    match target.sub_cell {
+        Some((sub_row, sub_col)) => (
+            precision_target(row, col, sub_row, sub_col),
+            Message::ExecuteMovePrecision(row, col, sub_row, sub_col, is_double),
+        ),
+        None => (
+            center_target(Some(target.cell)),
+            Message::ExecuteMoveCenter(Some(target.cell), is_double),
+        ),
+    }
+}
+
+/// A main cell, or a sub-cell of it, on the active profile, grid and page, for click
+/// offset learning; other profiles, grids and pages put the same cell elsewhere.
+fn offset_cell(cell: (i32, i32), sub_cell: Option<(i32, i32)>) -> offsets::Cell {
+    offsets::Cell {
+        profile: cfg().profile_name.clone(),
+        grid: (cfg().grid_rows(), cfg().grid_cols()),
+        page: active_page(),
+        cell,
+        sub_cell,
+    }
+}
+
+fn precision_target(main_row: i32, main_col: i32, sub_row: i32, sub_col: i32) -> (f32, f32) {
+    cfg().get_precision_target(
+        cfg().screen_width,
+        cfg().screen_height,
+        main_row,
+        main_col,
+        sub_row,
+        sub_col,
+    )
+}
+
+// --- Window Snapping ---
+
+/// A point near an edge of the window under it moved onto that edge, and one in its title
+/// bar onto the bar's middle line, so that border drags and title-bar clicks hit even
+/// from coarse cells.
+fn snap_to_windows((x, y): (f32, f32)) -> (f32, f32) {
+    let snap = cfg().snap_px;
+    if snap <= 0.0 {
+        return (x, y);
+    }
+    let Some(window) = compositor::toplevels()
+        .into_iter()
+        .filter(|t| {
+            x >= t.x - snap
+                && x < t.x + t.width + snap
+                && y >= t.y - snap
+                && y < t.y + t.height + snap
+        })
+        .min_by(|a, b| (a.width * a.height).total_cmp(&(b.width * b.height)))
+    else {
+        return (x, y);
+    };
+    let (left, right) = (window.x, window.x + window.width - 1.0);
+    let (top, bottom) = (window.y, window.y + window.height - 1.0);
+    let title_bar = cfg().title_bar_px;
+    let x = if (x - left).abs() <= snap {
+        left
+    } else if (x - right).abs() <= snap {
+        right
+    } else {
+        x
+    };
+    let y = if title_bar > 0.0 && y >= top && y < top + title_bar {
+        top + title_bar / HALF
+    } else if (y - top).abs() <= snap {
+        top
+    } else if (y - bottom).abs() <= snap {
+        bottom
+    } else {
+        y
+    };
+    (x, y)
+}
+
+fn move_sequence(pointer: &mut dyn Mouse, x: f32, y: f32) -> InputResult<()> {
     std::thread::sleep(std::time::Duration::from_millis(
         cfg().delay_surface_destroy_ms,
     ));
-    enigo.move_mouse(-10000, -10000, Coordinate::Rel)?;
+    pointer.move_mouse(-10000, -10000, Coordinate::Rel)?;
     std::thread::sleep(std::time::Duration::from_millis(
         cfg().delay_wayland_zero_ms,
     ));
-    enigo.move_mouse(x.round() as i32, y.round() as i32, Coordinate::Rel)?;
+    let (x, y) = placement().pointer_motion((x, y));
+    pointer.move_mouse(x.round() as i32, y.round() as i32, Coordinate::Rel)?;
     std::thread::sleep(std::time::Duration::from_millis(
         cfg().delay_wayland_move_ms,
     ));
     Ok(())
 }
 
-fn click_sequence(
-    enigo: &mut Enigo,
-    x: f32,
-    y: f32,
-    is_double: bool,
-    same_pos: bool,
-) -> InputResult<()> {
-    if !same_pos {
-        move_sequence(enigo, x, y)?;
+/// The button rowlink's "left click" actions press. Left-handed setups where the
+/// compositor doesn't swap emulated buttons can flip it with `swap_buttons`.
+fn primary_button() -> Button {
+    if cfg().swap_buttons {
+        Button::Right
+    } else {
+        Button::Left
+    }
+}
+
+/// The button for "right click" bindings, the opposite of [`primary_button`].
+fn secondary_button() -> Button {
+    if cfg().swap_buttons {
+        Button::Left
+    } else {
+        Button::Right
+    }
+}
+
+/// `clicks` primary clicks in a row, `delay_double_click_ms` apart so they count as one
+/// double or triple click.
+fn click_sequence(
+    pointer: &mut dyn Mouse,
+    x: f32,
+    y: f32,
+    clicks: u32,
+    same_pos: bool,
+) -> InputResult<()> {
+    if !same_pos {
+        move_sequence(pointer, x, y)?;
+    }
+
+    pointer.button(primary_button(), Direction::Click)?;
+    for _ in 1..clicks {
+        std::thread::sleep(std::time::Duration::from_millis(
+            cfg().delay_double_click_ms,
+        ));
+        pointer.button(primary_button(), Direction::Click)?;
+    }
+    Ok(())
+}
+
+/// With the button already held at `from`, glides to `to` in small relative steps so
+/// applications register a drag rather than a jump, then releases.
+fn drag_sequence(pointer: &mut dyn Mouse, from: (f32, f32), to: (f32, f32)) -> InputResult<()> {
+    let steps = cfg().drag_steps.max(1);
+    let mut moved = (0, 0);
+    for step in 1..=steps {
+        let t = step as f32 / steps as f32;
+        let dx = ((to.0 - from.0) * t).round() as i32;
+        let dy = ((to.1 - from.1) * t).round() as i32;
+        pointer.move_mouse(dx - moved.0, dy - moved.1, Coordinate::Rel)?;
+        moved = (dx, dy);
+        std::thread::sleep(std::time::Duration::from_millis(cfg().delay_drag_step_ms));
+    }
+    pointer.button(primary_button(), Direction::Release)
+}
+
+/// Axis units of one wheel click, as libinput reports a physical mouse's.
+const WHEEL_AXIS_STEP: i32 = 15;
+
+/// Scrolls `dx`/`dy` lines at the pointer, each sent as `step` units of scroll length.
+fn scroll_sequence(pointer: &mut dyn Mouse, (dx, dy): (i32, i32), step: i32) -> InputResult<()> {
+    let (final_dx, final_dy) = if cfg().scroll_natural {
+        (-dx * step, -dy * step)
+    } else {
+        (dx * step, dy * step)
+    };
+
+    if final_dx != 0 {
+        pointer.scroll(final_dx, enigo::Axis::Horizontal)?;
+    }
+    if final_dy != 0 {
+        pointer.scroll(final_dy, enigo::Axis::Vertical)?;
+    }
+    Ok(())
+}
+
+/// Text for a click-and-type without an explicit string: `type_text`, else the clipboard.
+fn resolve_type_text(text: Option<String>) -> Option<String> {
+    text.or_else(|| cfg().type_text.clone()).or_else(|| {
+        let output = std::process::Command::new("wl-paste")
+            .arg("--no-newline")
+            .output()
+            .ok()?;
+        if !output.status.success() {
+            eprintln!("wl-paste failed with {}", output.status);
+            return None;
+        }
+        String::from_utf8(output.stdout).ok()
+    })
+}
+
+// --- Subscription & Update ---
+fn signal_stream(kind: SignalKind, mode: Mode) -> impl iced::futures::Stream<Item = Message> {
+    stream::channel(10, async move |mut output| {
+        let mut sig = signal(kind).expect("Failed to setup signal listener");
+        loop {
+            sig.recv().await;
+            let _ = output.send(Message::SignalReceived(mode)).await;
+        }
+    })
+}
+
+fn signal_worker() -> impl iced::futures::Stream<Item = Message> {
+    signal_stream(SignalKind::user_defined1(), Mode::Grid)
+}
+
+fn quick_signal_worker() -> impl iced::futures::Stream<Item = Message> {
+    signal_stream(SignalKind::user_defined2(), Mode::Quick)
+}
+
+fn ipc_worker() -> impl iced::futures::Stream<Item = Message> {
+    stream::channel(10, async |output| {
+        let path = paths::socket();
+        let _ = std::fs::remove_file(&path);
+        let listener = match tokio::net::UnixListener::bind(&path) {
+            Ok(listener) => listener,
+            Err(e) => {
+                eprintln!("Control socket unavailable at {:?}: {:?}", path, e);
+                return;
+            }
+        };
+        loop {
+            let Ok((stream, _)) = listener.accept().await else {
+                continue;
+            };
+            // A client that never finishes its line must not hold up the others.
+            tokio::spawn(ipc_connection(stream, output.clone()));
+        }
+    })
+}
+
+/// Longest control-socket line read; anything longer is refused.
+const MAX_IPC_LINE: u64 = 64 * 1024;
+
+/// Serves one control-socket client: a single command line, or a subscription.
+async fn ipc_connection(
+    stream: tokio::net::UnixStream,
+    mut output: iced::futures::channel::mpsc::Sender<Message>,
+) {
+    let (reader, mut writer) = stream.into_split();
+    let mut line = String::new();
+    let mut reader = BufReader::new(reader.take(MAX_IPC_LINE));
+    if reader.read_line(&mut line).await.is_err() {
+        return;
+    }
+    if line.len() as u64 >= MAX_IPC_LINE && !line.ends_with('\n') {
+        let reply = format!("error: lines are limited to {} bytes\n", MAX_IPC_LINE);
+        let _ = writer.write_all(reply.as_bytes()).await;
+        return;
     }
 
-    enigo.button(Button::Left, Direction::Click)?;
-    if is_double {
-        std::thread::sleep(std::time::Duration::from_millis(
-            cfg().delay_double_click_ms,
-        ));
-        enigo.button(Button::Left, Direction::Click)?;
+    if let Some((reply, mut events)) = ipc::subscribe(line.trim()) {
+        let _ = writer.write_all(reply.as_bytes()).await;
+        while let Some(event) = events.next().await {
+            if writer.write_all(event.as_bytes()).await.is_err() {
+                break;
+            }
+        }
+        return;
     }
-    Ok(())
+    let reply = handle_line(line.trim(), &mut output).await;
+    let _ = writer.write_all(reply.as_bytes()).await;
 }
 
-fn scroll_sequence(
-    enigo: &mut Enigo,
-    x: f32,
-    y: f32,
-    dx: i32,
-    dy: i32,
-    same_pos: bool,
-) -> InputResult<()> {
-    if !same_pos {
-        move_sequence(enigo, x, y)?;
+#[cfg(feature = "websocket")]
+fn websocket_worker() -> impl iced::futures::Stream<Item = Message> {
+    stream::channel(10, async |output| {
+        if let Some(port) = base_config().websocket_port {
+            websocket::serve(port, output).await;
+        }
+    })
+}
+
+/// Runs one control-socket line, plain text or JSON-RPC, and returns its reply.
+async fn handle_line(
+    line: &str,
+    output: &mut iced::futures::channel::mpsc::Sender<Message>,
+) -> String {
+    if line.starts_with('{') {
+        match ipc::parse_request(line) {
+            Ok(request) => {
+                let result = match request.method.as_str() {
+                    "get_capabilities" => Ok(ipc::capabilities()),
+                    method if ipc::COMMANDS.contains(&method) => {
+                        ipc::reply_result(&run_command(method, &request.argument, output).await)
+                    }
+                    method => Err(ipc::method_not_found(method)),
+                };
+                ipc::respond(request.id, result)
+            }
+            Err(error) => error,
+        }
+    } else {
+        let (command, argument) = line.split_once(' ').unwrap_or((line, ""));
+        run_command(command, argument, output).await
+    }
+}
+
+/// Runs blocking work, like the compositor queries behind window targets, where it doesn't
+/// hold up the other connections.
+async fn blocking<T: Send + 'static>(work: impl FnOnce() -> T + Send + 'static) -> T {
+    tokio::task::spawn_blocking(work)
+        .await
+        .unwrap_or_else(|e| std::panic::resume_unwind(e.into_panic()))
+}
+
+/// Runs one control-socket command and returns its text reply.
+async fn run_command(
+    command: &str,
+    argument: &str,
+    output: &mut iced::futures::channel::mpsc::Sender<Message>,
+) -> String {
+    match command {
+        "list" => {
+            let entries = blocking(picker::entries).await;
+            entries
+                .into_iter()
+                .map(|entry| entry.label + "\n")
+                .collect()
+        }
+        "pick" => {
+            let label = argument.to_string();
+            let target = blocking(move || {
+                // Window targets are output-local, and the focused output may have changed
+                // since the overlay last opened.
+                update_placement(cfg().output.as_deref(), false, false);
+                picker::find(&label)
+            })
+            .await;
+            match target {
+                Some(target) => {
+                    let _ = output.send(Message::PickTarget(target)).await;
+                    "ok\n".to_string()
+                }
+                None => format!("error: no target named {:?}\n", argument),
+            }
+        }
+        "repeat" => {
+            let _ = output.send(Message::RepeatLastAction).await;
+            "ok\n".to_string()
+        }
+        "profile" if argument.is_empty() => profile_names()
+            .into_iter()
+            .map(|name| {
+                let marker = if name == cfg().profile_name { "*" } else { " " };
+                format!("{} {}\n", marker, name)
+            })
+            .collect(),
+        "profile" => match profile_index(argument) {
+            Some(_) => {
+                let _ = output
+                    .send(Message::SwitchProfile(argument.to_string()))
+                    .await;
+                "ok\n".to_string()
+            }
+            None => format!("error: no profile named {:?}\n", argument),
+        },
+        "theme" if argument.is_empty() => {
+            let active = ACTIVE_THEME
+                .read()
+                .unwrap_or_else(|e| e.into_inner())
+                .clone();
+            let active = active.as_deref().unwrap_or(DEFAULT_THEME);
+            theme_names()
+                .into_iter()
+                .map(|name| {
+                    let marker = if name == active { "*" } else { " " };
+                    format!("{} {}\n", marker, name)
+                })
+                .collect()
+        }
+        "theme" if theme_names().contains(&argument) => {
+            let _ = output
+                .send(Message::SwitchTheme(argument.to_string()))
+                .await;
+            "ok\n".to_string()
+        }
+        "theme" => format!("error: no theme named {:?}\n", argument),
+        "type" => {
+            let _ = output
+                .send(Message::TypeRequested(argument.to_string()))
+                .await;
+            "ok\n".to_string()
+        }
+        "private" => match argument {
+            "" if PRIVATE.load(Ordering::Relaxed) => "on\n".to_string(),
+            "" => "off\n".to_string(),
+            "on" | "off" => {
+                PRIVATE.store(argument == "on", Ordering::Relaxed);
+                "ok\n".to_string()
+            }
+            _ => format!("error: expected on or off, got {:?}\n", argument),
+        },
+        "mark" => {
+            let (action, rest) = argument.split_once(' ').unwrap_or((argument, ""));
+            let rest = rest.trim();
+            match action {
+                "" | "list" => marks::all()
+                    .into_iter()
+                    .map(|mark| format!("{} {} {}\n", mark.name, mark.x, mark.y))
+                    .collect(),
+                "add" if !rest.is_empty() => {
+                    let words: Vec<&str> = rest.split_whitespace().collect();
+                    let point = match words.as_slice() {
+                        [name @ .., x, y] if !name.is_empty() => {
+                            x.parse().ok().zip(y.parse().ok()).map(|p| (name, p))
+                        }
+                        _ => None,
+                    };
+                    match point {
+                        Some((name, (x, y))) => marks::add(&name.join(" "), x, y),
+                        None => {
+                            let _ = output.send(Message::MarkPointer(rest.to_string())).await;
+                        }
+                    }
+                    "ok\n".to_string()
+                }
+                "reload" => {
+                    marks::load();
+                    "ok\n".to_string()
+                }
+                "remove" if marks::remove(rest) => "ok\n".to_string(),
+                "remove" => format!("error: no mark named {:?}\n", rest),
+                _ => format!("error: unknown mark command {:?}\n", argument),
+            }
+        }
+        "purge" => {
+            let _ = output.send(Message::PurgeHistory).await;
+            "ok\n".to_string()
+        }
+        "quick" => {
+            let _ = output.send(Message::QuickRequested).await;
+            "ok\n".to_string()
+        }
+        _ => format!("error: unknown command {:?}\n", command),
     }
+}
 
-    let (final_dx, final_dy) = if cfg().scroll_natural {
-        (-dx, -dy)
+fn subscription(state: &Rowlink) -> Subscription<Message> {
+    let mut subscriptions = vec![
+        Subscription::run(signal_worker),
+        Subscription::run(quick_signal_worker),
+        Subscription::run(ipc_worker),
+    ];
+    #[cfg(feature = "websocket")]
+    if base_config().websocket_port.is_some() {
+        subscriptions.push(Subscription::run(websocket_worker));
+    }
+    if state.mode_osd.is_some() {
+        subscriptions.push(iced::time::every(Duration::from_millis(16)).map(|_| Message::OsdTick));
+    }
+    if state.dwell.is_some() {
+        subscriptions
+            .push(iced::time::every(Duration::from_millis(16)).map(|_| Message::DwellTick));
+    }
+    if state.mode == Mode::Move && !state.move_keys.is_empty() {
+        subscriptions.push(iced::time::every(Duration::from_millis(16)).map(|_| Message::MoveTick));
+    }
+    if state.scroll_hold.is_some() || state.scroll_burst.is_some() {
+        let interval = Duration::from_millis(cfg().scroll_repeat_interval_ms);
+        subscriptions.push(iced::time::every(interval).map(|_| Message::ScrollTick));
+    }
+    // While hidden the daemon only needs the activation signal; dropping the event
+    // listener keeps it from waking up on every input/window event.
+    if state.visible {
+        subscriptions.push(iced::event::listen_with(overlay_event));
+    }
+    Subscription::batch(subscriptions)
+}
+
+/// Passes on only the events the overlay reacts to. Every message runs `update` and
+/// redraws the surface, so focus, window and stray pointer events would otherwise
+/// repaint an unchanged grid.
+fn overlay_event(
+    event: Event,
+    status: iced::event::Status,
+    _window: iced::window::Id,
+) -> Option<Message> {
+    let relevant = match &event {
+        Event::Keyboard(_) => true,
+        Event::Touch(iced::touch::Event::FingerPressed { .. }) => cfg().touch_select,
+        Event::Mouse(
+            iced::mouse::Event::CursorMoved { .. } | iced::mouse::Event::ButtonPressed(_),
+        ) => cfg().pointer_select,
+        _ => false,
+    };
+    (relevant && status == iced::event::Status::Ignored).then_some(Message::IcedEvent(event))
+}
+
+/// Tracks held keys and tells whether `event` is a key repeat that must not reach the
+/// handlers. Compositor autorepeat would otherwise flood the label buffer and jump to an
+/// unintended cell; scrolling, move mode and editing keep their repeats.
+fn is_ignored_repeat(state: &mut Rowlink, event: &keyboard::Event) -> bool {
+    let unidentified =
+        keyboard::key::Physical::Unidentified(keyboard::key::NativeCode::Unidentified);
+    match event {
+        keyboard::Event::KeyPressed {
+            key,
+            physical_key,
+            modifiers,
+            repeat,
+            ..
+        } => {
+            let held = *physical_key != unidentified && state.held_keys.contains(physical_key);
+            if !held && *physical_key != unidentified {
+                state.held_keys.push(*physical_key);
+            }
+            let keeps_repeat = state.mode == Mode::Move
+                || (state.zoomed_cell.is_some() && modifiers.control())
+                || matches!(key, keyboard::Key::Named(keyboard::key::Named::Backspace));
+            (*repeat || held) && !keeps_repeat
+        }
+        keyboard::Event::KeyReleased { physical_key, .. } => {
+            state.held_keys.retain(|held| held != physical_key);
+            false
+        }
+        _ => false,
+    }
+}
+
+/// Runs configured bindings on the main grid. Returns `None` when the key is ordinary
+/// input; keys held back as a possible sequence prefix are replayed once it breaks.
+fn handle_binding_key(state: &mut Rowlink, event: &keyboard::Event) -> Option<iced::Task<Message>> {
+    let keyboard::Event::KeyPressed { key, modifiers, .. } = event else {
+        return None;
+    };
+    if cfg().compiled_bindings.is_empty()
+        || state.mode != Mode::Grid
+        || state.dwell.is_some()
+        || state.space_hold.is_some()
+        || matches!(key, keyboard::Key::Named(named) if is_modifier(*named))
+    {
+        return None;
+    }
+    let mut keys: Vec<(keyboard::Key, keyboard::Modifiers)> = state
+        .binding_keys
+        .iter()
+        .filter_map(|pending| match pending {
+            keyboard::Event::KeyPressed { key, modifiers, .. } => Some((key.clone(), *modifiers)),
+            _ => None,
+        })
+        .collect();
+    keys.push((key.clone(), *modifiers));
+
+    match bindings::lookup(&cfg().compiled_bindings, &keys) {
+        bindings::Lookup::Complete(action) => {
+            state.binding_keys.clear();
+            Some(run_binding(state, action))
+        }
+        bindings::Lookup::Prefix => {
+            state.binding_keys.push(event.clone());
+            Some(iced::Task::none())
+        }
+        bindings::Lookup::NoMatch if state.binding_keys.is_empty() => None,
+        bindings::Lookup::NoMatch => {
+            let mut replay = std::mem::take(&mut state.binding_keys);
+            replay.push(event.clone());
+            state.replaying_keys = true;
+            let tasks: Vec<_> = replay
+                .into_iter()
+                .map(|event| update(state, Message::IcedEvent(Event::Keyboard(event))))
+                .collect();
+            state.replaying_keys = false;
+            Some(iced::Task::batch(tasks))
+        }
+    }
+}
+
+fn is_modifier(key: keyboard::key::Named) -> bool {
+    use keyboard::key::Named;
+    matches!(
+        key,
+        Named::Control | Named::Shift | Named::Alt | Named::Super | Named::Meta
+    )
+}
+
+fn run_binding(state: &mut Rowlink, action: bindings::Action) -> iced::Task<Message> {
+    use bindings::Action;
+    let (x, y) = center_target(state.zoomed_cell);
+    let (right, bottom) = (cfg().screen_width - 1.0, cfg().screen_height - 1.0);
+    let step = cfg().scroll_lines;
+    let message = match action {
+        Action::LeftClick => Message::ExecuteClick(x, y, false),
+        Action::DoubleClick => Message::ExecuteClick(x, y, true),
+        Action::TripleClick => Message::ExecuteTripleClick(x, y),
+        Action::RightClick => Message::ExecuteButtonClick(x, y, secondary_button()),
+        Action::MiddleClick => Message::ExecuteButtonClick(x, y, Button::Middle),
+        Action::BackClick => Message::ExecuteButtonClick(x, y, Button::Back),
+        Action::ForwardClick => Message::ExecuteButtonClick(x, y, Button::Forward),
+        Action::Paste => Message::ExecutePaste(x, y),
+        Action::GotoTopLeft => Message::ExecuteMove(0.0, 0.0),
+        Action::GotoTopRight => Message::ExecuteMove(right, 0.0),
+        Action::GotoBottomLeft => Message::ExecuteMove(0.0, bottom),
+        Action::GotoBottomRight => Message::ExecuteMove(right, bottom),
+        Action::GotoCenter => {
+            Message::ExecuteMove(cfg().screen_width / HALF, cfg().screen_height / HALF)
+        }
+        // Scrolling keeps the overlay open, like Ctrl+j/k in the sub-grid.
+        Action::ScrollUp => {
+            return update(state, Message::ExecuteScroll(state.zoomed_cell, 0, step));
+        }
+        Action::ScrollDown => {
+            return update(state, Message::ExecuteScroll(state.zoomed_cell, 0, -step));
+        }
+        Action::ScrollLeft => {
+            return update(state, Message::ExecuteScroll(state.zoomed_cell, -step, 0));
+        }
+        Action::ScrollRight => {
+            return update(state, Message::ExecuteScroll(state.zoomed_cell, step, 0));
+        }
+        Action::RepeatLast => return update(state, Message::RepeatLastAction),
+        Action::Close => return state.hide_overlay(None),
+    };
+    state.hide_overlay(Some(message))
+}
+
+/// Mode entered by `key` from the idle main grid, if it is one of the mode keys.
+fn mode_for_key(key: &keyboard::Key) -> Option<Mode> {
+    if key_matches(key, &cfg().coarse_key) {
+        Some(Mode::Coarse)
+    } else if key_matches(key, &cfg().hotspot_key) && !cfg().hint_providers.is_empty() {
+        Some(Mode::Hotspot)
+    } else if key_matches(key, &cfg().coordinate_key) {
+        Some(Mode::Coordinate)
+    } else if key_matches(key, &cfg().move_key) {
+        Some(Mode::Move)
+    } else if key_matches(key, &cfg().window_move_key) {
+        Some(Mode::WindowMove)
+    } else if key_matches(key, &cfg().window_resize_key) {
+        Some(Mode::WindowResize)
+    } else if key_matches(key, &cfg().numpad_key) {
+        Some(Mode::Numpad)
     } else {
-        (dx, dy)
+        None
+    }
+}
+
+/// Enters a window mode with hints on the visible windows of the overlay's output; stays
+/// on the grid when the compositor reports none.
+fn start_window_pick(state: &mut Rowlink, mode: Mode) -> iced::Task<Message> {
+    let windows: Vec<_> = compositor::toplevels()
+        .into_iter()
+        .filter(|t| t.is_on_output(cfg().screen_width, cfg().screen_height))
+        .collect();
+    if windows.is_empty() {
+        eprintln!("No windows to pick.");
+        return iced::Task::none();
+    }
+    let alphabet: Vec<char> = HINT_ALPHABET.chars().collect();
+    let hints = labels::hint_labels(windows.len(), &alphabet)
+        .into_iter()
+        .zip(windows)
+        .collect();
+    state.enter_mode(mode);
+    state.window_pick = Some(WindowPick::Choosing(hints));
+    state.grid_cache.clear();
+    iced::Task::none()
+}
+
+/// Keys for the window modes: a window hint, then a main grid label.
+fn handle_window_key(state: &mut Rowlink, key: keyboard::Key) -> iced::Task<Message> {
+    let keyboard::Key::Character(c) = key else {
+        if key == keyboard::Key::Named(keyboard::key::Named::Escape) {
+            state.enter_mode(Mode::Grid);
+            state.window_pick = None;
+            state.input_buffer.clear();
+            state.grid_cache.clear();
+        }
+        return iced::Task::none();
     };
+    state.input_buffer.extend(c.chars().map(label_char));
+    state.grid_cache.clear();
+    match &mut state.window_pick {
+        Some(WindowPick::Choosing(hints)) => {
+            if let Some(index) = hints
+                .iter()
+                .position(|(label, _)| *label == state.input_buffer)
+            {
+                let (_, window) = hints.swap_remove(index);
+                state.window_pick = Some(WindowPick::Placing(window));
+                state.input_buffer.clear();
+            } else if !hints
+                .iter()
+                .any(|(label, _)| label.starts_with(state.input_buffer.as_str()))
+            {
+                state.input_buffer.clear();
+            }
+            iced::Task::none()
+        }
+        Some(WindowPick::Placing(window)) => {
+            match cfg()
+                .label_table
+                .lookup(&state.input_buffer, cfg().label_unique_prefix)
+            {
+                labels::Lookup::Cell(row, col) => {
+                    let (width, height) = (cfg().screen_width, cfg().screen_height);
+                    let (x, y) = cfg().get_main_cell_origin(width, height, row, col);
+                    if state.mode == Mode::WindowMove {
+                        let action = Message::MoveWindow(window.window.clone(), x, y);
+                        return state.hide_overlay(Some(action));
+                    }
+                    let (cell_width, cell_height) = cfg().get_main_cell_size(width, height);
+                    let (right, bottom) = (x + cell_width, y + cell_height);
+                    if right <= window.x || bottom <= window.y {
+                        eprintln!("Pick a cell below and right of the window's top-left corner.");
+                        state.input_buffer.clear();
+                        return iced::Task::none();
+                    }
+                    let action = Message::ResizeWindow(
+                        window.window.clone(),
+                        (window.x, window.y),
+                        (right - window.x, bottom - window.y),
+                    );
+                    state.hide_overlay(Some(action))
+                }
+                labels::Lookup::Prefix => iced::Task::none(),
+                labels::Lookup::NoMatch => {
+                    state.input_buffer.clear();
+                    iced::Task::none()
+                }
+            }
+        }
+        None => iced::Task::none(),
+    }
+}
 
-    if final_dx != 0 {
-        enigo.scroll(final_dx, enigo::Axis::Horizontal)?;
+/// Parses typed `x,y` coordinates within `area`, the output or a window. Each axis is
+/// either pixels from the area's corner (`812`) or a percentage of it (`50%`), so `50%,90%`
+/// works at any resolution and window size.
+fn parse_coordinates(input: &str, area: geometry::Rect) -> Option<(f32, f32)> {
+    let (x, y) = input.split_once(',')?;
+    Some((
+        area.x + parse_axis(x, area.width)?,
+        area.y + parse_axis(y, area.height)?,
+    ))
+}
+
+fn parse_axis(input: &str, extent: f32) -> Option<f32> {
+    let input = input.trim();
+    match input.strip_suffix('%') {
+        Some(percent) => Some(percent.trim().parse::<f32>().ok()? / 100.0 * extent),
+        None => input.parse().ok(),
     }
-    if final_dy != 0 {
-        enigo.scroll(final_dy, enigo::Axis::Vertical)?;
+}
+
+/// Coordinate entry: Enter clicks, Shift+Enter double clicks, Ctrl+Enter only moves; Tab
+/// switches between the output and the focused window.
+fn handle_coordinate_key(
+    state: &mut Rowlink,
+    key: keyboard::Key,
+    modifiers: keyboard::Modifiers,
+) -> iced::Task<Message> {
+    match key {
+        keyboard::Key::Named(keyboard::key::Named::Escape) => {
+            state.enter_mode(Mode::Grid);
+            state.input_buffer.clear();
+            state.coordinate_window = None;
+        }
+        keyboard::Key::Named(keyboard::key::Named::Backspace) => {
+            state.input_buffer.pop();
+        }
+        keyboard::Key::Named(keyboard::key::Named::Tab) => {
+            if state.coordinate_window.take().is_none() {
+                return in_background(
+                    || {
+                        compositor::focused_toplevel().map(|t| geometry::Rect {
+                            x: t.x,
+                            y: t.y,
+                            width: t.width,
+                            height: t.height,
+                        })
+                    },
+                    Message::CoordinateWindowFound,
+                );
+            }
+        }
+        keyboard::Key::Named(keyboard::key::Named::Enter) => {
+            let output = geometry::Rect {
+                x: 0.0,
+                y: 0.0,
+                width: cfg().screen_width,
+                height: cfg().screen_height,
+            };
+            let area = state.coordinate_window.unwrap_or(output);
+            let Some((x, y)) = parse_coordinates(&state.input_buffer, area) else {
+                return iced::Task::none();
+            };
+            if modifiers.control() {
+                return state.hide_overlay(Some(Message::ExecuteMove(x, y)));
+            }
+            let is_double = modifiers.shift();
+            return state.confirm_selection((x, y), Message::ExecuteClick(x, y, is_double));
+        }
+        keyboard::Key::Character(c) => {
+            let accepted = c
+                .chars()
+                .filter(|ch| ch.is_ascii_digit() || matches!(ch, ',' | '.' | '%'));
+            state.input_buffer.extend(accepted);
+        }
+        _ => return iced::Task::none(),
     }
-    Ok(())
+    state.grid_cache.clear();
+    iced::Task::none()
 }
 
-// --- Subscription & Update ---
-fn signal_worker() -> impl iced::futures::Stream<Item = Message> {
-    stream::channel(10, async |mut output| {
-        let mut sig = signal(SignalKind::user_defined1()).expect("Failed to setup signal listener");
-        loop {
-            sig.recv().await;
-            let _ = output.send(Message::SignalReceived).await;
+/// Move mode: WASD held glide the pointer, Space clicks in place, Enter clicks and
+/// closes, Shift+Enter double clicks and closes.
+fn handle_move_key(
+    state: &mut Rowlink,
+    key: keyboard::Key,
+    modifiers: keyboard::Modifiers,
+) -> iced::Task<Message> {
+    let (x, y) = state.last_mouse_pos.unwrap_or_default();
+    match key {
+        keyboard::Key::Named(keyboard::key::Named::Escape) => {
+            state.enter_mode(Mode::Grid);
+            state.move_keys.clear();
+            state.grid_cache.clear();
+            iced::Task::none()
         }
-    })
+        keyboard::Key::Named(keyboard::key::Named::Space) => {
+            state.learn_correction((x, y));
+            update(state, Message::ExecuteClick(x, y, modifiers.shift()))
+        }
+        keyboard::Key::Named(keyboard::key::Named::Enter) => {
+            state.learn_correction((x, y));
+            state.confirm_selection((x, y), Message::ExecuteClick(x, y, modifiers.shift()))
+        }
+        keyboard::Key::Character(c) => {
+            if let Some(ch) = c.chars().next().map(|ch| ch.to_ascii_lowercase())
+                && move_direction(ch).is_some()
+                && !state.move_keys.contains(&ch)
+            {
+                if state.move_keys.is_empty() {
+                    state.move_started = Instant::now();
+                    state.move_last_tick = Some(state.move_started);
+                    state.move_history.clear();
+                }
+                state.move_keys.push(ch);
+            }
+            iced::Task::none()
+        }
+        _ => iced::Task::none(),
+    }
 }
 
-fn subscription(_state: &Rowlink) -> Subscription<Message> {
-    Subscription::batch(vec![
-        Subscription::run(signal_worker),
-        iced::event::listen().map(Message::IcedEvent),
-    ])
+/// Numpad mode: see `NumpadStyle`. Shift+5 double clicks.
+fn handle_numpad_key(
+    state: &mut Rowlink,
+    key: keyboard::Key,
+    modifiers: keyboard::Modifiers,
+) -> iced::Task<Message> {
+    let keyboard::Key::Character(c) = key else {
+        if key == keyboard::Key::Named(keyboard::key::Named::Escape) {
+            state.enter_mode(Mode::Grid);
+            state.grid_cache.clear();
+        }
+        return iced::Task::none();
+    };
+    let Some(digit) = c.chars().next().and_then(|ch| ch.to_digit(10)) else {
+        return iced::Task::none();
+    };
+    let (x, y) = match cfg().numpad_style {
+        NumpadStyle::Regions => cfg().click_point(state.numpad_area),
+        NumpadStyle::Directions => state.last_mouse_pos.unwrap_or_default(),
+    };
+    if digit == 5 {
+        return state.confirm_selection((x, y), Message::ExecuteClick(x, y, modifiers.shift()));
+    }
+    // 0 stands in for the center ninth, since 5 clicks.
+    let (row, col) = numpad_cell(if digit == 0 { 5 } else { digit });
+    let target = match cfg().numpad_style {
+        NumpadStyle::Regions => {
+            let grid = geometry::Grid::over(state.numpad_area, 3, 3, placement().scale);
+            state.numpad_area = grid.cell(row, col);
+            let (x, y) = cfg().click_point(state.numpad_area);
+            if cfg().auto_confirm && is_precise(&state.numpad_area) {
+                return state.confirm_selection((x, y), Message::ExecuteClick(x, y, false));
+            }
+            (x, y)
+        }
+        NumpadStyle::Directions if digit != 0 => {
+            let step = cfg().numpad_step;
+            let x = (x + (col - 1) as f32 * step).clamp(0.0, cfg().screen_width - 1.0);
+            let y = (y + (row - 1) as f32 * step).clamp(0.0, cfg().screen_height - 1.0);
+            (x, y)
+        }
+        NumpadStyle::Directions => return iced::Task::none(),
+    };
+    state.grid_cache.clear();
+    update(state, Message::ExecuteMove(target.0, target.1))
+}
+
+/// Row and column of a numpad digit in its 3x3 layout, 7 at the top left.
+fn numpad_cell(digit: u32) -> (i32, i32) {
+    let index = digit.clamp(1, 9) as i32 - 1;
+    (2 - index / 3, index % 3)
+}
+
+/// Whether an area is below `precision_px` on both sides, so one point of it is as good
+/// as another.
+fn is_precise(area: &geometry::Rect) -> bool {
+    area.width < cfg().precision_px && area.height < cfg().precision_px
+}
+
+fn screen_rect() -> geometry::Rect {
+    geometry::Rect {
+        x: 0.0,
+        y: 0.0,
+        width: cfg().screen_width,
+        height: cfg().screen_height,
+    }
+}
+
+/// Moves the arrow-key cursor one cell, stopping at the edges. The first press only shows
+/// it, on the middle cell.
+fn move_cursor(state: &mut Rowlink, arrow: keyboard::key::Named) {
+    let (rows, cols) = match state.zoomed_cell {
+        Some(_) => (cfg().sub_rows, cfg().sub_cols),
+        None => (cfg().grid_rows(), cfg().grid_cols()),
+    };
+    let (row, col) = match state.cursor {
+        Some(cursor) if cursor.zoomed == state.zoomed_cell => {
+            let (row, col) = cursor.cell;
+            let (dr, dc) = match arrow {
+                keyboard::key::Named::ArrowUp => (-1, 0),
+                keyboard::key::Named::ArrowDown => (1, 0),
+                keyboard::key::Named::ArrowLeft => (0, -1),
+                _ => (0, 1),
+            };
+            ((row + dr).clamp(0, rows - 1), (col + dc).clamp(0, cols - 1))
+        }
+        _ => ((rows - 1) / 2, (cols - 1) / 2),
+    };
+    state.cursor = Some(CellCursor {
+        zoomed: state.zoomed_cell,
+        cell: (row, col),
+    });
+    state.input_buffer.clear();
+    state.grid_cache.clear();
+}
+
+fn move_direction(c: char) -> Option<(f32, f32)> {
+    match c {
+        'w' => Some((0.0, -1.0)),
+        'a' => Some((-1.0, 0.0)),
+        's' => Some((0.0, 1.0)),
+        'd' => Some((1.0, 0.0)),
+        _ => None,
+    }
+}
+
+/// A tap on the overlay: zooms into the tapped main cell, or clicks the tapped sub-cell
+/// of the zoomed one.
+fn handle_tap(state: &mut Rowlink, position: Point) -> iced::Task<Message> {
+    let (width, height) = (cfg().screen_width, cfg().screen_height);
+    if let Some(grid) = state.zoom_grid(width, height) {
+        return match grid.cell_at(position.x, position.y) {
+            Some(sub_cell) => state.pick_sub_cell(sub_cell, false),
+            None => iced::Task::none(),
+        };
+    }
+    match cfg().get_main_cell_at(width, height, position.x, position.y) {
+        Some(cell) => state.zoom_into(cell),
+        None => iced::Task::none(),
+    }
+}
+
+/// Keys for the hint-driven modes (coarse, quick, hotspot).
+fn handle_region_key(
+    state: &mut Rowlink,
+    key: keyboard::Key,
+    modifiers: keyboard::Modifiers,
+) -> iced::Task<Message> {
+    match key {
+        keyboard::Key::Named(keyboard::key::Named::Escape) => {
+            if state.mode == Mode::Quick {
+                return state.hide_overlay(None);
+            }
+            state.enter_mode(Mode::Grid);
+            state.input_buffer.clear();
+            state.grid_cache.clear();
+            iced::Task::none()
+        }
+        // Done refining: the pointer is already there.
+        keyboard::Key::Named(keyboard::key::Named::Enter) if state.mode == Mode::Coarse => {
+            state.hide_overlay(None)
+        }
+        keyboard::Key::Character(c) if state.mode == Mode::Coarse => {
+            let Some((row, col)) = c
+                .chars()
+                .next()
+                .and_then(|ch| map_key_to_label(&cfg().coarse_labels, ch))
+            else {
+                return iced::Task::none();
+            };
+            let region = cfg()
+                .coarse_grid(state.coarse_area.unwrap_or_else(screen_rect))
+                .cell(row, col);
+            let (x, y) = region.center();
+            if state.coarse_area.is_some() {
+                return state.hide_overlay(Some(Message::ExecuteMove(x, y)));
+            }
+            // The pointer warps right away; a second key refines within the region.
+            state.coarse_area = Some(region);
+            state.grid_cache.clear();
+            update(state, Message::ExecuteMove(x, y))
+        }
+        keyboard::Key::Character(c) if state.mode == Mode::Hotspot => {
+            state.input_buffer.extend(c.chars().map(label_char));
+            state.grid_cache.clear();
+            let hints = &state.hints;
+            if let Some((_, hint)) = hints.iter().find(|(label, _)| *label == state.input_buffer) {
+                let (x, y) = hint.center();
+                let is_double = modifiers.shift();
+                return state.confirm_selection((x, y), Message::ExecuteClick(x, y, is_double));
+            }
+            if !hints
+                .iter()
+                .any(|(label, _)| label.starts_with(state.input_buffer.as_str()))
+            {
+                // No hint starts with this; start over.
+                state.input_buffer.clear();
+            }
+            iced::Task::none()
+        }
+        keyboard::Key::Character(c) => {
+            let target = c.chars().next().and_then(|ch| {
+                state
+                    .mode
+                    .region(cfg().screen_width, cfg().screen_height, ch)
+            });
+            match target {
+                Some((x, y)) => state.hide_overlay(Some(Message::ExecuteMove(x, y))),
+                None => iced::Task::none(),
+            }
+        }
+        _ => iced::Task::none(),
+    }
 }
 
 fn update(state: &mut Rowlink, message: Message) -> iced::Task<Message> {
+    let task = handle_message(state, message);
+    if state.visible && cfg().mode_in_namespace && state.mode != state.surface_mode {
+        return iced::Task::batch(vec![task, state.open_overlay()]);
+    }
+    task
+}
+
+fn handle_message(state: &mut Rowlink, message: Message) -> iced::Task<Message> {
+    if let Message::IcedEvent(Event::Keyboard(event)) = &message
+        && !state.replaying_keys
+    {
+        if !state.key_seen {
+            state.key_seen = true;
+            println!(
+                "Keyboard grab acquired with {:?} interactivity.",
+                state.keyboard_interactivity
+            );
+        }
+        if is_ignored_repeat(state, event) {
+            return iced::Task::none();
+        }
+        if let Some(task) = handle_binding_key(state, event) {
+            return task;
+        }
+    }
+    let message = match message {
+        Message::IcedEvent(Event::Keyboard(event)) => {
+            Message::IcedEvent(Event::Keyboard(remap_confirm_cancel(state.mode, event)))
+        }
+        message => message,
+    };
     match message {
         Message::LayerChange { id, .. } | Message::NewLayerShell { id, .. } => {
             state.current_id = Some(id);
@@ -410,136 +4019,449 @@ fn update(state: &mut Rowlink, message: Message) -> iced::Task<Message> {
             id: state.current_id.unwrap_or(IcedId::unique()),
             callback: ActionCallback::new(|_region| {}),
         }),
-        Message::SignalReceived => {
+        Message::SignalReceived(mode) => {
+            state.activated_at.set(Some(Instant::now()));
+            in_background(query_focus, move |focus| Message::Activate(mode, focus))
+        }
+        Message::Activate(mode, focus) => {
+            if cfg().pause_on_fullscreen && focus.window.as_ref().is_some_and(|w| w.fullscreen) {
+                println!("Fullscreen client focused, ignoring activation.");
+                state.activated_at.set(None);
+                // A pick that opened the grid for its second point gets no second point.
+                state.pending_text = None;
+                if state.selection.take().is_some() {
+                    state.perform_pointer_action(|pointer| {
+                        pointer.button(primary_button(), Direction::Release)
+                    });
+                }
+                return iced::Task::none();
+            }
+            if !base_config().output_profiles.is_empty()
+                && let Some(name) = focus
+                    .output
+                    .as_ref()
+                    .and_then(|output| base_config().output_profiles.get(output))
+                && *name != cfg().profile_name
+            {
+                if switch_profile(name) {
+                    state.clear_static_layers();
+                } else {
+                    eprintln!("Output profile {} is not defined.", name);
+                }
+            }
+            if focus.placement_changed {
+                state.clear_static_layers();
+            }
             state.visible = true;
+            state.enter_mode(mode);
+            ipc::emit(json!({ "event": "shown", "mode": mode.name() }));
+            run_hook(cfg().on_show.as_deref(), mode);
             state.input_buffer.clear();
+            state.coordinate_window = None;
             state.last_mouse_pos = None;
-            let (new_id, spawn_task) = Message::layershell_open(get_layer_settings(true));
-            let old_id = state.current_id.replace(new_id).unwrap_or(IcedId::unique());
-            iced::Task::batch(vec![
-                iced::Task::done(Message::RemoveWindow(old_id)),
-                spawn_task,
-            ])
+            if cfg().idle_inhibit {
+                inhibit::acquire();
+            }
+            state.key_seen = false;
+            state.activation += 1;
+            let window = focus.window;
+            state.previous_focus = window
+                .as_ref()
+                .filter(|_| cfg().restore_focus)
+                .map(|w| w.window.clone());
+            state.focused_app = window.and_then(|w| w.app);
+            if let Some((layout, labels)) = focus.keyboard_layout {
+                state.update_keyboard_layout(layout, labels);
+            }
+            state.load_adaptive();
+            state.grid_cache.clear();
+
+            let open_task = state.open_overlay();
+            if cfg().keyboard_mode == KeyboardMode::Auto
+                && state.keyboard_interactivity == KeyboardInteractivity::OnDemand
+            {
+                let activation = state.activation;
+                let timeout = std::time::Duration::from_millis(cfg().keyboard_grab_timeout_ms);
+                return iced::Task::batch(vec![
+                    open_task,
+                    iced::Task::perform(tokio::time::sleep(timeout), move |_| {
+                        Message::KeyboardGrabCheck(activation)
+                    }),
+                ]);
+            }
+            open_task
+        }
+        Message::KeyboardGrabCheck(activation) => {
+            if !state.visible || state.key_seen || activation != state.activation {
+                return iced::Task::none();
+            }
+            // Some compositors never route keys to an OnDemand layer surface.
+            println!(
+                "No key events with OnDemand keyboard interactivity, retrying with Exclusive."
+            );
+            state.keyboard_interactivity = KeyboardInteractivity::Exclusive;
+            state.open_overlay()
+        }
+        Message::HoldDragCheck => {
+            let Some(SpaceHold::Pending {
+                target, started, ..
+            }) = state.space_hold
+            else {
+                return iced::Task::none();
+            };
+            // A later press has its own check pending.
+            if started.elapsed() < Duration::from_millis(cfg().hold_drag_ms) {
+                return iced::Task::none();
+            }
+            state.space_hold = Some(SpaceHold::Dragging);
+            state.perform_pointer_action(|pointer| {
+                move_sequence(pointer, target.0, target.1)?;
+                pointer.button(primary_button(), Direction::Press)
+            });
+            state.last_mouse_pos = Some(target);
+            state.grid_cache.clear();
+            iced::Task::none()
+        }
+        Message::MoveWindow(window, x, y) => {
+            compositor::move_window(&window, x, y);
+            iced::Task::none()
+        }
+        Message::ResizeWindow(window, (x, y), (width, height)) => {
+            compositor::resize_window(&window, x, y, width, height);
+            iced::Task::none()
+        }
+        Message::RestoreFocus => {
+            // Some compositors leave focus nowhere after the layer surface goes away.
+            if let Some(window) = state.previous_focus.take() {
+                compositor::focus_window(&window);
+            }
+            iced::Task::none()
+        }
+        Message::IcedEvent(Event::Touch(iced::touch::Event::FingerPressed {
+            position, ..
+        })) if cfg().touch_select && state.mode == Mode::Grid && state.dwell.is_none() => {
+            handle_tap(state, position)
+        }
+        Message::IcedEvent(Event::Mouse(iced::mouse::Event::CursorMoved { position }))
+            if cfg().pointer_select && state.mode == Mode::Grid =>
+        {
+            let previous = state.hovered_cell();
+            state.hover = Some(position);
+            if state.hovered_cell() != previous {
+                state.grid_cache.clear();
+            }
+            iced::Task::none()
+        }
+        Message::IcedEvent(Event::Mouse(iced::mouse::Event::ButtonPressed(
+            iced::mouse::Button::Left,
+        ))) if cfg().pointer_select && state.mode == Mode::Grid && state.dwell.is_none() => {
+            match state.hover {
+                Some(position) => handle_tap(state, position),
+                None => iced::Task::none(),
+            }
+        }
+        Message::IcedEvent(Event::Keyboard(keyboard::Event::ModifiersChanged(modifiers))) => {
+            state.modifiers = modifiers;
+            iced::Task::none()
+        }
+        Message::IcedEvent(Event::Keyboard(keyboard::Event::KeyReleased {
+            key: keyboard::Key::Named(keyboard::key::Named::Space),
+            ..
+        })) => match state.space_hold.take() {
+            Some(SpaceHold::Pending { target, action, .. }) => {
+                state.confirm_selection(target, action)
+            }
+            // hide_overlay releases the held button.
+            Some(SpaceHold::Dragging) => {
+                state.space_hold = Some(SpaceHold::Dragging);
+                state.hide_overlay(None)
+            }
+            None => iced::Task::none(),
+        },
+        Message::IcedEvent(Event::Keyboard(keyboard::Event::KeyReleased {
+            key: keyboard::Key::Character(c),
+            ..
+        })) => {
+            if let Some(released) = c.chars().next() {
+                let released = label_char(released);
+                state.chord_keys.retain(|(held, _)| *held != released);
+            }
+            if state
+                .scroll_hold
+                .as_ref()
+                .is_some_and(|hold| hold.key == c.to_lowercase())
+            {
+                state.scroll_hold = None;
+            }
+            state
+                .move_keys
+                .retain(|held| Some(*held) != c.chars().next().map(|ch| ch.to_ascii_lowercase()));
+            iced::Task::none()
+        }
+        Message::IcedEvent(Event::Keyboard(keyboard::Event::KeyPressed {
+            key, modifiers, ..
+        })) if state.mode == Mode::Coordinate => handle_coordinate_key(state, key, modifiers),
+        Message::IcedEvent(Event::Keyboard(keyboard::Event::KeyPressed {
+            key, modifiers, ..
+        })) if state.mode == Mode::Move => handle_move_key(state, key, modifiers),
+        Message::IcedEvent(Event::Keyboard(keyboard::Event::KeyPressed {
+            key, modifiers, ..
+        })) if state.mode == Mode::Numpad => handle_numpad_key(state, key, modifiers),
+        Message::IcedEvent(Event::Keyboard(keyboard::Event::KeyPressed { key, .. }))
+            if matches!(state.mode, Mode::WindowMove | Mode::WindowResize) =>
+        {
+            handle_window_key(state, key)
         }
         Message::IcedEvent(Event::Keyboard(keyboard::Event::KeyPressed {
             key, modifiers, ..
+        })) if state.mode != Mode::Grid => handle_region_key(state, key, modifiers),
+        Message::IcedEvent(Event::Keyboard(keyboard::Event::KeyPressed {
+            key,
+            modifiers,
+            repeat,
+            ..
         })) => {
+            if state.zoomed_cell.is_none()
+                && state.input_buffer.is_empty()
+                && let Some(mode) = mode_for_key(&key)
+            {
+                if matches!(mode, Mode::WindowMove | Mode::WindowResize) {
+                    return start_window_pick(state, mode);
+                }
+                if mode == Mode::Hotspot {
+                    state.hints = hints::collect();
+                    if state.hints.is_empty() {
+                        println!("No hints to show.");
+                        return iced::Task::none();
+                    }
+                }
+                state.enter_mode(mode);
+                state.grid_cache.clear();
+                state.numpad_area = screen_rect();
+                state.coarse_area = None;
+                let nudging = mode == Mode::Numpad && cfg().numpad_style == NumpadStyle::Directions;
+                if mode == Mode::Move || nudging {
+                    // Start from the last known pointer position, else the screen center.
+                    let (x, y) = state
+                        .last_mouse_pos
+                        .unwrap_or((cfg().screen_width / HALF, cfg().screen_height / HALF));
+                    return update(state, Message::ExecuteMove(x, y));
+                }
+                return iced::Task::none();
+            }
+            if state.zoomed_cell.is_none()
+                && state.input_buffer.is_empty()
+                && cfg().grid_pages > 1
+                && key_matches(&key, &cfg().page_key)
+            {
+                state.set_page((active_page() + 1) % cfg().grid_pages);
+                state.cursor = None;
+                state.load_adaptive();
+                return iced::Task::none();
+            }
+            if state.zoomed_cell.is_none()
+                && state.input_buffer.is_empty()
+                && key_matches(&key, &cfg().profile_key)
+            {
+                let names = profile_names();
+                let current = names.iter().position(|name| *name == cfg().profile_name);
+                let next = names[current.map_or(0, |i| (i + 1) % names.len())];
+                return update(state, Message::SwitchProfile(next.to_string()));
+            }
+            if state.zoomed_cell.is_none()
+                && state.input_buffer.is_empty()
+                && state.selection.is_none()
+                && key_matches(&key, &cfg().selection_key)
+            {
+                println!("Selection armed: pick the start point.");
+                state.selection = Some(Selection::Armed);
+                return iced::Task::none();
+            }
+            let adaptive_typed = std::mem::take(&mut state.adaptive_typed);
+            if !adaptive_typed.is_empty() {
+                state.grid_cache.clear();
+            }
+            if state.zoomed_cell.is_none()
+                && state.input_buffer.is_empty()
+                && let keyboard::Key::Character(c) = &key
+            {
+                let typed = format!("{}{}", adaptive_typed, c);
+                if let Some((_, target)) = state.adaptive.iter().find(|(label, _)| *label == typed)
+                {
+                    let (target, action) = adaptive_action(target, modifiers.shift());
+                    return state.confirm_selection(target, action);
+                }
+                if state
+                    .adaptive
+                    .iter()
+                    .any(|(label, _)| label.starts_with(&typed))
+                {
+                    state.adaptive_typed = typed;
+                    state.grid_cache.clear();
+                    return iced::Task::none();
+                }
+            }
+            // Escape only drops a started adaptive hint.
+            if !adaptive_typed.is_empty()
+                && key == keyboard::Key::Named(keyboard::key::Named::Escape)
+            {
+                return iced::Task::none();
+            }
             match key {
                 keyboard::Key::Named(keyboard::key::Named::Escape) => {
                     if !state.input_buffer.is_empty() {
                         state.input_buffer.pop();
                         state.grid_cache.clear();
                         iced::Task::none()
+                    } else if state.zoom_path.pop().is_some() {
+                        state.cursor = None;
+                        state.grid_cache.clear();
+                        iced::Task::none()
                     } else if state.zoomed_cell.is_some() {
                         state.zoomed_cell = None;
                         state.grid_cache.clear();
                         iced::Task::none()
                     } else {
-                        state.visible = false;
-                        state.input_buffer.clear();
-
-                        let (new_id, spawn_task) =
-                            Message::layershell_open(get_layer_settings(false));
-                        let old_id = state.current_id.replace(new_id).unwrap();
-
-                        iced::Task::batch(vec![
-                            iced::Task::done(Message::RemoveWindow(old_id)),
-                            spawn_task,
-                        ])
+                        state.hide_overlay(None)
                     }
                 }
+                keyboard::Key::Named(
+                    named @ (keyboard::key::Named::ArrowUp
+                    | keyboard::key::Named::ArrowDown
+                    | keyboard::key::Named::ArrowLeft
+                    | keyboard::key::Named::ArrowRight),
+                ) if state.dwell.is_none() => {
+                    move_cursor(state, named);
+                    iced::Task::none()
+                }
                 keyboard::Key::Named(keyboard::key::Named::Space) => {
+                    // Space skips the rest of a running dwell countdown.
+                    if let Some(dwell) = state.dwell.take() {
+                        return state.hide_overlay(Some(dwell.action));
+                    }
+                    if let Some(cursor) = state.cursor.take()
+                        && cursor.zoomed == state.zoomed_cell
+                    {
+                        if cursor.zoomed.is_none() {
+                            return state.zoom_into(cursor.cell);
+                        }
+                        return state.pick_sub_cell(cursor.cell, modifiers.shift());
+                    }
                     let target_cell = state.zoomed_cell;
                     let is_double = modifiers.shift();
-
-                    state.visible = false;
-                    state.input_buffer.clear();
-                    state.zoomed_cell = None;
-                    state.grid_cache.clear();
-                    let (new_id, spawn_task) = Message::layershell_open(get_layer_settings(false));
-                    let old_id = state.current_id.replace(new_id).unwrap();
-                    iced::Task::batch(vec![
-                        iced::Task::done(Message::RemoveWindow(old_id)),
-                        iced::Task::done(Message::ExecuteMoveCenter(target_cell, is_double)),
-                        spawn_task,
-                    ])
+                    let target = center_target(target_cell);
+                    let action = Message::ExecuteMoveCenter(target_cell, is_double);
+                    if cfg().hold_drag_ms == 0 {
+                        return state.confirm_selection(target, action);
+                    }
+                    if repeat || state.space_hold.is_some() {
+                        return iced::Task::none();
+                    }
+                    // Decided on release, or by HoldDragCheck if Space is still down.
+                    state.space_hold = Some(SpaceHold::Pending {
+                        target,
+                        action,
+                        started: Instant::now(),
+                    });
+                    let delay = Duration::from_millis(cfg().hold_drag_ms);
+                    iced::Task::perform(tokio::time::sleep(delay), |_| Message::HoldDragCheck)
+                }
+                keyboard::Key::Character(_) if state.dwell.is_some() => iced::Task::none(),
+                keyboard::Key::Character(c)
+                    if c.as_str() == cfg().repeat_key
+                        && state.zoomed_cell.is_none()
+                        && state.input_buffer.is_empty() =>
+                {
+                    update(state, Message::RepeatLastAction)
                 }
                 keyboard::Key::Character(c) => {
                     if state.zoomed_cell.is_some() && modifiers.control() {
-                        let step = cfg().scroll_lines;
-                        let page_step = cfg().scroll_page_lines;
-
-                        let (dx, dy) = match c.as_str() {
-                            "j" => (0, -step),      // Down (Negative Y in Enigo usually implies down)
-                            "k" => (0, step),       // Up
-                            "h" => (-step, 0),      // Left
-                            "l" => (step, 0),       // Right
-                            "d" => (0, -page_step), // Page Down
-                            "u" => (0, page_step),  // Page Up
-                            _ => (0, 0),
+                        let key = c.to_lowercase();
+                        // Shift turns j/k into horizontal scrolling, like Shift+wheel.
+                        // The flag marks the page keys, which scroll in bursts.
+                        let scroll = match key.as_str() {
+                            "j" if modifiers.shift() => Some(((1, 0), false)),
+                            "k" if modifiers.shift() => Some(((-1, 0), false)),
+                            "j" => Some(((0, -1), false)), // Down (negative Y in Enigo)
+                            "k" => Some(((0, 1), false)),  // Up
+                            "h" => Some(((-1, 0), false)), // Left
+                            "l" => Some(((1, 0), false)),  // Right
+                            "d" => Some(((0, -1), true)),  // Page Down
+                            "u" => Some(((0, 1), true)),   // Page Up
+                            _ => None,
                         };
 
-                        if dx != 0 || dy != 0 {
-                            return iced::Task::done(Message::ExecuteScroll(
-                                state.zoomed_cell,
-                                dx,
-                                dy,
-                            ));
+                        match scroll {
+                            // The scroll tick drives held keys; ignore the autorepeat.
+                            Some(_) if repeat => return iced::Task::none(),
+                            Some((direction, true)) => {
+                                state.scroll_burst = Some(ScrollBurst {
+                                    direction,
+                                    remaining: cfg().scroll_page_lines,
+                                });
+                                return update(state, Message::ScrollTick);
+                            }
+                            Some(((dx, dy), false)) => {
+                                state.scroll_hold = Some(ScrollHold {
+                                    key,
+                                    direction: (dx, dy),
+                                    started: Instant::now(),
+                                });
+                                let step = cfg().scroll_lines;
+                                return iced::Task::done(Message::ExecuteScroll(
+                                    state.zoomed_cell,
+                                    dx * step,
+                                    dy * step,
+                                ));
+                            }
+                            None => {}
                         }
                     }
                     let c_char = c.chars().next().unwrap();
                     if state.zoomed_cell.is_none() {
-                        let c_upper = c_char.to_ascii_uppercase();
-                        if c_upper.is_ascii_uppercase() {
+                        let c_upper = label_char(c_char);
+                        let is_label = cfg().label_table.uses_char(c_upper);
+                        if cfg().chord_window_ms > 0 && is_label {
+                            // A held chord key must not autorepeat into the buffer.
+                            if repeat || state.chord_keys.iter().any(|(k, _)| *k == c_upper) {
+                                return iced::Task::none();
+                            }
+                            state.chord_keys.push((c_upper, Instant::now()));
+                        }
+                        if is_label {
                             state.input_buffer.push(c_upper);
                             state.grid_cache.clear();
                         }
-                        if state.input_buffer.len() >= 2 {
-                            let chars: Vec<char> = state.input_buffer.chars().collect();
-                            let row = (chars[0] as u32 - BASE_CHAR) as i32;
-                            let col = (chars[1] as u32 - BASE_CHAR) as i32;
-
-                            if modifiers.shift() {
-                                state.visible = false;
-                                state.input_buffer.clear();
-                                state.grid_cache.clear();
-                                let (new_id, spawn_task) =
-                                    Message::layershell_open(get_layer_settings(false));
-                                let old_id = state.current_id.replace(new_id).unwrap();
-
-                                return iced::Task::batch(vec![
-                                    iced::Task::done(Message::RemoveWindow(old_id)),
-                                    iced::Task::done(Message::ExecuteMoveCenter(
-                                        Some((row, col)),
-                                        true,
-                                    )), // Double click
-                                    spawn_task,
-                                ]);
+                        if !state.input_buffer.is_empty() {
+                            let (row, col) = match cfg()
+                                .label_table
+                                .lookup(&state.input_buffer, cfg().label_unique_prefix)
+                            {
+                                labels::Lookup::Cell(row, col) => (row, col),
+                                labels::Lookup::Prefix => return iced::Task::none(),
+                                labels::Lookup::NoMatch => {
+                                    // No cell has this label; start over.
+                                    state.input_buffer.clear();
+                                    state.grid_cache.clear();
+                                    return iced::Task::none();
+                                }
+                            };
+
+                            // Shift double clicks; a chord clicks the cell center right away
+                            // instead of zooming into the sub-grid.
+                            if modifiers.shift() || is_chord(&state.chord_keys) {
+                                let is_double = modifiers.shift();
+                                return state.confirm_selection(
+                                    center_target(Some((row, col))),
+                                    Message::ExecuteMoveCenter(Some((row, col)), is_double),
+                                );
                             }
 
-                            state.zoomed_cell = Some((row, col));
-                            state.input_buffer.clear();
-                            state.grid_cache.clear();
+                            return state.zoom_into((row, col));
                         }
                         iced::Task::none()
-                    } else if let Some((sub_row, sub_col)) = map_key_to_subgrid(c_char) {
-                        let (main_row, main_col) = state.zoomed_cell.unwrap();
-                        let is_double = modifiers.shift();
-
-                        state.visible = false;
-                        state.input_buffer.clear();
-                        state.zoomed_cell = None;
-                        state.grid_cache.clear();
-                        let (new_id, spawn_task) =
-                            Message::layershell_open(get_layer_settings(false));
-                        let old_id = state.current_id.replace(new_id).unwrap();
-                        iced::Task::batch(vec![
-                            iced::Task::done(Message::RemoveWindow(old_id)),
-                            iced::Task::done(Message::ExecuteMovePrecision(
-                                main_row, main_col, sub_row, sub_col, is_double,
-                            )),
-                            spawn_task,
-                        ])
+                    } else if let Some(sub_cell) = map_key_to_label(state.sub_labels(), c_char) {
+                        state.pick_sub_cell(sub_cell, modifiers.shift())
                     } else {
                         iced::Task::none()
                     }
@@ -548,47 +4470,348 @@ fn update(state: &mut Rowlink, message: Message) -> iced::Task<Message> {
             }
         }
         Message::ExecuteMovePrecision(main_row, main_col, sub_row, sub_col, is_double) => {
-            let (target_x, target_y) = cfg().get_precision_target(
-                cfg().screen_width,
-                cfg().screen_height,
-                main_row,
-                main_col,
-                sub_row,
-                sub_col,
+            state.remember_action(&message);
+            state.record_click((main_row, main_col), Some((sub_row, sub_col)));
+            let (target_x, target_y) = state.learned_target(
+                offset_cell((main_row, main_col), Some((sub_row, sub_col))),
+                precision_target(main_row, main_col, sub_row, sub_col),
             );
+            let clicks = 1 + is_double as u32;
             let same_pos = state.last_mouse_pos == Some((target_x, target_y));
-            state.perform_enigo_action(|enigo| {
-                click_sequence(enigo, target_x, target_y, is_double, same_pos)
+            state.perform_pointer_action(|pointer| {
+                click_sequence(pointer, target_x, target_y, clicks, same_pos)
             });
             state.last_mouse_pos = Some((target_x, target_y));
+            notify_click(primary_button(), clicks, target_x, target_y);
             iced::Task::none()
         }
         Message::ExecuteMoveCenter(target_cell, is_double) => {
+            state.remember_action(&message);
+            if let Some(cell) = target_cell {
+                state.record_click(cell, None);
+            }
             let (target_x, target_y) = match target_cell {
-                Some((r, c)) => {
-                    cfg().get_main_cell_center(cfg().screen_width, cfg().screen_height, r, c)
+                Some(cell) => {
+                    state.learned_target(offset_cell(cell, None), center_target(target_cell))
                 }
-                None => (cfg().screen_width / HALF, cfg().screen_height / HALF),
+                None => center_target(target_cell),
             };
+            let clicks = 1 + is_double as u32;
             let same_pos = state.last_mouse_pos == Some((target_x, target_y));
-            state.perform_enigo_action(|enigo| {
-                click_sequence(enigo, target_x, target_y, is_double, same_pos)
+            state.perform_pointer_action(|pointer| {
+                click_sequence(pointer, target_x, target_y, clicks, same_pos)
             });
             state.last_mouse_pos = Some((target_x, target_y));
+            notify_click(primary_button(), clicks, target_x, target_y);
             iced::Task::none()
         }
         Message::ExecuteScroll(target_cell, dx, dy) => {
-            let (target_x, target_y) = match target_cell {
-                Some((r, c)) => {
-                    cfg().get_main_cell_center(cfg().screen_width, cfg().screen_height, r, c)
+            let (target_x, target_y) = center_target(target_cell);
+            let same_pos = state.last_mouse_pos == Some((target_x, target_y));
+            let step = state.scroll_step();
+            let lines = (state.app_scroll(dx), state.app_scroll(dy));
+            if !same_pos {
+                state.perform_pointer_action(|pointer| move_sequence(pointer, target_x, target_y));
+            }
+            match state.wheel() {
+                Some(wheel) => {
+                    if let Err(e) = scroll_sequence(wheel.as_mut(), lines, 1) {
+                        eprintln!("uinput wheel error: {:?}", e);
+                    }
+                }
+                None => {
+                    state.perform_pointer_action(|pointer| scroll_sequence(pointer, lines, step))
+                }
+            }
+            state.last_mouse_pos = Some((target_x, target_y));
+            if state.visible && state.scroll_point != Some((target_x, target_y)) {
+                state.scroll_point = Some((target_x, target_y));
+                state.grid_cache.clear();
+            }
+            iced::Task::none()
+        }
+        Message::ExecuteClick(target_x, target_y, is_double) => {
+            state.remember_action(&message);
+            let clicks = 1 + is_double as u32;
+            let same_pos = state.last_mouse_pos == Some((target_x, target_y));
+            state.perform_pointer_action(|pointer| {
+                click_sequence(pointer, target_x, target_y, clicks, same_pos)
+            });
+            state.last_mouse_pos = Some((target_x, target_y));
+            notify_click(primary_button(), clicks, target_x, target_y);
+            iced::Task::none()
+        }
+        Message::ExecuteTripleClick(target_x, target_y) => {
+            state.remember_action(&message);
+            let same_pos = state.last_mouse_pos == Some((target_x, target_y));
+            state.perform_pointer_action(|pointer| {
+                click_sequence(pointer, target_x, target_y, 3, same_pos)
+            });
+            state.last_mouse_pos = Some((target_x, target_y));
+            notify_click(primary_button(), 3, target_x, target_y);
+            iced::Task::none()
+        }
+        Message::ExecuteClickType(target_x, target_y, ref text) => {
+            let text = resolve_type_text(text.clone());
+            state.remember_action(&message);
+            let same_pos = state.last_mouse_pos == Some((target_x, target_y));
+            state.perform_pointer_action(|pointer| {
+                click_sequence(pointer, target_x, target_y, 1, same_pos)
+            });
+            state.last_mouse_pos = Some((target_x, target_y));
+            notify_click(primary_button(), 1, target_x, target_y);
+            if let Some(text) = text {
+                // Give the clicked field a moment to take focus.
+                std::thread::sleep(Duration::from_millis(cfg().delay_type_ms));
+                state.type_text(&text);
+            }
+            iced::Task::none()
+        }
+        Message::ExecutePaste(target_x, target_y) => {
+            state.remember_action(&message);
+            with_toplevel_at((target_x, target_y), move |toplevel| {
+                let middle_click =
+                    toplevel.is_some_and(|app| cfg().middle_click_paste_apps.contains(&app.app_id));
+                Message::PasteAt(target_x, target_y, middle_click)
+            })
+        }
+        Message::PasteAt(target_x, target_y, middle_click) => {
+            let same_pos = state.last_mouse_pos == Some((target_x, target_y));
+            state.perform_pointer_action(|pointer| {
+                if middle_click {
+                    if !same_pos {
+                        move_sequence(pointer, target_x, target_y)?;
+                    }
+                    pointer.button(Button::Middle, Direction::Click)
+                } else {
+                    click_sequence(pointer, target_x, target_y, 1, same_pos)
                 }
-                None => (cfg().screen_width / HALF, cfg().screen_height / HALF),
+            });
+            state.last_mouse_pos = Some((target_x, target_y));
+            let button = if middle_click {
+                Button::Middle
+            } else {
+                primary_button()
+            };
+            notify_click(button, 1, target_x, target_y);
+            if middle_click {
+                return iced::Task::none();
+            }
+            // Give the clicked field a moment to take focus.
+            let delay = Duration::from_millis(cfg().delay_type_ms);
+            iced::Task::perform(tokio::time::sleep(delay), |_| Message::PasteShortcut)
+        }
+        Message::PasteShortcut => {
+            state.press_shortcut(enigo::Key::Shift, enigo::Key::Insert);
+            iced::Task::none()
+        }
+        Message::CoordinateWindowFound(window) => {
+            if state.mode != Mode::Coordinate {
+                return iced::Task::none();
+            }
+            if window.is_none() {
+                eprintln!("No focused window for coordinates.");
+            }
+            state.coordinate_window = window;
+            state.grid_cache.clear();
+            iced::Task::none()
+        }
+        Message::TargetAppFound(cell, activation, name) => {
+            // Only for the cell still zoomed into.
+            if state.zoomed_cell == Some(cell) && state.activation == activation {
+                state.target_app = name;
+                state.grid_cache.clear();
+            }
+            iced::Task::none()
+        }
+        Message::ExecuteSelectStart(target_x, target_y) => {
+            state.perform_pointer_action(|pointer| {
+                move_sequence(pointer, target_x, target_y)?;
+                pointer.button(primary_button(), Direction::Press)
+            });
+            state.last_mouse_pos = Some((target_x, target_y));
+            // Activation releases the button again when it doesn't show the grid.
+            state.selection = Some(Selection::Dragging((target_x, target_y)));
+            update(state, Message::SignalReceived(Mode::Grid))
+        }
+        Message::ExecuteSelectEnd(from_x, from_y, target_x, target_y) => {
+            state.perform_pointer_action(|pointer| {
+                drag_sequence(pointer, (from_x, from_y), (target_x, target_y))
+            });
+            state.last_mouse_pos = Some((target_x, target_y));
+            iced::Task::none()
+        }
+        Message::SwitchProfile(name) => {
+            if switch_profile(&name) {
+                state.zoomed_cell = None;
+                state.zoom_path.clear();
+                state.input_buffer.clear();
+                state.grid_cache.clear();
+                state.clear_static_layers();
+            }
+            iced::Task::none()
+        }
+        Message::MarkPointer(name) => {
+            match state.last_mouse_pos {
+                Some((x, y)) => marks::add(&name, x, y),
+                None => eprintln!("No pointer position to mark as {} yet.", name),
+            }
+            iced::Task::none()
+        }
+        Message::RepeatLastAction => {
+            let Some((action, page)) = state.last_action.clone() else {
+                println!("Nothing to repeat yet.");
+                return iced::Task::none();
             };
+            // Cells are resolved against the active page.
+            state.set_page(page);
+            if state.visible {
+                state.hide_overlay(Some(action))
+            } else {
+                iced::Task::done(action)
+            }
+        }
+        Message::PurgeHistory => {
+            state.history = None;
+            state.offsets = None;
+            state.last_cell_click = None;
+            state.adaptive.clear();
+            marks::clear();
+            purge_state();
+            println!("Purged the click history, learned offsets and marks.");
+            iced::Task::none()
+        }
+        Message::SwitchTheme(name) => {
+            if switch_theme(&name) {
+                state.grid_cache.clear();
+                state.clear_static_layers();
+            }
+            iced::Task::none()
+        }
+        Message::TypeRequested(text) => {
+            state.pending_text = Some(text);
+            update(state, Message::SignalReceived(Mode::Grid))
+        }
+        Message::QuickRequested => update(state, Message::SignalReceived(Mode::Quick)),
+        Message::ExecuteButtonClick(target_x, target_y, button) => {
+            state.remember_action(&message);
             let same_pos = state.last_mouse_pos == Some((target_x, target_y));
-            state.perform_enigo_action(|enigo| {
-                scroll_sequence(enigo, target_x, target_y, dx, dy, same_pos)
+            state.perform_pointer_action(|pointer| {
+                if !same_pos {
+                    move_sequence(pointer, target_x, target_y)?;
+                }
+                pointer.button(button, Direction::Click)
             });
             state.last_mouse_pos = Some((target_x, target_y));
+            notify_click(button, 1, target_x, target_y);
+            iced::Task::none()
+        }
+        Message::PickTarget(target) => match target {
+            picker::Target::Hotspot { x, y } => {
+                iced::Task::done(Message::ExecuteClick(x, y, false))
+            }
+            picker::Target::Window { window, x, y } => {
+                compositor::focus_window(&window);
+                iced::Task::done(Message::ExecuteMove(x, y))
+            }
+        },
+        Message::ExecuteMove(target_x, target_y) => {
+            if state.last_mouse_pos != Some((target_x, target_y)) {
+                state.perform_pointer_action(|pointer| move_sequence(pointer, target_x, target_y));
+                state.last_mouse_pos = Some((target_x, target_y));
+            }
+            iced::Task::none()
+        }
+        Message::MoveTick => {
+            let now = Instant::now();
+            let elapsed = now - state.move_last_tick.replace(now).unwrap_or(now);
+            let (mut dir_x, mut dir_y) = (0.0, 0.0);
+            for (x, y) in state.move_keys.iter().filter_map(|c| move_direction(*c)) {
+                dir_x += x;
+                dir_y += y;
+            }
+            let length = (dir_x * dir_x + dir_y * dir_y).sqrt();
+            let Some((from_x, from_y)) = state.last_mouse_pos else {
+                return iced::Task::none();
+            };
+            let direction = if length == 0.0 {
+                (0.0, 0.0)
+            } else {
+                (dir_x / length, dir_y / length)
+            };
+            let Some((dir_x, dir_y)) =
+                cfg()
+                    .move_smoothing
+                    .filter(&mut state.move_history, now, direction)
+            else {
+                return iced::Task::none();
+            };
+            let tier = if state.modifiers.control() {
+                cfg().move_slow_factor
+            } else if state.modifiers.shift() {
+                cfg().move_fast_factor
+            } else {
+                1.0
+            };
+            let speed = cfg().move_acceleration.speed_at(now - state.move_started);
+            let distance = speed * tier * elapsed.as_secs_f32();
+            let to_x = (from_x + dir_x * distance).clamp(0.0, cfg().screen_width);
+            let to_y = (from_y + dir_y * distance).clamp(0.0, cfg().screen_height);
+            // Sub-pixel progress accumulates in last_mouse_pos; only whole pixels are sent.
+            let dx = to_x.round() as i32 - from_x.round() as i32;
+            let dy = to_y.round() as i32 - from_y.round() as i32;
+            state.last_mouse_pos = Some((to_x, to_y));
+            // The ring only needs repainting once the pointer has actually moved.
+            if dx != 0 || dy != 0 {
+                state.perform_pointer_action(|pointer| pointer.move_mouse(dx, dy, Coordinate::Rel));
+                state.grid_cache.clear();
+            }
+            iced::Task::none()
+        }
+        Message::ScrollTick => {
+            let (mut dx, mut dy) = (0, 0);
+            let delay = Duration::from_millis(cfg().scroll_repeat_delay_ms);
+            if let Some(hold) = &state.scroll_hold
+                && let Some(held) = hold.started.elapsed().checked_sub(delay)
+            {
+                let lines = cfg().scroll_lines as f32
+                    * (1.0 + cfg().scroll_acceleration * held.as_secs_f32());
+                let lines = (lines.round() as i32).min(cfg().scroll_max_lines);
+                dx += hold.direction.0 * lines;
+                dy += hold.direction.1 * lines;
+            }
+            if let Some(burst) = &mut state.scroll_burst {
+                let lines = burst.remaining.min(cfg().scroll_burst_lines.max(1));
+                dx += burst.direction.0 * lines;
+                dy += burst.direction.1 * lines;
+                burst.remaining -= lines;
+                if burst.remaining <= 0 {
+                    state.scroll_burst = None;
+                }
+            }
+            if dx == 0 && dy == 0 {
+                return iced::Task::none();
+            }
+            update(state, Message::ExecuteScroll(state.zoomed_cell, dx, dy))
+        }
+        Message::DwellTick => {
+            let Some(dwell) = &state.dwell else {
+                return iced::Task::none();
+            };
+            if dwell.started.elapsed() >= Duration::from_millis(cfg().dwell_click_ms) {
+                let action = dwell.action.clone();
+                return state.hide_overlay(Some(action));
+            }
+            state.grid_cache.clear();
+            iced::Task::none()
+        }
+        // Each tick redraws the fading name; the OSD is dropped once fully faded.
+        Message::OsdTick => {
+            if state.mode_osd.is_some_and(|(_, shown)| {
+                shown.elapsed() >= Duration::from_millis(cfg().mode_osd_ms + MODE_OSD_FADE_MS)
+            }) {
+                state.mode_osd = None;
+            }
             iced::Task::none()
         }
         _ => iced::Task::none(),
@@ -598,12 +4821,29 @@ fn update(state: &mut Rowlink, message: Message) -> iced::Task<Message> {
 // --- View & Style ---
 
 fn view(state: &'_ Rowlink) -> Element<'_, Message> {
+    // The idle surface is a 1x1 transparent placeholder; no canvas means no grid work at all.
     if !state.visible {
-        return iced::widget::container(iced::widget::space()).into();
+        return iced::widget::space().into();
     }
     Canvas::new(state).width(Fill).height(Fill).into()
 }
 
+/// Activation should reach its first frame well within this.
+const ACTIVATION_BUDGET: Duration = Duration::from_millis(100);
+
+/// Reports the time from the activation signal to the first frame, as a `drawn` event and,
+/// past the budget, on stderr.
+fn report_activation_time(elapsed: Duration) {
+    ipc::emit(json!({ "event": "drawn", "ms": elapsed.as_millis() as u64 }));
+    if elapsed > ACTIVATION_BUDGET {
+        eprintln!(
+            "Activation took {} ms to the first frame, over the {} ms budget.",
+            elapsed.as_millis(),
+            ACTIVATION_BUDGET.as_millis()
+        );
+    }
+}
+
 fn style(_state: &Rowlink, _theme: &Theme) -> iced::theme::Style {
     iced::theme::Style {
         background_color: Color::TRANSPARENT,
@@ -624,23 +4864,214 @@ impl<Message> canvas::Program<Message> for Rowlink {
         bounds: Rectangle,
         _cursor: iced::mouse::Cursor,
     ) -> Vec<canvas::Geometry> {
+        if let Some(activated_at) = self.activated_at.take() {
+            report_activation_time(activated_at.elapsed());
+        }
+        // Keep the surface (and its keyboard grab) during a hold-drag, but show nothing.
+        if matches!(self.space_hold, Some(SpaceHold::Dragging)) {
+            return Vec::new();
+        }
         let grid = self.grid_cache.draw(renderer, bounds.size(), |frame| {
             let (cell_width, cell_height) =
                 cfg().get_main_cell_size(bounds.width, bounds.height);
             let stroke_normal = canvas::Stroke {
-                style: Style::Solid(cfg().color_grid_border.to_iced()),
-                width: 1.0,
+                style: Style::Solid(theme().color_grid_border.to_iced()),
+                width: theme().border_width,
                 ..Default::default()
             };
 
-            let stroke_dimmed = canvas::Stroke {
-                style: Style::Solid(cfg().color_border_dimmed.to_iced()),
-                width: 1.0,
-                ..Default::default()
-            };
+            let hovered = self.hovered_cell();
+
+            if self.mode == Mode::Coarse {
+                let grid = cfg().coarse_grid(self.coarse_area.unwrap_or_else(screen_rect));
+                for (r_idx, row_str) in cfg().coarse_labels.iter().enumerate() {
+                    for (c_idx, label_char) in row_str.chars().enumerate() {
+                        let cell = grid.cell(r_idx as i32, c_idx as i32);
+                        let (center_x, center_y) = cell.center();
+                        frame.stroke(
+                            &canvas::Path::rectangle(
+                                Point::new(cell.x, cell.y),
+                                iced::Size::new(cell.width, cell.height),
+                            ),
+                            stroke_normal,
+                        );
+                        frame.fill_text(Text {
+                            content: label_char.to_string(),
+                            position: Point::new(center_x, center_y),
+                            color: theme().color_main_text.to_iced(),
+                            size: (cfg().font_size * COARSE_FONT_SCALE).into(),
+                            align_x: iced::widget::text::Alignment::Center,
+                            align_y: iced::alignment::Vertical::Center,
+                            font: Font::MONOSPACE,
+                            ..Default::default()
+                        });
+                    }
+                }
+            } else if self.mode == Mode::Quick {
+                for (r_idx, row_str) in cfg().quick_labels.iter().enumerate() {
+                    for (c_idx, label_char) in row_str.chars().enumerate() {
+                        let (x, y) = cfg().get_quick_target(
+                            bounds.width,
+                            bounds.height,
+                            r_idx as i32,
+                            c_idx as i32,
+                        );
+                        // Keep edge labels fully on screen.
+                        let inset = cfg().font_size * QUICK_FONT_SCALE;
+                        let position = Point::new(
+                            x.clamp(inset, bounds.width - inset),
+                            y.clamp(inset, bounds.height - inset),
+                        );
+                        frame.fill_text(Text {
+                            content: label_char.to_string(),
+                            position,
+                            color: theme().color_main_text.to_iced(),
+                            size: (cfg().font_size * QUICK_FONT_SCALE).into(),
+                            align_x: iced::widget::text::Alignment::Center,
+                            align_y: iced::alignment::Vertical::Center,
+                            font: Font::MONOSPACE,
+                            ..Default::default()
+                        });
+                    }
+                }
+            } else if self.mode == Mode::Coordinate {
+                let scope = if self.coordinate_window.is_some() {
+                    "window "
+                } else {
+                    ""
+                };
+                frame.fill_text(Text {
+                    content: format!("{}{}_", scope, self.input_buffer),
+                    position: Point::new(bounds.width / HALF, bounds.height / HALF),
+                    color: theme().color_main_text.to_iced(),
+                    size: (cfg().font_size * COARSE_FONT_SCALE).into(),
+                    align_x: iced::widget::text::Alignment::Center,
+                    align_y: iced::alignment::Vertical::Center,
+                    font: Font::MONOSPACE,
+                    ..Default::default()
+                });
+            } else if self.mode == Mode::Numpad && cfg().numpad_style == NumpadStyle::Regions {
+                let grid = geometry::Grid::over(self.numpad_area, 3, 3, placement().scale);
+                for digit in 1..=9 {
+                    let (row, col) = numpad_cell(digit);
+                    let cell = grid.cell(row, col);
+                    frame.stroke(
+                        &canvas::Path::rectangle(
+                            Point::new(cell.x, cell.y),
+                            iced::Size::new(cell.width, cell.height),
+                        ),
+                        stroke_normal,
+                    );
+                    let (center_x, center_y) = cell.center();
+                    frame.fill_text(Text {
+                        content: digit.to_string(),
+                        position: Point::new(center_x, center_y),
+                        color: theme().color_main_text.to_iced(),
+                        size: cfg().font_size.into(),
+                        align_x: iced::widget::text::Alignment::Center,
+                        align_y: iced::alignment::Vertical::Center,
+                        font: Font::MONOSPACE,
+                        ..Default::default()
+                    });
+                }
+            } else if matches!(self.mode, Mode::Move | Mode::Numpad) {
+                if let Some((x, y)) = self.last_mouse_pos {
+                    frame.stroke(
+                        &canvas::Path::circle(Point::new(x, y), DWELL_RING_RADIUS),
+                        canvas::Stroke {
+                            style: Style::Solid(theme().color_main_text.to_iced()),
+                            width: 2.0,
+                            ..Default::default()
+                        },
+                    );
+                }
+            } else if self.mode == Mode::Hotspot {
+                for (label, hint) in &self.hints {
+                    if !label.starts_with(self.input_buffer.as_str()) {
+                        continue;
+                    }
+                    let (center_x, center_y) = hint.center();
+                    if hint.width > 0.0 && hint.height > 0.0 {
+                        frame.stroke(
+                            &canvas::Path::rectangle(
+                                Point::new(hint.x, hint.y),
+                                iced::Size::new(hint.width, hint.height),
+                            ),
+                            stroke_normal,
+                        );
+                    }
+                    frame.fill_text(Text {
+                        content: format!("{} {}", label, hint.name),
+                        position: Point::new(center_x, center_y),
+                        color: theme().color_main_text.to_iced(),
+                        size: cfg().font_size.into(),
+                        align_x: iced::widget::text::Alignment::Center,
+                        align_y: iced::alignment::Vertical::Center,
+                        font: Font::MONOSPACE,
+                        ..Default::default()
+                    });
+                }
+            } else if let Some(WindowPick::Choosing(hints)) = &self.window_pick {
+                for (label, window) in hints {
+                    if !label.starts_with(self.input_buffer.as_str()) {
+                        continue;
+                    }
+                    frame.stroke(
+                        &canvas::Path::rectangle(
+                            Point::new(window.x, window.y),
+                            iced::Size::new(window.width, window.height),
+                        ),
+                        stroke_normal,
+                    );
+                    frame.fill_text(Text {
+                        content: format!("{} {}", label, window.app_id),
+                        position: Point::new(
+                            window.x + window.width / HALF,
+                            window.y + window.height / HALF,
+                        ),
+                        color: theme().color_main_text.to_iced(),
+                        size: cfg().font_size.into(),
+                        align_x: iced::widget::text::Alignment::Center,
+                        align_y: iced::alignment::Vertical::Center,
+                        font: Font::MONOSPACE,
+                        ..Default::default()
+                    });
+                }
+            } else if let Some(dwell) = &self.dwell {
+                let progress = (dwell.started.elapsed().as_secs_f32() * 1000.0
+                    / cfg().dwell_click_ms as f32)
+                    .min(1.0);
+                let center = Point::new(dwell.target.0, dwell.target.1);
+                let start_angle = -std::f32::consts::FRAC_PI_2;
 
-            if let Some((zoom_r, zoom_c)) = self.zoomed_cell {
-                for (r_idx, row_str) in cfg().sub_labels.iter().enumerate() {
+                frame.stroke(
+                    &canvas::Path::circle(center, DWELL_RING_RADIUS),
+                    stroke_normal,
+                );
+                frame.stroke(
+                    &canvas::Path::new(|path| {
+                        path.arc(canvas::path::Arc {
+                            center,
+                            radius: DWELL_RING_RADIUS,
+                            start_angle: iced::Radians(start_angle),
+                            end_angle: iced::Radians(
+                                start_angle + progress * std::f32::consts::TAU,
+                            ),
+                        })
+                    }),
+                    canvas::Stroke {
+                        style: Style::Solid(theme().color_dwell_ring.to_iced()),
+                        width: 3.0,
+                        ..Default::default()
+                    },
+                );
+            } else if let Some(sub_grid) = self.zoom_grid(bounds.width, bounds.height) {
+                let stroke_sub = canvas::Stroke {
+                    style: Style::Solid(theme().color_sub_border.to_iced()),
+                    width: theme().sub_border_width,
+                    ..Default::default()
+                };
+                for (r_idx, row_str) in self.sub_labels().iter().enumerate() {
                     if r_idx >= cfg().sub_rows as usize {
                         break;
                     }
@@ -650,26 +5081,41 @@ impl<Message> canvas::Program<Message> for Rowlink {
                             break;
                         }
 
-                        let (target_x, target_y) = cfg().get_precision_target(
-                            bounds.width,
-                            bounds.height,
-                            zoom_r,
-                            zoom_c,
-                            r_idx as i32,
-                            c_idx as i32,
-                        );
+                        let cell = sub_grid.cell(r_idx as i32, c_idx as i32);
+                        let (target_x, target_y) = cell.center();
+                        let cell_origin = Point::new(cell.x, cell.y);
+                        let cell_size = iced::Size::new(cell.width, cell.height);
+                        if hovered == Some((r_idx as i32, c_idx as i32)) {
+                            frame.fill_rectangle(
+                                cell_origin,
+                                cell_size,
+                                theme().color_row_highlight.to_iced(),
+                            );
+                        } else if theme().color_sub_fill.a > 0.0 {
+                            frame.fill_rectangle(
+                                cell_origin,
+                                cell_size,
+                                theme().color_sub_fill.to_iced(),
+                            );
+                        }
+                        if theme().color_sub_border.a > 0.0 && theme().sub_border_width > 0.0 {
+                            frame.stroke(
+                                &iced::widget::canvas::Path::rectangle(cell_origin, cell_size),
+                                stroke_sub,
+                            );
+                        }
 
                         let text_color = if r_idx == 1 {
-                            cfg().color_sub_home_row.to_iced()
+                            theme().color_sub_home_row.to_iced()
                         } else {
-                            cfg().color_sub_default.to_iced()
+                            theme().color_sub_default.to_iced()
                         };
 
                         frame.fill_text(Text {
                             content: label_char.to_string(),
                             position: Point::new(target_x, target_y),
                             color: text_color,
-                            size: cfg().font_size.into(),
+                            size: cfg().label_size((cell.width, cell.height), 1).into(),
                             align_x: iced::widget::text::Alignment::Center,
                             align_y: iced::alignment::Vertical::Center,
                             font: Font::MONOSPACE,
@@ -677,52 +5123,87 @@ impl<Message> canvas::Program<Message> for Rowlink {
                         });
                     }
                 }
-            } else {
-                let active_row = if self.input_buffer.len() == 1 {
-                    let c = self.input_buffer.chars().next().unwrap();
-                    Some((c as u32 - BASE_CHAR) as i32)
-                } else {
-                    None
-                };
-
-                for r in 0..cfg().main_grid_size as i32 {
-                    let is_active_row = Some(r) == active_row;
-                    let is_dimmed_mode = active_row.is_some();
-
-                    let (current_stroke, current_text_color) = if !is_dimmed_mode || is_active_row {
-                        (stroke_normal, cfg().color_main_text.to_iced())
+                if let Some(app) = &self.target_app {
+                    // Below the sub-grid, or above it at the bottom of the screen.
+                    let area = sub_grid.area;
+                    let below = area.y + area.height + cfg().font_size;
+                    let y = if below + cfg().font_size > bounds.height {
+                        area.y - cfg().font_size
                     } else {
-                        (stroke_dimmed, cfg().color_text_dimmed.to_iced())
+                        below
                     };
-
-                    if is_active_row {
-                        frame.fill_rectangle(
-                            Point::new(0.0, r as f32 * cell_height),
-                            iced::Size::new(bounds.width, cell_height),
-                            cfg().color_row_highlight.to_iced(),
-                        );
-                    }
-
-                    for c in 0..cfg().main_grid_size as i32 {
-                        let x = c as f32 * cell_width;
-                        let y = r as f32 * cell_height;
-                        let (center_x, center_y) =
-                            cfg().get_main_cell_center(bounds.width, bounds.height, r, c);
-                        frame.stroke(
-                            &iced::widget::canvas::Path::rectangle(
+                    frame.fill_text(Text {
+                        content: app.clone(),
+                        position: Point::new(area.center().0, y),
+                        color: theme().color_main_text.to_iced(),
+                        size: cfg().font_size.into(),
+                        align_x: iced::widget::text::Alignment::Center,
+                        align_y: iced::alignment::Vertical::Center,
+                        font: Font::MONOSPACE,
+                        ..Default::default()
+                    });
+                }
+            } else {
+                // The static layer below carries the grid itself; here only the hovered cell
+                // and the cells matching the typed prefix are drawn again, highlighted.
+                if let Some(WindowPick::Placing(window)) = &self.window_pick {
+                    frame.stroke(
+                        &canvas::Path::rectangle(
+                            Point::new(window.x, window.y),
+                            iced::Size::new(window.width, window.height),
+                        ),
+                        canvas::Stroke {
+                            style: Style::Solid(theme().color_dwell_ring.to_iced()),
+                            width: 3.0,
+                            ..Default::default()
+                        },
+                    );
+                }
+                let prefix = &self.input_buffer;
+                for r in 0..cfg().grid_rows() {
+                    for c in 0..cfg().grid_cols() {
+                        let label = cfg().label_table.get(r, c);
+                        let is_active = !prefix.is_empty()
+                            && label.is_some_and(|label| label.starts_with(prefix.as_str()));
+                        if is_active || hovered == Some((r, c)) {
+                            let (x, y) =
+                                cfg().get_main_cell_origin(bounds.width, bounds.height, r, c);
+                            frame.fill_rectangle(
                                 Point::new(x, y),
                                 iced::Size::new(cell_width, cell_height),
-                            ),
-                            current_stroke,
+                                theme().color_row_highlight.to_iced(),
+                            );
+                            draw_main_cell(
+                                frame,
+                                bounds,
+                                r,
+                                c,
+                                stroke_normal,
+                                theme().color_main_text.to_iced(),
+                            );
+                        }
+                    }
+                }
+                if prefix.is_empty() {
+                    let typed = &self.adaptive_typed;
+                    for (hint, target) in &self.adaptive {
+                        if !hint.starts_with(typed.as_str()) {
+                            continue;
+                        }
+                        let ((x, y), _) = adaptive_action(target, false);
+                        let size = cfg().font_size * 1.5;
+                        // Wider by a monospace advance for each key past the first.
+                        let width =
+                            size + cfg().font_size * 0.6 * (hint.chars().count() - 1) as f32;
+                        frame.fill_rectangle(
+                            Point::new(x - width / HALF, y - size / HALF),
+                            iced::Size::new(width, size),
+                            theme().color_row_highlight.to_iced(),
                         );
                         frame.fill_text(Text {
-                            content: format!(
-                                "{}{}",
-                                (BASE_BYTE + r as u8) as char,
-                                (BASE_BYTE + c as u8) as char
-                            ),
-                            position: Point::new(center_x, center_y),
-                            color: current_text_color,
+                            content: hint.to_string(),
+                            position: Point::new(x, y),
+                            color: theme().color_main_text.to_iced(),
                             size: cfg().font_size.into(),
                             align_x: iced::widget::text::Alignment::Center,
                             align_y: iced::alignment::Vertical::Center,
@@ -732,7 +5213,184 @@ impl<Message> canvas::Program<Message> for Rowlink {
                     }
                 }
             }
+            // Only while still looking at the scrolled cell; zooming elsewhere hides it.
+            if cfg().scroll_indicator
+                && let Some((x, y)) = self.scroll_point
+                && (x, y) == center_target(self.zoomed_cell)
+            {
+                draw_scroll_indicator(frame, x, y);
+            }
+        });
+        let mut layers = vec![grid];
+        if let Some((mode, shown)) = self.mode_osd {
+            let mut frame = canvas::Frame::new(renderer, bounds.size());
+            draw_mode_osd(&mut frame, bounds, mode, shown);
+            layers.push(frame.into_geometry());
+        }
+        let placing = matches!(self.window_pick, Some(WindowPick::Placing(_)));
+        let shows_main_grid = (self.mode == Mode::Grid || placing)
+            && self.dwell.is_none()
+            && self.zoomed_cell.is_none();
+        if !shows_main_grid {
+            return layers;
+        }
+        let main_grid = if self.input_buffer.is_empty() {
+            self.main_grid_cache.draw(renderer, bounds.size(), |frame| {
+                draw_main_grid(frame, bounds, false);
+            })
+        } else {
+            self.dimmed_grid_cache
+                .draw(renderer, bounds.size(), |frame| {
+                    draw_main_grid(frame, bounds, true);
+                })
+        };
+        // Under the rest, so highlights and everything else stay on top.
+        layers.insert(0, main_grid);
+        layers
+    }
+}
+
+/// The mode's name in large letters at the center, fading out after `mode_osd_ms`.
+fn draw_mode_osd(frame: &mut canvas::Frame, bounds: Rectangle, mode: Mode, shown: Instant) {
+    let visible = Duration::from_millis(cfg().mode_osd_ms);
+    let fade = shown.elapsed().saturating_sub(visible).as_millis() as f32;
+    let alpha = 1.0 - (fade / MODE_OSD_FADE_MS as f32).min(1.0);
+    let mut color = theme().color_main_text.to_iced();
+    color.a *= alpha;
+    frame.fill_text(Text {
+        content: mode.name().replace('_', " ").to_uppercase(),
+        position: bounds.center(),
+        color,
+        size: (cfg().font_size * MODE_OSD_FONT_SCALE).into(),
+        align_x: iced::widget::text::Alignment::Center,
+        align_y: iced::alignment::Vertical::Center,
+        font: Font::MONOSPACE,
+        ..Default::default()
+    });
+}
+
+/// Four arrowheads around `(x, y)`, pointing away from it.
+fn draw_scroll_indicator(frame: &mut canvas::Frame, x: f32, y: f32) {
+    let distance = cfg().font_size * 1.2;
+    let size = cfg().font_size / HALF;
+    for (dx, dy) in [(0.0, -1.0), (0.0, 1.0), (-1.0, 0.0), (1.0, 0.0)] {
+        let tip = Point::new(x + dx * (distance + size), y + dy * (distance + size));
+        let base = Point::new(x + dx * distance, y + dy * distance);
+        let arrow = canvas::Path::new(|path| {
+            path.move_to(tip);
+            path.line_to(Point::new(base.x - dy * size, base.y - dx * size));
+            path.line_to(Point::new(base.x + dy * size, base.y + dx * size));
+            path.close();
         });
-        vec![grid]
+        frame.fill(&arrow, theme().color_dwell_ring.to_iced());
+    }
+}
+
+/// Every main-grid cell, either in the normal or the dimmed style. Uses the precomputed
+/// layout unless the surface differs from the configured screen size.
+fn draw_main_grid(frame: &mut canvas::Frame, bounds: Rectangle, dimmed: bool) {
+    let (border, text) = if dimmed {
+        (&theme().color_border_dimmed, &theme().color_text_dimmed)
+    } else {
+        (&theme().color_grid_border, &theme().color_main_text)
+    };
+    let stroke = canvas::Stroke {
+        style: Style::Solid(border.to_iced()),
+        width: theme().border_width,
+        ..Default::default()
+    };
+    // The layout is of the first page, within the margins it was made for.
+    if let Some(layout) = &cfg().main_grid_layout
+        && ACTIVE_PAGE.load(Ordering::Relaxed) == 0
+        && layout.margins == cfg().margins()
+        && bounds.width == cfg().screen_width
+        && bounds.height == cfg().screen_height
+    {
+        frame.stroke(&layout.outline, stroke);
+        for label in &layout.labels {
+            frame.fill_text(Text {
+                color: text.to_iced(),
+                ..label.clone()
+            });
+        }
+        return;
+    }
+    for r in 0..cfg().grid_rows() {
+        for c in 0..cfg().grid_cols() {
+            draw_main_cell(frame, bounds, r, c, stroke, text.to_iced());
+        }
+    }
+}
+
+fn draw_main_cell(
+    frame: &mut canvas::Frame,
+    bounds: Rectangle,
+    r: i32,
+    c: i32,
+    stroke: canvas::Stroke,
+    text_color: Color,
+) {
+    let (cell_width, cell_height) = cfg().get_main_cell_size(bounds.width, bounds.height);
+    let (x, y) = cfg().get_main_cell_origin(bounds.width, bounds.height, r, c);
+    frame.stroke(
+        &canvas::Path::rectangle(Point::new(x, y), iced::Size::new(cell_width, cell_height)),
+        stroke,
+    );
+    // Blanked cells keep their outline so the grid stays readable.
+    let Some(label) = cfg().label_table.get(r, c) else {
+        return;
+    };
+    let chars = label.chars().count();
+    if cfg().label_hidden((cell_width, cell_height), chars, r, c) {
+        return;
+    }
+    let (center_x, center_y) = cfg().get_main_cell_center(bounds.width, bounds.height, r, c);
+    frame.fill_text(Text {
+        content: label.to_string(),
+        position: Point::new(center_x, center_y),
+        color: text_color,
+        size: cfg().label_size((cell_width, cell_height), chars).into(),
+        align_x: iced::widget::text::Alignment::Center,
+        align_y: iced::alignment::Vertical::Center,
+        font: Font::MONOSPACE,
+        ..Default::default()
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn coordinates_are_relative_to_their_area() {
+        let output = geometry::Rect {
+            x: 0.0,
+            y: 0.0,
+            width: 1920.0,
+            height: 1080.0,
+        };
+        let window = geometry::Rect {
+            x: 100.0,
+            y: 50.0,
+            width: 800.0,
+            height: 600.0,
+        };
+        assert_eq!(parse_coordinates("812,40", output), Some((812.0, 40.0)));
+        assert_eq!(parse_coordinates("50%,90%", output), Some((960.0, 972.0)));
+        assert_eq!(parse_coordinates("50%, 10", window), Some((500.0, 60.0)));
+        assert_eq!(parse_coordinates("50%", window), None);
+        assert_eq!(parse_coordinates("x%,1", window), None);
+    }
+
+    /// What `config default` and `config dump` print must load back as the same config.
+    #[test]
+    fn printed_config_loads_back() {
+        let default = AppConfig::default();
+        let finished = finish_config(AppConfig::default(), DEFAULT_PROFILE);
+        for config in [&default, &finished] {
+            let yaml = serde_yaml::to_string(config).unwrap();
+            let loaded: AppConfig = serde_yaml::from_str(&yaml).unwrap();
+            assert_eq!(serde_yaml::to_string(&loaded).unwrap(), yaml);
+        }
     }
 }
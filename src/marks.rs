@@ -0,0 +1,105 @@
+use crate::paths;
+use serde::{Deserialize, Serialize};
+use std::sync::RwLock;
+
+// --- Marks ---
+// Named screen points, kept in `marks.toml` in the state directory so they survive
+// restarts: one `"<name>" = [x, y]` line each, in logical pixels. They can be picked like
+// hotspots (`mark: <name>`) and are managed over the control socket with `rowlink mark`.
+
+pub const FILE: &str = "marks.toml";
+
+static MARKS: RwLock<Vec<Mark>> = RwLock::new(Vec::new());
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Mark {
+    pub name: String,
+    pub x: f32,
+    pub y: f32,
+}
+
+/// Reads the saved marks, replacing the ones in memory.
+pub fn load() {
+    let Some(path) = paths::state_file(FILE) else {
+        return;
+    };
+    let Ok(text) = std::fs::read_to_string(&path) else {
+        return;
+    };
+    let marks = match parse(&text) {
+        Ok(marks) => marks,
+        Err(e) => {
+            eprintln!("Ignoring {}: {}", path.display(), e);
+            return;
+        }
+    };
+    *MARKS.write().unwrap_or_else(|e| e.into_inner()) = marks;
+}
+
+fn parse(text: &str) -> Result<Vec<Mark>, String> {
+    let document: toml_edit::DocumentMut = text.parse().map_err(|e| format!("{}", e))?;
+    let number = |value: Option<&toml_edit::Value>| {
+        value.and_then(|v| v.as_float().or_else(|| v.as_integer().map(|i| i as f64)))
+    };
+    document
+        .iter()
+        .map(|(name, item)| {
+            let point = item.as_array().filter(|point| point.len() == 2);
+            match point.and_then(|p| Some((number(p.get(0))?, number(p.get(1))?))) {
+                Some((x, y)) => Ok(Mark {
+                    name: name.to_string(),
+                    x: x as f32,
+                    y: y as f32,
+                }),
+                None => Err(format!("mark {:?} is not an [x, y] pair", name)),
+            }
+        })
+        .collect()
+}
+
+fn save(marks: &[Mark]) {
+    let mut text =
+        String::from("# Marks saved by rowlink: \"<name>\" = [x, y] in logical pixels.\n");
+    for mark in marks {
+        // A JSON string is also a valid TOML basic string.
+        let name = serde_json::to_string(&mark.name).unwrap_or_default();
+        text += &format!("{} = [{:?}, {:?}]\n", name, mark.x, mark.y);
+    }
+    paths::write_state_file(FILE, text.as_bytes());
+}
+
+pub fn all() -> Vec<Mark> {
+    MARKS.read().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+/// Sets `name` to `(x, y)`, replacing a mark of the same name, and saves.
+pub fn add(name: &str, x: f32, y: f32) {
+    let mut marks = MARKS.write().unwrap_or_else(|e| e.into_inner());
+    let mark = Mark {
+        name: name.to_string(),
+        x,
+        y,
+    };
+    match marks.iter_mut().find(|mark| mark.name == name) {
+        Some(existing) => *existing = mark,
+        None => marks.push(mark),
+    }
+    save(&marks);
+}
+
+/// Forgets every mark, e.g. after `rowlink purge` deleted the file.
+pub fn clear() {
+    MARKS.write().unwrap_or_else(|e| e.into_inner()).clear();
+}
+
+/// Removes `name` and saves; false if there was no such mark.
+pub fn remove(name: &str) -> bool {
+    let mut marks = MARKS.write().unwrap_or_else(|e| e.into_inner());
+    let count = marks.len();
+    marks.retain(|mark| mark.name != name);
+    if marks.len() == count {
+        return false;
+    }
+    save(&marks);
+    true
+}
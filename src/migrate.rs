@@ -0,0 +1,54 @@
+use serde_yaml::{Mapping, Value};
+
+// --- Config Migration ---
+// Configs carry the `version` they were written for. Options renamed since then are
+// moved to their new names on load, in the base config and in every profile, so old
+// files keep working; the file itself is left untouched.
+
+/// Version written by this build.
+pub const CURRENT: u64 = 1;
+
+/// `(version, old name, new name)`: options renamed when the config format reached
+/// `version`. Add an entry here whenever an option is renamed, and bump `CURRENT`.
+const RENAMES: &[(u64, &str, &str)] = &[];
+
+/// The version a raw config was written for; configs from before versioning count as 0.
+pub fn version(raw: &Value) -> u64 {
+    raw.get("version").and_then(Value::as_u64).unwrap_or(0)
+}
+
+/// Upgrades `raw` to `CURRENT` in place and describes each change made.
+pub fn migrate(raw: &mut Value) -> Vec<String> {
+    let from = version(raw);
+    let mut notes = Vec::new();
+    let Some(config) = raw.as_mapping_mut() else {
+        return notes;
+    };
+    for (_, old, new) in RENAMES.iter().filter(|(at, _, _)| *at > from) {
+        rename(config, old, new, "", &mut notes);
+        if let Some(Value::Mapping(profiles)) = config.get_mut("profiles") {
+            for (name, profile) in profiles.iter_mut() {
+                if let (Some(name), Value::Mapping(profile)) = (name.as_str(), profile) {
+                    let scope = format!("profile {}: ", name);
+                    rename(profile, old, new, &scope, &mut notes);
+                }
+            }
+        }
+    }
+    if from < CURRENT {
+        config.insert("version".into(), CURRENT.into());
+    }
+    notes
+}
+
+fn rename(config: &mut Mapping, old: &str, new: &str, scope: &str, notes: &mut Vec<String>) {
+    let Some(value) = config.remove(old) else {
+        return;
+    };
+    if config.contains_key(new) {
+        notes.push(format!("{}dropped {}, {} is already set", scope, old, new));
+    } else {
+        config.insert(new.into(), value);
+        notes.push(format!("{}renamed {} to {}", scope, old, new));
+    }
+}
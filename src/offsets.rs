@@ -0,0 +1,85 @@
+use crate::paths;
+use serde::{Deserialize, Serialize};
+
+// --- Click Offset Learning ---
+// When a cell's click is corrected by hand in move mode right afterwards, the correction
+// is remembered per cell, and the average of them is added to that cell's next clicks.
+// This quietly makes up for systematic scaling or panel-offset errors. Cells are told
+// apart by profile, main grid shape and grid page, like the click history, since the same
+// cell sits elsewhere on another grid or page. Kept in `click_offsets.json` in the state directory.
+
+pub const FILE: &str = "click_offsets.json";
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Offsets {
+    corrections: Vec<Learned>,
+}
+
+/// A main cell, or a sub-cell of it, on one profile's grid.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Cell {
+    pub profile: String,
+    /// Rows and columns of the main grid the cell belongs to.
+    pub grid: (i32, i32),
+    #[serde(default)]
+    pub page: u32,
+    pub cell: (i32, i32),
+    pub sub_cell: Option<(i32, i32)>,
+}
+
+/// Running sums of the corrections seen for one cell.
+#[derive(Debug, Serialize, Deserialize)]
+struct Learned {
+    #[serde(flatten)]
+    cell: Cell,
+    dx: f32,
+    dy: f32,
+    count: u32,
+}
+
+impl Offsets {
+    /// The saved offsets, if any.
+    pub fn load() -> Self {
+        paths::read_state(FILE)
+    }
+
+    /// Average correction for `cell`, zero until one was recorded.
+    pub fn get(&self, cell: &Cell) -> (f32, f32) {
+        match self
+            .corrections
+            .iter()
+            .find(|learned| learned.cell == *cell)
+        {
+            Some(learned) if learned.count > 0 => (
+                learned.dx / learned.count as f32,
+                learned.dy / learned.count as f32,
+            ),
+            _ => (0.0, 0.0),
+        }
+    }
+
+    /// Adds one correction for `cell` and saves.
+    pub fn record(&mut self, cell: Cell, dx: f32, dy: f32) {
+        let index = match self
+            .corrections
+            .iter()
+            .position(|learned| learned.cell == cell)
+        {
+            Some(index) => index,
+            None => {
+                self.corrections.push(Learned {
+                    cell,
+                    dx: 0.0,
+                    dy: 0.0,
+                    count: 0,
+                });
+                self.corrections.len() - 1
+            }
+        };
+        let learned = &mut self.corrections[index];
+        learned.dx += dx;
+        learned.dy += dy;
+        learned.count += 1;
+        paths::write_state(FILE, self);
+    }
+}
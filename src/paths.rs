@@ -0,0 +1,121 @@
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use std::path::PathBuf;
+use std::sync::OnceLock;
+
+// --- Paths ---
+// Where rowlink keeps its files, following the XDG base directory spec:
+//   config  $XDG_CONFIG_HOME/rowlink/config.yaml  (--config, ROWLINK_CONFIG)
+//   state   $XDG_STATE_HOME/rowlink/              (--state-dir, ROWLINK_STATE_DIR)
+//   socket  $XDG_RUNTIME_DIR/rowlink.sock         (--socket, ROWLINK_SOCKET)
+//   token   rowlink-websocket.token next to the socket
+// Command-line flags win over environment variables.
+
+#[derive(Debug, Default)]
+pub struct Overrides {
+    pub config: Option<PathBuf>,
+    pub state_dir: Option<PathBuf>,
+    pub socket: Option<PathBuf>,
+}
+
+static OVERRIDES: OnceLock<Overrides> = OnceLock::new();
+
+/// Installs the command-line overrides; call before anything asks for a path.
+pub fn set_overrides(overrides: Overrides) {
+    let _ = OVERRIDES.set(overrides);
+}
+
+fn overridden(flag: fn(&Overrides) -> &Option<PathBuf>, var: &str) -> Option<PathBuf> {
+    OVERRIDES
+        .get()
+        .and_then(|overrides| flag(overrides).clone())
+        .or_else(|| {
+            std::env::var_os(var)
+                .filter(|value| !value.is_empty())
+                .map(PathBuf::from)
+        })
+}
+
+fn project_dirs() -> Option<directories::ProjectDirs> {
+    directories::ProjectDirs::from("com", "rowlink", "rowlink")
+}
+
+/// The config file, whether or not it exists.
+pub fn config_file() -> Option<PathBuf> {
+    overridden(|o| &o.config, "ROWLINK_CONFIG")
+        .or_else(|| Some(project_dirs()?.config_dir().join("config.yaml")))
+}
+
+/// Directory for history, marks and statistics; created on first use by its writers.
+pub fn state_dir() -> Option<PathBuf> {
+    overridden(|o| &o.state_dir, "ROWLINK_STATE_DIR")
+        .or_else(|| Some(project_dirs()?.state_dir()?.to_path_buf()))
+}
+
+/// The daemon's control socket. Falls back to the temp directory without
+/// `$XDG_RUNTIME_DIR`.
+pub fn socket() -> PathBuf {
+    overridden(|o| &o.socket, "ROWLINK_SOCKET").unwrap_or_else(|| {
+        std::env::var_os("XDG_RUNTIME_DIR")
+            .map(PathBuf::from)
+            .unwrap_or_else(std::env::temp_dir)
+            .join("rowlink.sock")
+    })
+}
+
+/// Secret WebSocket clients present; beside the socket, so it shares its private directory.
+#[cfg(feature = "websocket")]
+pub fn websocket_token() -> PathBuf {
+    socket().with_file_name("rowlink-websocket.token")
+}
+
+/// `name` in the state directory.
+pub fn state_file(name: &str) -> Option<PathBuf> {
+    Some(state_dir()?.join(name))
+}
+
+/// Reads `name` from the state directory as JSON; the default when it doesn't exist yet
+/// or can't be read.
+pub fn read_state<T: DeserializeOwned + Default>(name: &str) -> T {
+    let Some(path) = state_file(name) else {
+        return T::default();
+    };
+    match std::fs::read(&path) {
+        Ok(bytes) => serde_json::from_slice(&bytes).unwrap_or_else(|e| {
+            eprintln!("Ignoring {}: {}", path.display(), e);
+            T::default()
+        }),
+        Err(_) => T::default(),
+    }
+}
+
+/// Writes `value` to `name` in the state directory as JSON.
+pub fn write_state<T: Serialize>(name: &str, value: &T) {
+    write_state_file(name, &serde_json::to_vec(value).unwrap_or_default());
+}
+
+/// Writes `contents` to `name` in the state directory, creating the directory.
+pub fn write_state_file(name: &str, contents: &[u8]) {
+    let Some(path) = state_file(name) else {
+        return;
+    };
+    let written = path
+        .parent()
+        .map_or(Ok(()), std::fs::create_dir_all)
+        .and_then(|_| std::fs::write(&path, contents));
+    if let Err(e) = written {
+        eprintln!("Could not write {}: {}", path.display(), e);
+    }
+}
+
+/// Deletes `name` from the state directory, if it is there.
+pub fn remove_state(name: &str) {
+    let Some(path) = state_file(name) else {
+        return;
+    };
+    match std::fs::remove_file(&path) {
+        Ok(()) => {}
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+        Err(e) => eprintln!("Could not remove {}: {}", path.display(), e),
+    }
+}
@@ -0,0 +1,52 @@
+use crate::compositor::{self, WindowRef};
+use crate::{HALF, cfg, marks};
+
+// --- Named Targets ---
+// Everything that can be picked by name from a launcher, rendered as dmenu lines.
+
+#[derive(Debug, Clone)]
+pub enum Target {
+    /// Clicked at its center, or at the point of a mark.
+    Hotspot { x: f32, y: f32 },
+    /// Focused, with the pointer warped to its center.
+    Window { window: WindowRef, x: f32, y: f32 },
+}
+
+pub struct Entry {
+    pub label: String,
+    pub target: Target,
+}
+
+pub fn entries() -> Vec<Entry> {
+    let hotspots = cfg().hotspots.iter().map(|hotspot| {
+        let (x, y) = hotspot.center();
+        Entry {
+            label: format!("hotspot: {}", hotspot.name),
+            target: Target::Hotspot { x, y },
+        }
+    });
+    let marks = marks::all().into_iter().map(|mark| Entry {
+        label: format!("mark: {}", mark.name),
+        target: Target::Hotspot {
+            x: mark.x,
+            y: mark.y,
+        },
+    });
+    let windows = compositor::toplevels().into_iter().map(|toplevel| Entry {
+        label: format!("window: {} - {}", toplevel.app_id, toplevel.title),
+        target: Target::Window {
+            window: toplevel.window,
+            x: toplevel.x + toplevel.width / HALF,
+            y: toplevel.y + toplevel.height / HALF,
+        },
+    });
+    hotspots.chain(marks).chain(windows).collect()
+}
+
+pub fn find(label: &str) -> Option<Target> {
+    let label = label.trim();
+    entries()
+        .into_iter()
+        .find(|entry| entry.label == label)
+        .map(|entry| entry.target)
+}
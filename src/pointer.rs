@@ -0,0 +1,90 @@
+use enigo::{Axis, Button, Coordinate, Direction, InputError, InputResult, Mouse};
+use std::process::Command;
+
+// --- Sway Seat Pointer ---
+// Drives the cursor of a specific seat through `swaymsg seat <name> cursor ...`, for
+// multi-seat setups where the virtual pointer would otherwise always land on seat0.
+
+pub struct SwaySeat {
+    seat: String,
+}
+
+impl SwaySeat {
+    pub fn new(seat: String) -> Self {
+        Self { seat }
+    }
+
+    fn cursor(&self, args: &[&str]) -> InputResult<()> {
+        let status = Command::new("swaymsg")
+            .args(["seat", &self.seat, "cursor"])
+            .args(args)
+            .status()
+            .map_err(|_| InputError::Simulate("failed to run swaymsg"))?;
+        if status.success() {
+            Ok(())
+        } else {
+            Err(InputError::Simulate("swaymsg seat cursor command failed"))
+        }
+    }
+}
+
+fn button_name(button: Button) -> &'static str {
+    match button {
+        Button::Left => "button1",
+        Button::Middle => "button2",
+        Button::Right => "button3",
+        Button::ScrollUp => "button4",
+        Button::ScrollDown => "button5",
+        Button::ScrollLeft => "button6",
+        Button::ScrollRight => "button7",
+        Button::Back => "button8",
+        Button::Forward => "button9",
+    }
+}
+
+impl Mouse for SwaySeat {
+    fn button(&mut self, button: Button, direction: Direction) -> InputResult<()> {
+        let name = button_name(button);
+        match direction {
+            Direction::Press => self.cursor(&["press", name]),
+            Direction::Release => self.cursor(&["release", name]),
+            Direction::Click => {
+                self.cursor(&["press", name])?;
+                self.cursor(&["release", name])
+            }
+        }
+    }
+
+    fn move_mouse(&mut self, x: i32, y: i32, coordinate: Coordinate) -> InputResult<()> {
+        let action = match coordinate {
+            Coordinate::Abs => "set",
+            Coordinate::Rel => "move",
+        };
+        self.cursor(&[action, &x.to_string(), &y.to_string()])
+    }
+
+    fn scroll(&mut self, length: i32, axis: Axis) -> InputResult<()> {
+        let button = match (axis, length < 0) {
+            (Axis::Vertical, true) => Button::ScrollUp,
+            (Axis::Vertical, false) => Button::ScrollDown,
+            (Axis::Horizontal, true) => Button::ScrollLeft,
+            (Axis::Horizontal, false) => Button::ScrollRight,
+        };
+        for _ in 0..length.unsigned_abs() {
+            self.button(button, Direction::Click)?;
+        }
+        Ok(())
+    }
+
+    fn main_display(&self) -> InputResult<(i32, i32)> {
+        Err(InputError::Simulate(
+            "display size is not available per seat",
+        ))
+    }
+
+    fn location(&self) -> InputResult<(i32, i32)> {
+        Err(InputError::Simulate(
+            "cursor location is not available per seat",
+        ))
+    }
+}
@@ -0,0 +1,93 @@
+use crate::{ACTIVE_PAGE, cfg, draw_main_grid, history};
+use iced::widget::canvas;
+use iced::{Color, Font, Pixels, Point, Rectangle, Size};
+use iced_tiny_skia::graphics::Viewport;
+use iced_tiny_skia::graphics::geometry::Renderer as _;
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::atomic::Ordering;
+
+// --- Offscreen Render ---
+// Draws the idle main grid of the active profile and theme with tiny-skia, the same way
+// the overlay does, and writes it to a PNG at the configured screen size.
+
+pub fn run(out: &Path) -> Result<(), String> {
+    render(out, |frame, bounds| draw_main_grid(frame, bounds, false))
+}
+
+/// The dimmed main grid, every page of it, under a heatmap of the recorded clicks on it,
+/// of every app or only `app`: the more clicks a cell got, the redder and more opaque it is.
+pub fn heatmap(out: &Path, app: Option<&str>) -> Result<(), String> {
+    let grid = (cfg().grid_rows(), cfg().grid_cols());
+    let mut counts: HashMap<(u32, (i32, i32)), u32> = HashMap::new();
+    for target in history::History::load().targets(app, grid) {
+        *counts.entry((target.page, target.cell)).or_default() += target.count;
+    }
+    if counts.is_empty() {
+        println!("No clicks recorded on this {}x{} grid yet.", grid.0, grid.1);
+    }
+    let max = counts.values().copied().max().unwrap_or(1) as f32;
+    render(out, |frame, bounds| {
+        let (width, height) = cfg().get_main_cell_size(bounds.width, bounds.height);
+        // Cells are placed on the active page; this process draws nothing else.
+        for page in 0..cfg().grid_pages.max(1) {
+            ACTIVE_PAGE.store(page, Ordering::Relaxed);
+            for (&(_, (row, col)), &count) in counts.iter().filter(|((p, _), _)| *p == page) {
+                let heat = count as f32 / max;
+                let (x, y) = cfg().get_main_cell_origin(bounds.width, bounds.height, row, col);
+                frame.fill_rectangle(
+                    Point::new(x, y),
+                    Size::new(width, height),
+                    Color::from_rgba(1.0, 1.0 - heat, 0.0, 0.15 + 0.6 * heat),
+                );
+            }
+            draw_main_grid(frame, bounds, true);
+        }
+        ACTIVE_PAGE.store(0, Ordering::Relaxed);
+    })
+}
+
+fn render(out: &Path, draw: impl FnOnce(&mut canvas::Frame, Rectangle)) -> Result<(), String> {
+    let size = Size::new(cfg().screen_width, cfg().screen_height);
+    let tiny_skia = iced_tiny_skia::Renderer::new(Font::MONOSPACE, Pixels(cfg().font_size));
+    let mut renderer = iced::Renderer::Secondary(tiny_skia);
+    let mut frame = canvas::Frame::new(&renderer, size);
+    draw(&mut frame, Rectangle::with_size(size));
+    renderer.draw_geometry(frame.into_geometry());
+    let iced::Renderer::Secondary(tiny_skia) = &mut renderer else {
+        unreachable!("built as the tiny-skia renderer above");
+    };
+
+    let (width, height) = (size.width.round() as u32, size.height.round() as u32);
+    let viewport = Viewport::with_physical_size(Size::new(width, height), 1.0);
+    let rgba =
+        iced_tiny_skia::window::compositor::screenshot(tiny_skia, &viewport, Color::TRANSPARENT);
+    std::fs::write(out, encode_png(width, height, rgba)?).map_err(|e| e.to_string())
+}
+
+/// Compressed PNG of the premultiplied RGBA tiny-skia renders.
+fn encode_png(width: u32, height: u32, rgba: Vec<u8>) -> Result<Vec<u8>, String> {
+    let size = tiny_skia::IntSize::from_wh(width, height).ok_or("the screen size is empty")?;
+    let pixmap = tiny_skia::Pixmap::from_vec(rgba, size).ok_or("the render has the wrong size")?;
+    pixmap.encode_png().map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn png_decodes_to_the_rendered_pixels() {
+        let rgba = vec![255, 0, 0, 255, 0, 0, 0, 0, 0, 64, 0, 128];
+        let png = encode_png(3, 1, rgba.clone()).unwrap();
+        let decoded = tiny_skia::Pixmap::decode_png(&png).unwrap();
+        assert_eq!((decoded.width(), decoded.height()), (3, 1));
+        assert_eq!(decoded.data(), rgba);
+    }
+
+    #[test]
+    fn empty_or_short_renders_are_errors() {
+        assert!(encode_png(0, 1, Vec::new()).is_err());
+        assert!(encode_png(2, 2, vec![0; 4]).is_err());
+    }
+}
@@ -0,0 +1,152 @@
+use crate::{compositor, migrate};
+use std::io::{BufRead, Write};
+use std::path::Path;
+
+// --- First-Run Setup ---
+// A few terminal prompts that write a minimal config: the screen size from the detected
+// outputs, a grid density and sub-grid keys for the keyboard layout. Everything else stays
+// at its default; `rowlink config default` lists the rest.
+
+/// Target cell size in logical pixels per density, largest first.
+const DENSITIES: [(&str, f32); 3] = [("coarse", 90.0), ("normal", 65.0), ("fine", 45.0)];
+
+/// Sub-grid keys per layout, on the same physical keys as the QWERTY default.
+const LAYOUTS: [(&str, [&str; 3]); 3] = [
+    ("qwerty", ["QWERUIOP", "ASDFJKL;", "ZXCVNM,."]),
+    ("dvorak", ["',.PGCRL", "AOEUHTNS", ";QJKBMWV"]),
+    ("colemak", ["QWFPLUY;", "ARSTNEIO", "ZXCVKM,."]),
+];
+
+/// Rows and columns beyond this would need longer labels.
+const MAX_GRID: f32 = 26.0;
+
+/// Runs the prompts and writes `path`, replacing any config there; false if it could not
+/// be written.
+pub fn run(path: &Path) -> bool {
+    println!("Answer a few questions to create {}.", path.display());
+    println!("Press Enter to take the [default].\n");
+    let mut input = std::io::stdin().lock();
+
+    let outputs = compositor::outputs();
+    let (width, height) = match outputs.as_slice() {
+        [] => ask_size(&mut input),
+        [only] => {
+            println!(
+                "Using output {} ({}x{}).",
+                only.name, only.width, only.height
+            );
+            (only.width, only.height)
+        }
+        _ => {
+            let names: Vec<String> = outputs
+                .iter()
+                .map(|o| format!("{} ({}x{})", o.name, o.width, o.height))
+                .collect();
+            let output = &outputs[choose(&mut input, "Output to size the grid for", &names, 0)];
+            (output.width, output.height)
+        }
+    };
+
+    let names: Vec<String> = DENSITIES
+        .iter()
+        .map(|(name, cell)| {
+            let (rows, cols) = grid_for(width, height, *cell);
+            format!("{} ({}x{} cells)", name, rows, cols)
+        })
+        .collect();
+    let (rows, cols) = grid_for(
+        width,
+        height,
+        DENSITIES[choose(&mut input, "Grid density", &names, 1)].1,
+    );
+
+    let names: Vec<String> = LAYOUTS.iter().map(|(name, _)| name.to_string()).collect();
+    let sub_labels = LAYOUTS[choose(&mut input, "Keyboard layout", &names, 0)].1;
+
+    let mut config = serde_yaml::Mapping::new();
+    let mut set = |key: &str, value: serde_yaml::Value| {
+        config.insert(key.into(), value);
+    };
+    set("version", migrate::CURRENT.into());
+    set("screen_width", width.into());
+    set("screen_height", height.into());
+    set("main_grid_rows", rows.into());
+    set("main_grid_cols", cols.into());
+    set("sub_labels", sub_labels.to_vec().into());
+    let yaml = match serde_yaml::to_string(&config) {
+        Ok(yaml) => yaml,
+        Err(e) => {
+            eprintln!("Could not write the config: {}", e);
+            return false;
+        }
+    };
+    let contents = format!(
+        "# Written by rowlink's first-run setup. `rowlink config default` lists every option.\n{}",
+        yaml
+    );
+    let written = path
+        .parent()
+        .map_or(Ok(()), std::fs::create_dir_all)
+        .and_then(|_| std::fs::write(path, contents));
+    match written {
+        Ok(()) => {
+            println!("\nWrote {}.", path.display());
+            true
+        }
+        Err(e) => {
+            eprintln!("Could not write {}: {}", path.display(), e);
+            false
+        }
+    }
+}
+
+/// Rows and columns for cells of about `cell` pixels.
+fn grid_for(width: f32, height: f32, cell: f32) -> (i32, i32) {
+    let count = |extent: f32| (extent / cell).round().clamp(1.0, MAX_GRID) as i32;
+    (count(height), count(width))
+}
+
+/// Lists `options` and returns the index picked, `default` on an empty answer or EOF.
+fn choose(input: &mut impl BufRead, question: &str, options: &[String], default: usize) -> usize {
+    println!("{}:", question);
+    for (index, option) in options.iter().enumerate() {
+        println!("  {}) {}", index + 1, option);
+    }
+    loop {
+        let answer = prompt(input, &format!("Choice [{}]", default + 1));
+        if answer.is_empty() {
+            return default;
+        }
+        match answer.parse::<usize>() {
+            Ok(n) if (1..=options.len()).contains(&n) => return n - 1,
+            _ => println!("Enter a number from 1 to {}.", options.len()),
+        }
+    }
+}
+
+fn ask_size(input: &mut impl BufRead) -> (f32, f32) {
+    println!("Could not detect the outputs.");
+    loop {
+        let answer = prompt(input, "Screen size in logical pixels [1920x1080]");
+        if answer.is_empty() {
+            return (1920.0, 1080.0);
+        }
+        let size = answer
+            .split_once('x')
+            .and_then(|(w, h)| Some((w.trim().parse().ok()?, h.trim().parse().ok()?)))
+            .filter(|(w, h): &(f32, f32)| *w > 0.0 && *h > 0.0);
+        match size {
+            Some(size) => return size,
+            None => println!("Enter the size as WIDTHxHEIGHT, e.g. 2560x1440."),
+        }
+    }
+}
+
+/// Trimmed answer to one prompt; empty on EOF.
+fn prompt(input: &mut impl BufRead, text: &str) -> String {
+    print!("{}: ", text);
+    let _ = std::io::stdout().flush();
+    let mut line = String::new();
+    let _ = input.read_line(&mut line);
+    line.trim().to_string()
+}
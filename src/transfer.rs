@@ -0,0 +1,153 @@
+use crate::{ipc, marks, migrate, paths};
+use serde::{Deserialize, Serialize};
+use serde_yaml::{Mapping, Value};
+use std::path::Path;
+
+// --- Import & Export ---
+// Marks, hotspots and profiles in one portable JSON file, to move them between machines
+// or share them. Importing merges by name: entries of the same name are replaced, the
+// rest are kept.
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct Bundle {
+    /// Config format of the hotspots and profiles, migrated like a config file on import.
+    version: u64,
+    marks: Vec<marks::Mark>,
+    /// Entries as written in the config file.
+    hotspots: Vec<Value>,
+    profiles: Mapping,
+}
+
+/// Writes the marks and the config file's hotspots and profiles to `out`.
+pub fn export(out: &Path) -> Result<(), String> {
+    let mut config = read_config()?;
+    migrate::migrate(&mut config);
+    marks::load();
+    let bundle = Bundle {
+        version: migrate::CURRENT,
+        marks: marks::all(),
+        hotspots: match config.get("hotspots") {
+            Some(Value::Sequence(hotspots)) => hotspots.clone(),
+            _ => Vec::new(),
+        },
+        profiles: match config.get("profiles") {
+            Some(Value::Mapping(profiles)) => profiles.clone(),
+            _ => Mapping::new(),
+        },
+    };
+    let json = serde_json::to_vec_pretty(&bundle).map_err(|e| e.to_string())?;
+    std::fs::write(out, json).map_err(|e| e.to_string())?;
+    println!(
+        "Exported {} marks, {} hotspots and {} profiles.",
+        bundle.marks.len(),
+        bundle.hotspots.len(),
+        bundle.profiles.len()
+    );
+    Ok(())
+}
+
+/// Merges the marks into the saved ones. The hotspots and profiles are merged into a copy
+/// of the config file written next to it, since rewriting the YAML would lose its comments
+/// and key order; the user reviews it and moves it into place.
+pub fn import(file: &Path) -> Result<(), String> {
+    let text = std::fs::read(file).map_err(|e| e.to_string())?;
+    let bundle: Bundle = serde_json::from_slice(&text).map_err(|e| e.to_string())?;
+    if bundle.version > migrate::CURRENT {
+        return Err(format!(
+            "written for config version {}, newer than this rowlink ({})",
+            bundle.version,
+            migrate::CURRENT
+        ));
+    }
+    // Older hotspots and profiles get the renames a config file of their version would.
+    let mut imported = Mapping::new();
+    imported.insert("version".into(), bundle.version.into());
+    imported.insert("hotspots".into(), Value::Sequence(bundle.hotspots));
+    imported.insert("profiles".into(), Value::Mapping(bundle.profiles));
+    let mut imported = Value::Mapping(imported);
+    for note in migrate::migrate(&mut imported) {
+        println!("Imported {}", note);
+    }
+    let imported_hotspots = match imported.get("hotspots") {
+        Some(Value::Sequence(hotspots)) => hotspots.clone(),
+        _ => Vec::new(),
+    };
+    let profiles = match imported.get("profiles") {
+        Some(Value::Mapping(profiles)) => profiles.clone(),
+        _ => Mapping::new(),
+    };
+
+    marks::load();
+    for mark in &bundle.marks {
+        marks::add(&mark.name, mark.x, mark.y);
+    }
+    // A running daemon would otherwise overwrite them with its own marks.
+    let _ = ipc::send("mark reload");
+
+    if !imported_hotspots.is_empty() || !profiles.is_empty() {
+        let path = paths::config_file().ok_or("no home directory to keep the config in")?;
+        let mut config = read_config()?;
+        // Bring the config to the bundle's version so both use the same option names.
+        for note in migrate::migrate(&mut config) {
+            println!("Config {}", note);
+        }
+        let Value::Mapping(config_map) = &mut config else {
+            return Err(format!("{} is not a mapping", path.display()));
+        };
+        let hotspots = config_map
+            .entry("hotspots".into())
+            .or_insert_with(|| Value::Sequence(Vec::new()));
+        if let Value::Sequence(hotspots) = hotspots {
+            for hotspot in &imported_hotspots {
+                let name = hotspot.get("name");
+                match hotspots
+                    .iter_mut()
+                    .find(|h| name.is_some() && h.get("name") == name)
+                {
+                    Some(existing) => *existing = hotspot.clone(),
+                    None => hotspots.push(hotspot.clone()),
+                }
+            }
+        }
+        let existing = config_map
+            .entry("profiles".into())
+            .or_insert_with(|| Value::Mapping(Mapping::new()));
+        if let Value::Mapping(existing) = existing {
+            existing.extend(profiles.iter().map(|(k, v)| (k.clone(), v.clone())));
+        }
+        let yaml = serde_yaml::to_string(&config).map_err(|e| e.to_string())?;
+        let merged = path.with_extension("imported.yaml");
+        path.parent()
+            .map_or(Ok(()), std::fs::create_dir_all)
+            .and_then(|_| std::fs::write(&merged, yaml))
+            .map_err(|e| e.to_string())?;
+        println!(
+            "Wrote the config with the imported hotspots and profiles to {}. It leaves out \
+             the comments in {}; review it, move it over that file and restart rowlink to use \
+             them.",
+            merged.display(),
+            path.display()
+        );
+    }
+    println!(
+        "Imported {} marks, {} hotspots and {} profiles.",
+        bundle.marks.len(),
+        imported_hotspots.len(),
+        profiles.len()
+    );
+    Ok(())
+}
+
+/// The config file as raw YAML; an empty mapping when there is none yet.
+fn read_config() -> Result<Value, String> {
+    let Some(path) = paths::config_file().filter(|path| path.exists()) else {
+        return Ok(Value::Mapping(Mapping::new()));
+    };
+    let text = std::fs::read_to_string(&path).map_err(|e| e.to_string())?;
+    match serde_yaml::from_str(&text) {
+        Ok(Value::Null) => Ok(Value::Mapping(Mapping::new())),
+        Ok(config) => Ok(config),
+        Err(e) => Err(format!("{}: {}", path.display(), e)),
+    }
+}
@@ -0,0 +1,339 @@
+use enigo::{Axis, Button, Coordinate, Direction, InputError, InputResult, Mouse};
+use evdev::uinput::VirtualDevice;
+use evdev::{
+    AbsInfo, AbsoluteAxisCode, AbsoluteAxisEvent, AttributeSet, InputEvent, KeyCode, KeyEvent,
+    PropType, RelativeAxisCode, RelativeAxisEvent, UinputAbsSetup,
+};
+
+// --- uinput Devices ---
+// Virtual direct-input devices spanning the output, for applications that treat touch or
+// pen input differently from the mouse, and a scroll wheel for real wheel clicks. All need
+// write access to /dev/uinput.
+
+pub struct TouchScreen {
+    device: VirtualDevice,
+    x: i32,
+    y: i32,
+    width: i32,
+    height: i32,
+    touching: bool,
+    tracking_id: i32,
+}
+
+fn axis(code: AbsoluteAxisCode, max: i32) -> UinputAbsSetup {
+    UinputAbsSetup::new(code, AbsInfo::new(0, 0, max, 0, 0, 0))
+}
+
+fn abs(code: AbsoluteAxisCode, value: i32) -> InputEvent {
+    *AbsoluteAxisEvent::new(code, value)
+}
+
+fn rel(code: RelativeAxisCode, value: i32) -> InputEvent {
+    *RelativeAxisEvent::new(code, value)
+}
+
+fn key(code: KeyCode, pressed: bool) -> InputEvent {
+    *KeyEvent::new(code, pressed as i32)
+}
+
+fn emit_error(_: std::io::Error) -> InputError {
+    InputError::Simulate("failed to write to the uinput device")
+}
+
+impl TouchScreen {
+    /// The device's axes map 1:1 onto the output's logical pixels.
+    pub fn new(width: f32, height: f32) -> std::io::Result<Self> {
+        let (width, height) = (width.round() as i32, height.round() as i32);
+        let device = VirtualDevice::builder()?
+            .name("rowlink touchscreen")
+            .with_keys(&AttributeSet::from_iter([KeyCode::BTN_TOUCH]))?
+            .with_properties(&AttributeSet::from_iter([PropType::DIRECT]))?
+            .with_absolute_axis(&axis(AbsoluteAxisCode::ABS_X, width))?
+            .with_absolute_axis(&axis(AbsoluteAxisCode::ABS_Y, height))?
+            .with_absolute_axis(&axis(AbsoluteAxisCode::ABS_MT_SLOT, 0))?
+            .with_absolute_axis(&axis(AbsoluteAxisCode::ABS_MT_TRACKING_ID, i32::MAX))?
+            .with_absolute_axis(&axis(AbsoluteAxisCode::ABS_MT_POSITION_X, width))?
+            .with_absolute_axis(&axis(AbsoluteAxisCode::ABS_MT_POSITION_Y, height))?
+            .build()?;
+        Ok(Self {
+            device,
+            x: 0,
+            y: 0,
+            width,
+            height,
+            touching: false,
+            tracking_id: 0,
+        })
+    }
+
+    fn position_events(&self) -> [InputEvent; 4] {
+        [
+            abs(AbsoluteAxisCode::ABS_MT_POSITION_X, self.x),
+            abs(AbsoluteAxisCode::ABS_MT_POSITION_Y, self.y),
+            abs(AbsoluteAxisCode::ABS_X, self.x),
+            abs(AbsoluteAxisCode::ABS_Y, self.y),
+        ]
+    }
+
+    fn touch_down(&mut self) -> InputResult<()> {
+        self.tracking_id = self.tracking_id.wrapping_add(1) & i32::MAX;
+        let mut events = vec![
+            abs(AbsoluteAxisCode::ABS_MT_SLOT, 0),
+            abs(AbsoluteAxisCode::ABS_MT_TRACKING_ID, self.tracking_id),
+        ];
+        events.extend(self.position_events());
+        events.push(key(KeyCode::BTN_TOUCH, true));
+        self.device.emit(&events).map_err(emit_error)?;
+        self.touching = true;
+        Ok(())
+    }
+
+    fn touch_up(&mut self) -> InputResult<()> {
+        self.device
+            .emit(&[
+                abs(AbsoluteAxisCode::ABS_MT_SLOT, 0),
+                abs(AbsoluteAxisCode::ABS_MT_TRACKING_ID, -1),
+                key(KeyCode::BTN_TOUCH, false),
+            ])
+            .map_err(emit_error)?;
+        self.touching = false;
+        Ok(())
+    }
+}
+
+impl Mouse for TouchScreen {
+    /// Every button is a tap; a held button keeps the finger down so moves become drags.
+    fn button(&mut self, _button: Button, direction: Direction) -> InputResult<()> {
+        match direction {
+            Direction::Press => self.touch_down(),
+            Direction::Release => self.touch_up(),
+            Direction::Click => {
+                self.touch_down()?;
+                self.touch_up()
+            }
+        }
+    }
+
+    fn move_mouse(&mut self, x: i32, y: i32, coordinate: Coordinate) -> InputResult<()> {
+        let (x, y) = match coordinate {
+            Coordinate::Abs => (x, y),
+            Coordinate::Rel => (self.x.saturating_add(x), self.y.saturating_add(y)),
+        };
+        self.x = x.clamp(0, self.width);
+        self.y = y.clamp(0, self.height);
+        // Without a finger down there is nothing to report; the position is used by the
+        // next touch.
+        if self.touching {
+            self.device
+                .emit(&self.position_events())
+                .map_err(emit_error)?;
+        }
+        Ok(())
+    }
+
+    fn scroll(&mut self, _length: i32, _axis: Axis) -> InputResult<()> {
+        Err(InputError::Simulate("a touchscreen has no scroll wheel"))
+    }
+
+    fn main_display(&self) -> InputResult<(i32, i32)> {
+        Ok((self.width, self.height))
+    }
+
+    fn location(&self) -> InputResult<(i32, i32)> {
+        Ok((self.x, self.y))
+    }
+}
+
+/// Pressure axis resolution reported by the virtual pen.
+const PRESSURE_MAX: i32 = 4096;
+
+pub struct Stylus {
+    device: VirtualDevice,
+    x: i32,
+    y: i32,
+    width: i32,
+    height: i32,
+    pressure: i32,
+    touching: bool,
+}
+
+impl Stylus {
+    /// `pressure` is the constant contact pressure, from 0.0 to 1.0.
+    pub fn new(width: f32, height: f32, pressure: f32) -> std::io::Result<Self> {
+        let (width, height) = (width.round() as i32, height.round() as i32);
+        let device = VirtualDevice::builder()?
+            .name("rowlink stylus")
+            .with_keys(&AttributeSet::from_iter([
+                KeyCode::BTN_TOOL_PEN,
+                KeyCode::BTN_TOUCH,
+            ]))?
+            .with_properties(&AttributeSet::from_iter([PropType::DIRECT]))?
+            .with_absolute_axis(&axis(AbsoluteAxisCode::ABS_X, width))?
+            .with_absolute_axis(&axis(AbsoluteAxisCode::ABS_Y, height))?
+            .with_absolute_axis(&axis(AbsoluteAxisCode::ABS_PRESSURE, PRESSURE_MAX))?
+            .build()?;
+        Ok(Self {
+            device,
+            x: 0,
+            y: 0,
+            width,
+            height,
+            pressure: (pressure.clamp(0.0, 1.0) * PRESSURE_MAX as f32).round() as i32,
+            touching: false,
+        })
+    }
+
+    /// Brings the pen into proximity at the current position and puts it down.
+    fn pen_down(&mut self) -> InputResult<()> {
+        self.device
+            .emit(&[
+                key(KeyCode::BTN_TOOL_PEN, true),
+                abs(AbsoluteAxisCode::ABS_X, self.x),
+                abs(AbsoluteAxisCode::ABS_Y, self.y),
+            ])
+            .map_err(emit_error)?;
+        self.device
+            .emit(&[
+                key(KeyCode::BTN_TOUCH, true),
+                abs(AbsoluteAxisCode::ABS_PRESSURE, self.pressure),
+            ])
+            .map_err(emit_error)?;
+        self.touching = true;
+        Ok(())
+    }
+
+    /// Lifts the pen and takes it out of proximity.
+    fn pen_up(&mut self) -> InputResult<()> {
+        self.device
+            .emit(&[
+                key(KeyCode::BTN_TOUCH, false),
+                abs(AbsoluteAxisCode::ABS_PRESSURE, 0),
+            ])
+            .map_err(emit_error)?;
+        self.device
+            .emit(&[key(KeyCode::BTN_TOOL_PEN, false)])
+            .map_err(emit_error)?;
+        self.touching = false;
+        Ok(())
+    }
+}
+
+impl Mouse for Stylus {
+    /// Every button is a pen tap; a held button keeps the pen down so moves become strokes.
+    fn button(&mut self, _button: Button, direction: Direction) -> InputResult<()> {
+        match direction {
+            Direction::Press => self.pen_down(),
+            Direction::Release => self.pen_up(),
+            Direction::Click => {
+                self.pen_down()?;
+                self.pen_up()
+            }
+        }
+    }
+
+    fn move_mouse(&mut self, x: i32, y: i32, coordinate: Coordinate) -> InputResult<()> {
+        let (x, y) = match coordinate {
+            Coordinate::Abs => (x, y),
+            Coordinate::Rel => (self.x.saturating_add(x), self.y.saturating_add(y)),
+        };
+        self.x = x.clamp(0, self.width);
+        self.y = y.clamp(0, self.height);
+        if self.touching {
+            self.device
+                .emit(&[
+                    abs(AbsoluteAxisCode::ABS_X, self.x),
+                    abs(AbsoluteAxisCode::ABS_Y, self.y),
+                ])
+                .map_err(emit_error)?;
+        }
+        Ok(())
+    }
+
+    fn scroll(&mut self, _length: i32, _axis: Axis) -> InputResult<()> {
+        Err(InputError::Simulate("a stylus has no scroll wheel"))
+    }
+
+    fn main_display(&self) -> InputResult<(i32, i32)> {
+        Ok((self.width, self.height))
+    }
+
+    fn location(&self) -> InputResult<(i32, i32)> {
+        Ok((self.x, self.y))
+    }
+}
+
+/// High-resolution units in one wheel click, as the kernel defines them.
+const WHEEL_HI_RES_STEP: i32 = 120;
+
+/// A mouse that only ever scrolls: every scroll line is one wheel click, reported on both
+/// the classic and the high-resolution wheel axes like a physical mouse does. The
+/// virtual pointer protocol can only send continuous axis motion, which apps that count
+/// wheel clicks (maps, zoomable canvases) treat differently.
+pub struct Wheel {
+    device: VirtualDevice,
+}
+
+impl Wheel {
+    pub fn new() -> std::io::Result<Self> {
+        // Motion axes and buttons are declared but never sent, so libinput sees a mouse.
+        let device = VirtualDevice::builder()?
+            .name("rowlink wheel")
+            .with_keys(&AttributeSet::from_iter([
+                KeyCode::BTN_LEFT,
+                KeyCode::BTN_RIGHT,
+                KeyCode::BTN_MIDDLE,
+            ]))?
+            .with_relative_axes(&AttributeSet::from_iter([
+                RelativeAxisCode::REL_X,
+                RelativeAxisCode::REL_Y,
+                RelativeAxisCode::REL_WHEEL,
+                RelativeAxisCode::REL_HWHEEL,
+                RelativeAxisCode::REL_WHEEL_HI_RES,
+                RelativeAxisCode::REL_HWHEEL_HI_RES,
+            ]))?
+            .build()?;
+        Ok(Self { device })
+    }
+}
+
+impl Mouse for Wheel {
+    fn button(&mut self, _button: Button, _direction: Direction) -> InputResult<()> {
+        Err(InputError::Simulate("the wheel device only scrolls"))
+    }
+
+    fn move_mouse(&mut self, _x: i32, _y: i32, _coordinate: Coordinate) -> InputResult<()> {
+        Err(InputError::Simulate("the wheel device only scrolls"))
+    }
+
+    /// `length` wheel clicks, positive down or right as for the virtual pointer.
+    fn scroll(&mut self, length: i32, axis: Axis) -> InputResult<()> {
+        // The kernel's vertical wheel counts up as positive.
+        let (wheel, hi_res, click) = match axis {
+            Axis::Vertical => (
+                RelativeAxisCode::REL_WHEEL,
+                RelativeAxisCode::REL_WHEEL_HI_RES,
+                -length.signum(),
+            ),
+            Axis::Horizontal => (
+                RelativeAxisCode::REL_HWHEEL,
+                RelativeAxisCode::REL_HWHEEL_HI_RES,
+                length.signum(),
+            ),
+        };
+        // One report per click, so each arrives as its own wheel event.
+        for _ in 0..length.abs() {
+            self.device
+                .emit(&[rel(wheel, click), rel(hi_res, click * WHEEL_HI_RES_STEP)])
+                .map_err(emit_error)?;
+        }
+        Ok(())
+    }
+
+    fn main_display(&self) -> InputResult<(i32, i32)> {
+        Err(InputError::Simulate("the wheel device has no display"))
+    }
+
+    fn location(&self) -> InputResult<(i32, i32)> {
+        Err(InputError::Simulate("the wheel device has no position"))
+    }
+}
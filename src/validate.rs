@@ -0,0 +1,450 @@
+use crate::{AppConfig, ConfigColor, GridTheme, bindings};
+use std::path::Path;
+
+// --- Config Validation ---
+// Catches values that would otherwise be clamped, ignored or crash later, and reports all
+// of them at once, each with the config file line it came from.
+
+/// One problem, tied to the option it was found under.
+pub struct Problem {
+    pub option: &'static str,
+    /// Text within the option to point at, e.g. one `bind` line of `bindings`.
+    pub snippet: Option<String>,
+    pub message: String,
+}
+
+pub fn problem(option: &'static str, message: String) -> Problem {
+    Problem {
+        option,
+        snippet: None,
+        message,
+    }
+}
+
+/// Every problem in one resolved config.
+pub fn check(config: &AppConfig) -> Vec<Problem> {
+    let mut problems = Vec::new();
+    check_grid(config, &mut problems);
+    check_colors(config, &mut problems);
+    check_keys(config, &mut problems);
+    check_confirm_cancel_keys(config, &mut problems);
+    check_adaptive_keys(config, &mut problems);
+    check_bindings(config, &mut problems);
+    check_cell_labels(config, &mut problems);
+    if config.websocket_port.is_some() && !cfg!(feature = "websocket") {
+        problems.push(problem(
+            "websocket_port",
+            "rowlink was built without the websocket feature; ignoring it".to_string(),
+        ));
+    }
+    problems
+}
+
+fn check_grid(config: &AppConfig, problems: &mut Vec<Problem>) {
+    let positive = [
+        ("screen_width", config.screen_width),
+        ("screen_height", config.screen_height),
+        ("font_size", config.font_size),
+        ("min_font_size", config.min_font_size),
+        ("screen_dpi", config.screen_dpi),
+    ];
+    for (option, value) in positive {
+        if value <= 0.0 {
+            problems.push(problem(option, format!("must be positive, got {}", value)));
+        }
+    }
+    for (app, factor) in &config.scroll_app_multipliers {
+        if *factor <= 0.0 {
+            problems.push(Problem {
+                option: "scroll_app_multipliers",
+                snippet: Some(format!("{}:", app)),
+                message: format!("{} must be positive, got {}", app, factor),
+            });
+        }
+    }
+    let counts = [
+        ("main_grid_rows", config.main_grid_rows),
+        ("main_grid_cols", config.main_grid_cols),
+        ("sub_rows", Some(config.sub_rows)),
+        ("sub_cols", Some(config.sub_cols)),
+        ("scroll_smooth_step", Some(config.scroll_smooth_step)),
+        ("zoom_depth", Some(config.zoom_depth as i32)),
+        ("grid_pages", Some(config.grid_pages as i32)),
+    ];
+    for (option, value) in counts {
+        if let Some(value) = value
+            && value < 1
+        {
+            problems.push(problem(
+                option,
+                format!("must be at least 1, got {}", value),
+            ));
+        }
+    }
+    if config.main_grid_size < 1.0
+        && (config.main_grid_rows.is_none() || config.main_grid_cols.is_none())
+    {
+        problems.push(problem(
+            "main_grid_size",
+            format!("must be at least 1, got {}", config.main_grid_size),
+        ));
+    }
+    if !(0.0..=1.0).contains(&config.move_smoothing.threshold) {
+        problems.push(Problem {
+            option: "move_smoothing",
+            snippet: Some("threshold:".to_string()),
+            message: format!(
+                "threshold must be between 0 and 1, got {}",
+                config.move_smoothing.threshold
+            ),
+        });
+    }
+    if let Some(cell_mm) = config.grid_auto_cell_mm
+        && cell_mm <= 0.0
+    {
+        problems.push(problem(
+            "grid_auto_cell_mm",
+            format!("must be positive, got {}", cell_mm),
+        ));
+    }
+    let too_small = |labels: &[String]| {
+        (labels.len() as i32) < config.sub_rows
+            || labels
+                .iter()
+                .any(|row| (row.chars().count() as i32) < config.sub_cols)
+    };
+    let message = format!(
+        "needs {} rows of {} keys for the sub-grid; cells without a key can't be picked",
+        config.sub_rows, config.sub_cols
+    );
+    if too_small(&config.sub_labels) {
+        problems.push(problem("sub_labels", message));
+    }
+    for (layout, labels) in &config.layout_sub_labels {
+        let fits = labels.len() as i32 == config.sub_rows
+            && labels
+                .iter()
+                .all(|row| row.chars().count() as i32 == config.sub_cols);
+        if !fits {
+            problems.push(Problem {
+                option: "layout_sub_labels",
+                snippet: Some(format!("{}:", layout)),
+                message: format!(
+                    "{} needs exactly {} rows of {} keys to match the sub-grid; extra keys are \
+                     dropped and missing ones come from sub_labels",
+                    layout, config.sub_rows, config.sub_cols
+                ),
+            });
+        }
+    }
+    if config
+        .label_alphabet
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .count()
+        < 2
+    {
+        problems.push(problem(
+            "label_alphabet",
+            "needs at least two characters; using A-Z".to_string(),
+        ));
+    }
+}
+
+fn check_colors(config: &AppConfig, problems: &mut Vec<Problem>) {
+    check_theme(&config.colors, None, problems);
+    for name in config.themes.keys() {
+        match config.resolved_themes.iter().find(|(n, _)| n == name) {
+            Some((_, theme)) => check_theme(theme, Some(name), problems),
+            None => problems.push(Problem {
+                option: "themes",
+                snippet: Some(format!("{}:", name)),
+                message: format!("theme {} doesn't parse as colors; ignoring it", name),
+            }),
+        }
+    }
+}
+
+/// Checks one set of colors; `theme` names the entry of `themes` they came from.
+fn check_theme(config: &GridTheme, theme: Option<&str>, problems: &mut Vec<Problem>) {
+    let colors: [(&'static str, ConfigColor); 10] = [
+        ("color_grid_border", config.color_grid_border),
+        ("color_main_text", config.color_main_text),
+        ("color_sub_home_row", config.color_sub_home_row),
+        ("color_sub_default", config.color_sub_default),
+        ("color_row_highlight", config.color_row_highlight),
+        ("color_text_dimmed", config.color_text_dimmed),
+        ("color_border_dimmed", config.color_border_dimmed),
+        ("color_dwell_ring", config.color_dwell_ring),
+        ("color_sub_fill", config.color_sub_fill),
+        ("color_sub_border", config.color_sub_border),
+    ];
+    for (option, color) in colors {
+        let channels = [
+            ("r", color.r),
+            ("g", color.g),
+            ("b", color.b),
+            ("a", color.a),
+        ];
+        for (channel, value) in channels {
+            if !(0.0..=1.0).contains(&value) {
+                let message = format!("{} must be between 0.0 and 1.0, got {}", channel, value);
+                problems.push(match theme {
+                    Some(name) => Problem {
+                        option: "themes",
+                        snippet: Some(option.to_string()),
+                        message: format!("theme {}: {}: {}", name, option, message),
+                    },
+                    None => problem(option, message),
+                });
+            }
+        }
+    }
+}
+
+fn check_keys(config: &AppConfig, problems: &mut Vec<Problem>) {
+    let keys: [(&'static str, &str); 11] = [
+        ("repeat_key", &config.repeat_key),
+        ("coarse_key", &config.coarse_key),
+        ("hotspot_key", &config.hotspot_key),
+        ("coordinate_key", &config.coordinate_key),
+        ("selection_key", &config.selection_key),
+        ("move_key", &config.move_key),
+        ("window_move_key", &config.window_move_key),
+        ("window_resize_key", &config.window_resize_key),
+        ("numpad_key", &config.numpad_key),
+        ("profile_key", &config.profile_key),
+        ("page_key", &config.page_key),
+    ];
+    for (index, (option, key)) in keys.iter().enumerate() {
+        if !bindings::is_key_name(key) {
+            problems.push(problem(option, format!("unknown key {:?}", key)));
+            continue;
+        }
+        if let Some((other, _)) = keys[..index]
+            .iter()
+            .find(|(_, other)| other.eq_ignore_ascii_case(key))
+        {
+            problems.push(problem(
+                option,
+                format!("{:?} is already used by {}", key, other),
+            ));
+        }
+        let mut chars = key.chars();
+        if let (Some(c), None) = (chars.next(), chars.next())
+            && config.label_chars.contains(&crate::label_char(c))
+        {
+            problems.push(problem(
+                option,
+                format!("{:?} is also a main grid label key", key),
+            ));
+        }
+    }
+}
+
+fn check_confirm_cancel_keys(config: &AppConfig, problems: &mut Vec<Problem>) {
+    if config.cancel_keys.is_empty() {
+        problems.push(problem(
+            "cancel_keys",
+            "is empty; the overlay can't be closed from the keyboard".to_string(),
+        ));
+    }
+    let lists = [
+        ("confirm_keys", &config.confirm_keys),
+        ("cancel_keys", &config.cancel_keys),
+    ];
+    for (option, keys) in lists {
+        for key in keys {
+            let mut chars = key.chars();
+            if !bindings::is_key_name(key) {
+                problems.push(problem(option, format!("unknown key {:?}", key)));
+            } else if let (Some(c), None) = (chars.next(), chars.next())
+                && config.label_chars.contains(&crate::label_char(c))
+            {
+                problems.push(problem(
+                    option,
+                    format!("{:?} is also a main grid label key", key),
+                ));
+            }
+        }
+    }
+}
+
+fn check_adaptive_keys(config: &AppConfig, problems: &mut Vec<Problem>) {
+    if config.adaptive_hints == 0 {
+        return;
+    }
+    if !config.click_history {
+        problems.push(problem(
+            "adaptive_hints",
+            "needs click_history: true to learn the most clicked targets".to_string(),
+        ));
+    }
+    let taken: String = config
+        .adaptive_hint_keys
+        .chars()
+        .filter(|c| config.label_chars.contains(&crate::label_char(*c)))
+        .collect();
+    if !taken.is_empty() {
+        problems.push(problem(
+            "adaptive_hint_keys",
+            format!("{:?} are also main grid label keys", taken),
+        ));
+    }
+}
+
+fn check_cell_labels(config: &AppConfig, problems: &mut Vec<Problem>) {
+    if config.cell_labels.is_empty() {
+        return;
+    }
+    let mut config = config.clone();
+    config.build_label_table();
+    for message in config.label_table.conflicts() {
+        problems.push(problem("cell_labels", message));
+    }
+}
+
+fn check_bindings(config: &AppConfig, problems: &mut Vec<Problem>) {
+    let mut compiled: Vec<(&String, bindings::Binding)> = Vec::new();
+    for line in &config.bindings {
+        match bindings::parse(line) {
+            Ok(binding) => compiled.push((line, binding)),
+            Err(e) => problems.push(Problem {
+                option: "bindings",
+                snippet: Some(line.clone()),
+                message: format!("{:?}: {}", line, e),
+            }),
+        }
+    }
+    for (index, (line, binding)) in compiled.iter().enumerate() {
+        let earlier = compiled[..index]
+            .iter()
+            .find(|(_, other)| other.shadows(binding));
+        let later = compiled[index + 1..]
+            .iter()
+            .find(|(_, other)| other.shadows(binding) && !binding.shadows(other));
+        if let Some((other, _)) = earlier.or(later) {
+            problems.push(Problem {
+                option: "bindings",
+                snippet: Some(line.to_string()),
+                message: format!("{:?} is unreachable because of {:?}", line, other),
+            });
+        }
+    }
+}
+
+/// Formats problems as `file:line: message` for one config file.
+pub struct Report<'a> {
+    path: &'a Path,
+    lines: Vec<&'a str>,
+}
+
+impl<'a> Report<'a> {
+    pub fn new(path: &'a Path, text: &'a str) -> Self {
+        Self {
+            path,
+            lines: text.lines().collect(),
+        }
+    }
+
+    /// A message about the whole file.
+    pub fn file(&self, message: impl std::fmt::Display) -> String {
+        format!("{}: {}", self.path.display(), message)
+    }
+
+    /// A problem in the base config, or in the overrides of `profile`.
+    pub fn problem(&self, profile: Option<&str>, problem: &Problem) -> String {
+        let (mut line, indent) = match profile {
+            Some(name) => match self.profile_line(name) {
+                Some(line) => (Some(line), self.indent(line)),
+                None => (None, 0),
+            },
+            None => (None, 0),
+        };
+        // Base options sit at the top level; profile options are indented under the
+        // profile's own key.
+        let start = line.map_or(0, |l| l + 1);
+        line = self
+            .find(start, |text, own| {
+                if profile.is_some() && own <= indent {
+                    Some(false)
+                } else if (profile.is_some() || own == 0) && is_key(text, problem.option) {
+                    Some(true)
+                } else {
+                    None
+                }
+            })
+            .or(line);
+        if let (Some(start), Some(snippet)) = (line, &problem.snippet) {
+            line = self
+                .find(start, |text, _| {
+                    text.contains(snippet.as_str()).then_some(true)
+                })
+                .or(line);
+        }
+        let prefix = match profile {
+            Some(name) => format!("profile {}: ", name),
+            None => String::new(),
+        };
+        let location = match line {
+            Some(line) => format!("{}:{}", self.path.display(), line + 1),
+            None => self.path.display().to_string(),
+        };
+        format!(
+            "{}: {}{}: {}",
+            location, prefix, problem.option, problem.message
+        )
+    }
+
+    /// A profile whose merged config doesn't deserialize.
+    pub fn profile(&self, name: &str, message: impl std::fmt::Display) -> String {
+        match self.profile_line(name) {
+            Some(line) => format!(
+                "{}:{}: profile {}: {}",
+                self.path.display(),
+                line + 1,
+                name,
+                message
+            ),
+            None => self.file(format!("profile {}: {}", name, message)),
+        }
+    }
+
+    fn profile_line(&self, name: &str) -> Option<usize> {
+        let profiles = self.find(0, |text, indent| {
+            (indent == 0 && is_key(text, "profiles")).then_some(true)
+        })?;
+        self.find(profiles + 1, |text, indent| match indent {
+            0 => Some(false),
+            _ => is_key(text, name).then_some(true),
+        })
+    }
+
+    fn indent(&self, line: usize) -> usize {
+        let text = self.lines[line];
+        text.len() - text.trim_start().len()
+    }
+
+    /// First line from `start` on that `test` accepts, given its trimmed text and
+    /// indentation. `test` returns `Some(false)` to give up, `None` to skip the line.
+    fn find(&self, start: usize, test: impl Fn(&str, usize) -> Option<bool>) -> Option<usize> {
+        for (index, text) in self.lines.iter().enumerate().skip(start) {
+            let trimmed = text.trim_start();
+            if trimmed.is_empty() || trimmed.starts_with('#') {
+                continue;
+            }
+            match test(trimmed, text.len() - trimmed.len()) {
+                Some(true) => return Some(index),
+                Some(false) => return None,
+                None => {}
+            }
+        }
+        None
+    }
+}
+
+/// Whether a trimmed YAML line starts the mapping key `key`.
+fn is_key(text: &str, key: &str) -> bool {
+    text.strip_prefix(key)
+        .is_some_and(|rest| rest.trim_start().starts_with(':'))
+}
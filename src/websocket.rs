@@ -0,0 +1,411 @@
+use crate::{Message, base_config, handle_line, ipc, paths};
+use base64::Engine;
+use base64::engine::general_purpose::{STANDARD, URL_SAFE_NO_PAD};
+use iced::futures::StreamExt;
+use iced::futures::channel::mpsc::{Sender, UnboundedSender, unbounded};
+use sha1::{Digest, Sha1};
+use std::io::Write;
+use std::os::unix::fs::OpenOptionsExt;
+use tokio::io::{
+    AsyncBufRead, AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader,
+};
+use tokio::net::{TcpListener, TcpStream};
+
+// --- WebSocket Control ---
+// The control socket's commands over a WebSocket on localhost, for tools that can't open
+// unix sockets, such as browser dashboards. Each text message is one command line, plain
+// or JSON-RPC, answered by one text message; after `subscribe`, events follow as text
+// messages too. Unlike the unix socket in the private runtime directory, loopback TCP is
+// open to every local user, so clients must pass the secret from `paths::websocket_token`,
+// which only this user can read: `ws://127.0.0.1:<port>/?token=<secret>`. Browsers must
+// also send an Origin listed in `websocket_origins`.
+
+const ACCEPT_GUID: &str = "258EAFA5-E914-47DA-95CA-C5AB0DC85B11";
+/// Longest request header and message accepted; larger ones drop the connection.
+const MAX_HEADER: usize = 8 * 1024;
+const MAX_MESSAGE: usize = 64 * 1024;
+
+const OPCODE_CONTINUATION: u8 = 0x0;
+const OPCODE_TEXT: u8 = 0x1;
+const OPCODE_CLOSE: u8 = 0x8;
+const OPCODE_PING: u8 = 0x9;
+const OPCODE_PONG: u8 = 0xA;
+
+pub async fn serve(port: u16, output: Sender<Message>) {
+    let token = match new_token() {
+        Ok(token) => token,
+        Err(e) => {
+            eprintln!(
+                "WebSocket endpoint disabled, could not write its token: {}",
+                e
+            );
+            return;
+        }
+    };
+    let listener = match TcpListener::bind(("127.0.0.1", port)).await {
+        Ok(listener) => listener,
+        Err(e) => {
+            eprintln!("WebSocket endpoint unavailable on port {}: {:?}", port, e);
+            return;
+        }
+    };
+    loop {
+        let Ok((stream, _)) = listener.accept().await else {
+            continue;
+        };
+        let (output, token) = (output.clone(), token.clone());
+        tokio::spawn(async move {
+            match connection(stream, output, &token).await {
+                Ok(()) => {}
+                Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => {}
+                Err(e) => eprintln!("WebSocket client dropped: {}", e),
+            }
+        });
+    }
+}
+
+/// A fresh secret for this run, written to `paths::websocket_token` readable only by us.
+fn new_token() -> std::io::Result<String> {
+    let mut secret = [0u8; 32];
+    getrandom::fill(&mut secret).map_err(|e| std::io::Error::other(e.to_string()))?;
+    let token = URL_SAFE_NO_PAD.encode(secret);
+    let path = paths::websocket_token();
+    // Recreated rather than overwritten, so a leftover file can't keep looser permissions.
+    let _ = std::fs::remove_file(&path);
+    std::fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .mode(0o600)
+        .open(&path)?
+        .write_all(token.as_bytes())?;
+    Ok(token)
+}
+
+async fn connection(
+    stream: TcpStream,
+    mut output: Sender<Message>,
+    token: &str,
+) -> std::io::Result<()> {
+    let (reader, mut writer) = stream.into_split();
+    let mut reader = BufReader::new(reader);
+    let Some(request) = read_request(&mut reader).await? else {
+        return Ok(());
+    };
+    let (response, accepted) = upgrade_response(&request, token, &base_config().websocket_origins);
+    writer.write_all(response.as_bytes()).await?;
+    if !accepted {
+        return Ok(());
+    }
+    // Replies, events and control frames all go out through one task, so that a
+    // subscription can stream events while requests are still being read.
+    let (frames, mut outgoing) = unbounded::<(u8, Vec<u8>)>();
+    tokio::spawn(async move {
+        while let Some((opcode, payload)) = outgoing.next().await {
+            if write_frame(&mut writer, opcode, &payload).await.is_err() {
+                break;
+            }
+        }
+    });
+    let result = async {
+        while let Some(line) = read_message(&mut reader, &frames).await? {
+            let line = line.trim();
+            if let Some((reply, events)) = ipc::subscribe(line) {
+                let _ = frames.unbounded_send((OPCODE_TEXT, reply.into_bytes()));
+                let events = events.map(|event| Ok((OPCODE_TEXT, event.into_bytes())));
+                tokio::spawn(events.forward(frames.clone()));
+                continue;
+            }
+            let reply = handle_line(line, &mut output).await;
+            let _ = frames.unbounded_send((OPCODE_TEXT, reply.into_bytes()));
+        }
+        Ok(())
+    }
+    .await;
+    // Ends the event forwarding along with the writer.
+    frames.close_channel();
+    result
+}
+
+/// The HTTP request line and headers, through the blank line; `None` past `MAX_HEADER`.
+async fn read_request(reader: &mut (impl AsyncBufRead + Unpin)) -> std::io::Result<Option<String>> {
+    let mut request = String::new();
+    while !request.ends_with("\r\n\r\n") {
+        let Some(room) = MAX_HEADER
+            .checked_sub(request.len())
+            .filter(|room| *room > 0)
+        else {
+            return Ok(None);
+        };
+        if (&mut *reader)
+            .take(room as u64)
+            .read_line(&mut request)
+            .await?
+            == 0
+        {
+            return Err(std::io::ErrorKind::UnexpectedEof.into());
+        }
+    }
+    Ok(Some(request))
+}
+
+/// The reply to an upgrade request, and whether it opens the connection.
+fn upgrade_response(request: &str, token: &str, origins: &[String]) -> (String, bool) {
+    let mut lines = request.lines();
+    let target = lines
+        .next()
+        .and_then(|line| line.split_whitespace().nth(1))
+        .unwrap_or("");
+    let header = |name: &str| {
+        lines.clone().find_map(|line| {
+            let (key, value) = line.split_once(':')?;
+            key.trim()
+                .eq_ignore_ascii_case(name)
+                .then(|| value.trim().to_string())
+        })
+    };
+    let presented = target
+        .split_once('?')
+        .map_or("", |(_, query)| query)
+        .split('&')
+        .find_map(|pair| pair.strip_prefix("token="));
+    let key = header("Sec-WebSocket-Key").filter(|_| {
+        header("Upgrade").is_some_and(|upgrade| upgrade.eq_ignore_ascii_case("websocket"))
+    });
+    let status = if header("Origin").is_some_and(|origin| !origins.contains(&origin)) {
+        "403 Forbidden"
+    } else if !presented.is_some_and(|presented| same_secret(presented, token)) {
+        "401 Unauthorized"
+    } else if let Some(key) = key {
+        let response = format!(
+            "HTTP/1.1 101 Switching Protocols\r\nUpgrade: websocket\r\nConnection: Upgrade\r\n\
+             Sec-WebSocket-Accept: {}\r\n\r\n",
+            accept_key(&key)
+        );
+        return (response, true);
+    } else {
+        "400 Bad Request"
+    };
+    let response = format!("HTTP/1.1 {}\r\nContent-Length: 0\r\n\r\n", status);
+    (response, false)
+}
+
+/// Compares two secrets in time that doesn't depend on where they differ.
+fn same_secret(a: &str, b: &str) -> bool {
+    a.len() == b.len()
+        && a.bytes()
+            .zip(b.bytes())
+            .fold(0, |diff, (x, y)| diff | (x ^ y))
+            == 0
+}
+
+/// `Sec-WebSocket-Accept` for a client's `Sec-WebSocket-Key`.
+fn accept_key(key: &str) -> String {
+    let mut sha1 = Sha1::new();
+    sha1.update(key.as_bytes());
+    sha1.update(ACCEPT_GUID.as_bytes());
+    STANDARD.encode(sha1.finalize())
+}
+
+/// The next text message, joined from its fragments; `None` once the client closes.
+/// Pongs and close replies are queued on `frames`.
+async fn read_message(
+    stream: &mut (impl AsyncRead + Unpin),
+    frames: &UnboundedSender<(u8, Vec<u8>)>,
+) -> std::io::Result<Option<String>> {
+    let mut message = Vec::new();
+    loop {
+        let mut head = [0u8; 2];
+        stream.read_exact(&mut head).await?;
+        let fin = head[0] & 0x80 != 0;
+        let opcode = head[0] & 0x0F;
+        let masked = head[1] & 0x80 != 0;
+        let len = match head[1] & 0x7F {
+            126 => stream.read_u16().await? as usize,
+            127 => usize::try_from(stream.read_u64().await?).unwrap_or(usize::MAX),
+            len => len as usize,
+        };
+        // Clients must mask every frame.
+        if !masked || message.len().saturating_add(len) > MAX_MESSAGE {
+            let _ = frames.unbounded_send((OPCODE_CLOSE, Vec::new()));
+            return Ok(None);
+        }
+        let mut mask = [0u8; 4];
+        stream.read_exact(&mut mask).await?;
+        let mut payload = vec![0u8; len];
+        stream.read_exact(&mut payload).await?;
+        for (index, byte) in payload.iter_mut().enumerate() {
+            *byte ^= mask[index % 4];
+        }
+        match opcode {
+            OPCODE_PING => {
+                let _ = frames.unbounded_send((OPCODE_PONG, payload));
+            }
+            OPCODE_PONG => {}
+            OPCODE_TEXT | OPCODE_CONTINUATION => {
+                message.extend_from_slice(&payload);
+                if fin {
+                    return Ok(Some(String::from_utf8_lossy(&message).into_owned()));
+                }
+            }
+            // Close, and binary messages, which carry no commands.
+            _ => {
+                let _ = frames.unbounded_send((OPCODE_CLOSE, Vec::new()));
+                return Ok(None);
+            }
+        }
+    }
+}
+
+async fn write_frame(
+    stream: &mut (impl AsyncWrite + Unpin),
+    opcode: u8,
+    payload: &[u8],
+) -> std::io::Result<()> {
+    let mut frame = vec![0x80 | opcode];
+    match payload.len() {
+        len @ 0..=125 => frame.push(len as u8),
+        len @ 126..=0xFFFF => {
+            frame.push(126);
+            frame.extend_from_slice(&(len as u16).to_be_bytes());
+        }
+        len => {
+            frame.push(127);
+            frame.extend_from_slice(&(len as u64).to_be_bytes());
+        }
+    }
+    frame.extend_from_slice(payload);
+    stream.write_all(&frame).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use iced::futures::channel::mpsc::UnboundedReceiver;
+    use iced::futures::executor::block_on;
+
+    const MASK: [u8; 4] = [0x37, 0xFA, 0x21, 0x3D];
+
+    fn frame(fin: bool, opcode: u8, payload: &[u8]) -> Vec<u8> {
+        let mut frame = vec![if fin { 0x80 | opcode } else { opcode }];
+        match payload.len() {
+            len @ 0..=125 => frame.push(0x80 | len as u8),
+            len => {
+                frame.push(0x80 | 126);
+                frame.extend_from_slice(&(len as u16).to_be_bytes());
+            }
+        }
+        frame.extend_from_slice(&MASK);
+        frame.extend(payload.iter().enumerate().map(|(i, b)| b ^ MASK[i % 4]));
+        frame
+    }
+
+    fn read(bytes: &[u8]) -> (Option<String>, UnboundedReceiver<(u8, Vec<u8>)>) {
+        let (frames, queued) = unbounded();
+        let mut stream = bytes;
+        let message = block_on(read_message(&mut stream, &frames)).unwrap();
+        (message, queued)
+    }
+
+    #[test]
+    fn accept_key_matches_rfc_example() {
+        assert_eq!(
+            accept_key("dGhlIHNhbXBsZSBub25jZQ=="),
+            "s3pPLMBiTxaQ9kYGzzhZRbK+xOo="
+        );
+    }
+
+    fn upgrade(target: &str, origin: Option<&str>) -> (String, bool) {
+        let origin = origin.map_or(String::new(), |o| format!("Origin: {}\r\n", o));
+        let request = format!(
+            "GET {} HTTP/1.1\r\nHost: 127.0.0.1\r\nUpgrade: websocket\r\n{}\
+             Sec-WebSocket-Key: dGhlIHNhbXBsZSBub25jZQ==\r\n\r\n",
+            target, origin
+        );
+        upgrade_response(&request, "secret", &["http://localhost:8080".to_string()])
+    }
+
+    #[test]
+    fn upgrades_only_with_the_token_and_a_listed_origin() {
+        let (response, accepted) = upgrade("/?token=secret", None);
+        assert!(accepted);
+        assert!(response.starts_with("HTTP/1.1 101 "));
+        assert!(response.contains("Sec-WebSocket-Accept: s3pPLMBiTxaQ9kYGzzhZRbK+xOo=\r\n"));
+        assert!(upgrade("/?v=1&token=secret", Some("http://localhost:8080")).1);
+
+        for target in ["/", "/?token=", "/?token=secreT", "/?token=secret2"] {
+            let (response, accepted) = upgrade(target, None);
+            assert!(!accepted);
+            assert!(response.starts_with("HTTP/1.1 401 "), "{}", target);
+        }
+        let (response, accepted) = upgrade("/?token=secret", Some("https://example.com"));
+        assert!(!accepted);
+        assert!(response.starts_with("HTTP/1.1 403 "));
+    }
+
+    #[test]
+    fn reads_the_request_up_to_the_blank_line() {
+        let mut stream: &[u8] = b"GET / HTTP/1.1\r\nHost: x\r\n\r\n\x81\x85";
+        let request = block_on(read_request(&mut stream)).unwrap();
+        assert_eq!(
+            request.as_deref(),
+            Some("GET / HTTP/1.1\r\nHost: x\r\n\r\n")
+        );
+        assert_eq!(stream, b"\x81\x85");
+
+        let endless = vec![b'a'; MAX_HEADER + 10];
+        assert_eq!(
+            block_on(read_request(&mut endless.as_slice())).unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn unmasks_a_single_frame() {
+        // RFC 6455 section 5.7: a masked "Hello".
+        let bytes = [
+            0x81, 0x85, 0x37, 0xFA, 0x21, 0x3D, 0x7F, 0x9F, 0x4D, 0x51, 0x58,
+        ];
+        assert_eq!(frame(true, OPCODE_TEXT, b"Hello"), bytes);
+        let (message, mut queued) = read(&bytes);
+        assert_eq!(message.as_deref(), Some("Hello"));
+        assert!(queued.try_recv().is_err());
+    }
+
+    #[test]
+    fn joins_fragments_and_answers_pings_between_them() {
+        let mut bytes = frame(false, OPCODE_TEXT, b"mark ");
+        bytes.extend(frame(true, OPCODE_PING, b"hi"));
+        bytes.extend(frame(false, OPCODE_CONTINUATION, &[b'x'; 200]));
+        bytes.extend(frame(true, OPCODE_CONTINUATION, b" end"));
+        let (message, mut queued) = read(&bytes);
+        assert_eq!(message, Some(format!("mark {} end", "x".repeat(200))));
+        assert_eq!(queued.try_recv().ok(), Some((OPCODE_PONG, b"hi".to_vec())));
+    }
+
+    #[test]
+    fn closes_on_unmasked_frames() {
+        let (message, mut queued) = read(&[0x81, 0x02, b'h', b'i']);
+        assert_eq!(message, None);
+        assert_eq!(queued.try_recv().ok(), Some((OPCODE_CLOSE, Vec::new())));
+    }
+
+    #[test]
+    fn closes_on_oversize_messages_before_reading_them() {
+        // Only the header: the payload must not be waited for.
+        let mut bytes = vec![0x81, 0x80 | 127];
+        bytes.extend_from_slice(&(MAX_MESSAGE as u64 + 1).to_be_bytes());
+        let (message, mut queued) = read(&bytes);
+        assert_eq!(message, None);
+        assert_eq!(queued.try_recv().ok(), Some((OPCODE_CLOSE, Vec::new())));
+
+        // Fragments that only add up to too much.
+        let chunk = vec![b'x'; 0xFFFF];
+        let mut bytes = Vec::new();
+        for _ in 0..MAX_MESSAGE / chunk.len() + 1 {
+            bytes.extend(frame(false, OPCODE_TEXT, &chunk));
+        }
+        let (message, mut queued) = read(&bytes);
+        assert_eq!(message, None);
+        assert_eq!(queued.try_recv().ok(), Some((OPCODE_CLOSE, Vec::new())));
+    }
+}
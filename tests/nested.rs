@@ -0,0 +1,13 @@
+// End-to-end run of scripts/nested-test.sh in a headless, nested sway against this build.
+// Needs sway, wev and wtype, so it only runs on request: `cargo test -- --ignored`.
+
+#[test]
+#[ignore = "needs sway, wev and wtype"]
+fn nested_sway() {
+    let status = std::process::Command::new("sh")
+        .arg(concat!(env!("CARGO_MANIFEST_DIR"), "/scripts/nested-test.sh"))
+        .arg(env!("CARGO_BIN_EXE_rowlink"))
+        .status()
+        .expect("sh runs");
+    assert!(status.success(), "nested-test.sh failed");
+}